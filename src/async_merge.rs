@@ -0,0 +1,1048 @@
+//! Asynchronous counterparts of the mergers, built on top of `tokio`.
+use crate::csv::{self, Columns, Csv, HeaderScan};
+#[cfg(feature = "encoding_rs")]
+use crate::encoding::Decode;
+use crate::error::{ErrorKind, Result};
+use crate::index::{IndexPlacement, Tracker};
+use crate::lines::LineFilter;
+use crate::merge::{Bom, NewlineStyle, Pad, PartPos, PartRange, RsMerger, Skip, Terminator};
+use crate::normalize::Detector;
+use crate::report::MergeReport;
+use crate::rewrite::{Pipeline, Rewrite};
+use crate::stream::Buffered;
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "encoding_rs")]
+use encoding_rs::Encoding;
+use tokio::fs::{self, File};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// An asynchronous counterpart of [`RsMerger`] that merges sources implementing [`AsyncRead`]
+/// and [`AsyncSeek`] into an [`AsyncWrite`].
+///
+/// Given the same configurations and sources, `AsyncRsMerger` produces exactly the same bytes as
+/// [`RsMerger`].
+///
+/// This type is only available if the `tokio` feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use admerge::{AsyncRsMerger, Skip, Pad, Newline, Result};
+/// use std::io::Cursor;
+///
+/// # fn main() -> Result<()> {
+/// # tokio::runtime::Builder::new_current_thread().build()?.block_on(async {
+/// // Cursor implements `AsyncRead` and `AsyncSeek`.
+/// let c1 = Cursor::new(" record 1\n date created: 2000/01/01\n");
+/// let c2 = Cursor::new(" record 2\n date created: 2000/01/01\n");
+/// let c3 = Cursor::new(" record 3\n date created: 2000/01/01\n");
+/// let mut buf = Vec::new();
+///
+/// // Configures merger.
+/// let mut merger = AsyncRsMerger::new();
+/// merger.skip_tail(Skip::LinesOnce(1));
+/// merger.pad_with(Pad::Before(b"header\n"));
+/// merger.force_ending_newline(Newline::Lf);
+///
+/// // Merges sources into one.
+/// merger.merge_sources_into(vec![c1, c2, c3], &mut buf).await?;
+/// assert_eq!(
+///     std::str::from_utf8(&buf).unwrap(),
+///     "header\n record 1\n record 2\n record 3\n date created: 2000/01/01\n"
+/// );
+/// # Ok(())
+/// # })
+/// # }
+/// ```
+///
+/// [`AsyncRead`]: tokio::io::AsyncRead
+/// [`AsyncSeek`]: tokio::io::AsyncSeek
+/// [`AsyncWrite`]: tokio::io::AsyncWrite
+#[derive(Clone, Debug, Default)]
+pub struct AsyncRsMerger<'a>(RsMerger<'a>);
+
+impl<'a> AsyncRsMerger<'a> {
+    /// Creates a new `AsyncRsMerger` builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Configures this merger to skip partial of contents from the head of each source.
+    ///
+    /// See [`RsMerger::skip_head`].
+    pub fn skip_head(&mut self, skip: Skip<'a>) -> &mut Self {
+        self.0.skip_head(skip);
+        self
+    }
+
+    /// Configures this merger to skip partial of contents from the tail of each source.
+    ///
+    /// See [`RsMerger::skip_tail`].
+    pub fn skip_tail(&mut self, skip: Skip<'a>) -> &mut Self {
+        self.0.skip_tail(skip);
+        self
+    }
+
+    /// Configures this merger to fill some padding before, between or after the given sources.
+    ///
+    /// See [`RsMerger::pad_with`].
+    pub fn pad_with(&mut self, padding: Pad<'a>) -> &mut Self {
+        self.0.pad_with(padding);
+        self
+    }
+
+    /// Configures this merger to force the presence of ending newline after each source.
+    ///
+    /// See [`RsMerger::force_ending_newline`].
+    pub fn force_ending_newline(&mut self, newline: impl Into<NewlineStyle>) -> &mut Self {
+        self.0.force_ending_newline(newline);
+        self
+    }
+
+    /// Configures the line terminators that end lines of each source.
+    ///
+    /// See [`RsMerger::line_terminator`].
+    pub fn line_terminator(&mut self, terminator: Terminator<'a>) -> &mut Self {
+        self.0.line_terminator(terminator);
+        self
+    }
+
+    /// Configures this merger to check that the contents kept from each source are made of
+    /// whole records of the given size.
+    ///
+    /// See [`RsMerger::check_records`].
+    pub fn check_records(&mut self, size: usize) -> &mut Self {
+        self.0.check_records(size);
+        self
+    }
+
+    /// Configures this merger to treat sources as CSV in the given format, keeping the header of
+    /// the first source only.
+    ///
+    /// See [`RsMerger::csv_headers`].
+    pub fn csv_headers(&mut self, csv: Csv) -> &mut Self {
+        self.0.csv_headers(csv);
+        self
+    }
+
+    /// Configures this merger to rewrite the records of CSV sources so that they hold the given
+    /// columns.
+    ///
+    /// See [`RsMerger::csv_columns`].
+    pub fn csv_columns(&mut self, columns: Columns<'a>) -> &mut Self {
+        self.0.csv_columns(columns);
+        self
+    }
+
+    /// Sets the field that fills columns a CSV source lacks.
+    ///
+    /// See [`RsMerger::missing_fields`].
+    pub fn missing_fields(&mut self, field: &'a [u8]) -> &mut Self {
+        self.0.missing_fields(field);
+        self
+    }
+
+    /// Configures this merger to start each source at a multiple of the given alignment.
+    ///
+    /// See [`RsMerger::align_parts`].
+    pub fn align_parts(&mut self, alignment: u64) -> &mut Self {
+        self.0.align_parts(alignment);
+        self
+    }
+
+    /// Configures this merger to end the merged output at a multiple of the given alignment.
+    ///
+    /// See [`RsMerger::align_output`].
+    pub fn align_output(&mut self, alignment: u64) -> &mut Self {
+        self.0.align_output(alignment);
+        self
+    }
+
+    /// Configures this merger to fill the merged output up to the given size.
+    ///
+    /// See [`RsMerger::image_size`].
+    pub fn image_size(&mut self, size: u64) -> &mut Self {
+        self.0.image_size(size);
+        self
+    }
+
+    /// Configures the byte written to align sources and to fill images.
+    ///
+    /// See [`RsMerger::fill_byte`].
+    pub fn fill_byte(&mut self, fill: u8) -> &mut Self {
+        self.0.fill_byte(fill);
+        self
+    }
+
+    /// Configures this merger to work out an index of the merged output.
+    ///
+    /// See [`RsMerger::emit_index`].
+    pub fn emit_index(&mut self, placement: IndexPlacement) -> &mut Self {
+        self.0.emit_index(placement);
+        self
+    }
+
+    /// Configures this merger to rewrite every line ending into the given newline.
+    ///
+    /// See [`RsMerger::normalize_newlines`].
+    pub fn normalize_newlines(&mut self, newline: impl Into<NewlineStyle>) -> &mut Self {
+        self.0.normalize_newlines(newline);
+        self
+    }
+
+    /// Configures this merger to write the given byte order mark at the top of the output.
+    ///
+    /// See [`RsMerger::emit_bom`].
+    pub fn emit_bom(&mut self, bom: Bom) -> &mut Self {
+        self.0.emit_bom(bom);
+        self
+    }
+
+    /// Configures this merger to keep or drop lines of each source.
+    ///
+    /// See [`RsMerger::filter_lines`].
+    pub fn filter_lines(&mut self, filter: LineFilter<'a>) -> &mut Self {
+        self.0.filter_lines(filter);
+        self
+    }
+
+    /// Configures this merger to decode sources into UTF-8.
+    ///
+    /// See [`RsMerger::decode_sources`].
+    #[cfg(feature = "encoding_rs")]
+    pub fn decode_sources(&mut self, decode: Decode<'a>) -> &mut Self {
+        self.0.decode_sources(decode);
+        self
+    }
+
+    /// Configures this merger to encode the merged output into the given encoding.
+    ///
+    /// See [`RsMerger::encode_output`].
+    #[cfg(feature = "encoding_rs")]
+    pub fn encode_output(&mut self, encoding: &'static Encoding) -> &mut Self {
+        self.0.encode_output(encoding);
+        self
+    }
+
+    /// Configures the maximum number of bytes this merger may hold in memory while buffering a
+    /// source.
+    ///
+    /// See [`RsMerger::buffer_limit`].
+    pub fn buffer_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.buffer_limit(limit);
+        self
+    }
+
+    /// Configures this merger to spill buffered sources into an anonymous temporary file once
+    /// they exceed the buffer limit, instead of failing with [`ErrorKind::BufferExceeded`].
+    ///
+    /// On a multi-threaded runtime, work on spilled buffers runs in
+    /// [`tokio::task::block_in_place`], so that other tasks are not held up by the disk. On a
+    /// current-thread runtime, it blocks the runtime while it runs.
+    ///
+    /// See [`RsMerger::spill_to_disk`].
+    ///
+    /// This method is only available if the `tempfile` feature is enabled.
+    #[cfg(feature = "tempfile")]
+    pub fn spill_to_disk(&mut self, spill: bool) -> &mut Self {
+        self.0.spill_to_disk(spill);
+        self
+    }
+
+    /// Merges the given sources into the given writer according to the given configurations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given source vector is
+    /// empty;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given sources;
+    ///
+    /// Returns an error variant of [`ErrorKind::BufferExceeded`] if a buffered source exceeds
+    /// its limit and spilling to disk is disabled;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    pub async fn merge_sources_into<RS, W>(&self, sources: Vec<RS>, writer: &mut W) -> Result<()>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        self.merge_sources_into_with_report(sources, writer)
+            .await
+            .map(|_| ())
+    }
+
+    /// Does the same as [`merge_sources_into`], returning a [`MergeReport`] telling where each
+    /// source landed in the output.
+    ///
+    /// [`merge_sources_into`]: AsyncRsMerger::merge_sources_into
+    pub async fn merge_sources_into_with_report<RS, W>(
+        &self,
+        mut sources: Vec<RS>,
+        writer: &mut W,
+    ) -> Result<MergeReport>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let len = sources.len();
+        if len == 0 {
+            return Err(ErrorKind::NothingPassed);
+        }
+
+        let mut merger = self.resolved(&mut sources[0]).await?;
+        if let Some(csv) = self.0.union_csv() {
+            let mut columns = Vec::new();
+            for source in sources.iter_mut() {
+                csv::unite(&mut columns, self.read_header(csv, source).await?);
+            }
+            merger.to_mut().0.set_union(columns);
+        }
+
+        let mut sink = Sink::new(writer, &merger.0);
+        let mut report = MergeReport::default();
+        for (i, source) in sources.iter_mut().enumerate() {
+            let pos = PartPos::of(i, len);
+            merger
+                .write_part(source, &mut sink, &mut report, pos, None)
+                .await?;
+        }
+        merger.write_end(&mut sink, &mut report).await?;
+
+        Ok(report)
+    }
+
+    // Writes one part, along with the fill bytes before it.
+    async fn write_part<RS, W>(
+        &self,
+        reader: &mut RS,
+        sink: &mut Sink<'_, W>,
+        report: &mut MergeReport,
+        pos: PartPos,
+        path: Option<PathBuf>,
+    ) -> Result<()>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let index = report.parts().len();
+        sink.write_fill(self.0.fill(), self.0.fill_before(report.len()))
+            .await?;
+        sink.tracker.begin(&self.0, pos)?;
+        let mut range = self.write_contents(reader, sink, pos, index).await?;
+        sink.tracker.end(&mut range);
+        report.push(&self.0, range, pos, path)
+    }
+
+    // Writes the fill bytes after the last part and the index footer, if any, then flushes.
+    async fn write_end<W>(&self, sink: &mut Sink<'_, W>, report: &mut MergeReport) -> Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        sink.write_fill(self.0.fill(), report.finish(&self.0)?)
+            .await?;
+        self.0.write_footer(&mut sink.tracker, report)?;
+        sink.flush().await?;
+        sink.writer.flush().await?;
+        Ok(())
+    }
+
+    // Writes the contents (entire or partial) of one part into the sink.
+    async fn write_contents<RS, W>(
+        &self,
+        reader: &mut RS,
+        sink: &mut Sink<'_, W>,
+        pos: PartPos,
+        index: usize,
+    ) -> Result<PartRange>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        if self.0.rewrites() {
+            return self.write_rewritten(reader, sink, pos, index).await;
+        }
+
+        // Paddings and newlines are tiny, so they are rendered synchronously.
+        self.0.write_bom(&mut sink.tracker, pos)?;
+        sink.flush().await?;
+
+        // Works out the range to copy, reading the contents only if the skips need them.
+        let mut range = if self.0.needs_buffering() {
+            // Custom skips may look at any byte, so they are applied to a buffered copy.
+            let mut buffered = self.buffer_source(reader).await?;
+            self.blocking(|| self.0.part_range(&mut buffered, pos))?
+        } else {
+            self.scan_range(reader, pos).await?
+        };
+
+        // Nothing is written for a part whose header does not match, as the sync mergers do.
+        if range.mismatch {
+            return Ok(range);
+        }
+
+        self.0.write_padding_before(&mut sink.tracker, pos)?;
+        sink.flush().await?;
+
+        if range.end > range.start {
+            reader.seek(SeekFrom::Start(range.start)).await?;
+            let mut take = reader.take(range.end - range.start);
+            let mut sieve = self.0.sieve(range.start);
+            let mut chunk = vec![0; 8 * 1024];
+            let mut buf = Vec::new();
+            loop {
+                let n = take.read(&mut chunk).await?;
+                if n == 0 {
+                    break;
+                }
+                match &mut sieve {
+                    // Kept lines are collected synchronously, one chunk at a time.
+                    Some(sieve) => {
+                        sieve.feed(&chunk[..n], &mut buf)?;
+                        sink.write_all(&buf).await?;
+                        buf.clear();
+                    }
+                    None => sink.write_all(&chunk[..n]).await?,
+                }
+            }
+            if take.limit() != 0 {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            if let Some(mut sieve) = sieve {
+                sieve.finish(&mut range, &mut buf)?;
+                sink.write_all(&buf).await?;
+            }
+        } else if self.0.filters_lines() {
+            range.endn = false;
+        }
+
+        self.0.write_ending_newline(&mut sink.tracker, range.endn)?;
+        self.0.write_padding_after(&mut sink.tracker, pos)?;
+        sink.flush().await?;
+
+        Ok(range)
+    }
+
+    // Rewrites one part chunk by chunk, writing out the output of each chunk as it goes.
+    async fn write_rewritten<RS, W>(
+        &self,
+        reader: &mut RS,
+        sink: &mut Sink<'_, W>,
+        pos: PartPos,
+        index: usize,
+    ) -> Result<PartRange>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        // Custom skips may look at any byte, so they are applied to a buffered copy.
+        if self.0.needs_buffering() {
+            return self.write_buffered(reader, sink, pos, index).await;
+        }
+
+        self.0.write_bom(&mut sink.tracker, pos)?;
+        sink.flush().await?;
+        let rewriter = self.0.rewriter(pos, index);
+
+        #[cfg(feature = "encoding_rs")]
+        if self.0.transcodes() {
+            let mut writer = self.0.encoder(Vec::new());
+            let range = self
+                .write_stream_part(reader, rewriter, &mut writer, sink, pos)
+                .await?;
+            let total = writer.finish()?;
+            sink.write_all(writer.as_mut()).await?;
+            return self.0.encoded_range(range, total, pos);
+        }
+
+        self.write_stream_part(reader, rewriter, &mut Vec::new(), sink, pos)
+            .await
+    }
+
+    // Writes one part, apart from the BOM, rewriting the source chunk by chunk. The output is
+    // rendered into `writer`, then moved to the sink after each chunk.
+    async fn write_stream_part<RS, O, W>(
+        &self,
+        reader: &mut RS,
+        mut rewriter: Pipeline<'_>,
+        writer: &mut O,
+        sink: &mut Sink<'_, W>,
+        pos: PartPos,
+    ) -> Result<PartRange>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+        O: Write + AsMut<Vec<u8>>,
+        W: AsyncWrite + Unpin,
+    {
+        reader.seek(SeekFrom::Start(0)).await?;
+        let mut part = self.0.stream_part(writer, pos)?;
+        let mut chunk = vec![0; 8 * 1024];
+        let mut text = Vec::new();
+        loop {
+            let n = reader.read(&mut chunk).await?;
+            if n == 0 {
+                break;
+            }
+            // Held back bytes may be spilled to disk.
+            self.blocking(|| {
+                rewriter.push(&chunk[..n], &mut text)?;
+                part.feed(&text, writer)
+            })?;
+            text.clear();
+            sink.write_all(writer.as_mut()).await?;
+            writer.as_mut().clear();
+        }
+
+        let range = self.blocking(|| {
+            rewriter.finish(&mut text)?;
+            part.feed(&text, writer)?;
+            part.finish(writer)
+        })?;
+        sink.write_all(writer.as_mut()).await?;
+        writer.as_mut().clear();
+        Ok(range)
+    }
+
+    // Rewrites one part from a buffered copy of the source, then copies the output over.
+    async fn write_buffered<RS, W>(
+        &self,
+        reader: &mut RS,
+        sink: &mut Sink<'_, W>,
+        pos: PartPos,
+        index: usize,
+    ) -> Result<PartRange>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut source = self.buffer_source(reader).await?;
+        let mut output = self.0.buffered();
+        let range = self.blocking(|| {
+            self.0
+                .write_stream_contents(&mut source, &mut output, pos, index)
+                .map_err(crate::stream::recover)
+        })?;
+
+        self.blocking(|| output.seek(SeekFrom::Start(0)))?;
+        let mut chunk = vec![0; 8 * 1024];
+        loop {
+            let n = self.blocking(|| output.read(&mut chunk))?;
+            if n == 0 {
+                break;
+            }
+            sink.write_all(&chunk[..n]).await?;
+        }
+
+        Ok(range)
+    }
+
+    // Resolves `NewlineStyle::Auto` and reads the CSV header from the given first source, which is
+    // rewound afterwards.
+    async fn resolved<RS>(&self, first: &mut RS) -> Result<Cow<'_, AsyncRsMerger<'a>>>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+    {
+        self.0.check_csv_skips()?;
+        if !self.0.resolves() {
+            return Ok(Cow::Borrowed(self));
+        }
+
+        let mut merger = self.clone();
+        if self.0.detects_newline() {
+            let mut detector = Detector::default();
+            first.seek(SeekFrom::Start(0)).await?;
+            let mut chunk = vec![0; 8 * 1024];
+            loop {
+                let n = first.read(&mut chunk).await?;
+                if n == 0 {
+                    break;
+                }
+                detector.push(&chunk[..n]);
+            }
+            merger = AsyncRsMerger(self.0.with_newline(detector.newline()));
+        }
+        if let Some(csv) = self.0.header_csv() {
+            let header = self.read_header(csv, first).await?;
+            merger.0.set_csv_header(header.fields);
+        }
+        first.seek(SeekFrom::Start(0)).await?;
+        Ok(Cow::Owned(merger))
+    }
+
+    // Reads the CSV header of the given source from its start.
+    async fn read_header<RS>(&self, csv: Csv, reader: &mut RS) -> Result<csv::Header>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+    {
+        let mut scan = HeaderScan::new(csv, self.0.limit());
+        reader.seek(SeekFrom::Start(0)).await?;
+        let mut chunk = vec![0; 8 * 1024];
+        loop {
+            let n = reader.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(scan.finish());
+            }
+            if let Some(header) = scan.feed(&chunk[..n])? {
+                return Ok(header);
+            }
+        }
+    }
+
+    // Reads the whole source into a buffer, rewinding the buffer afterwards.
+    async fn buffer_source<RS>(&self, reader: &mut RS) -> Result<Buffered>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+    {
+        let mut buffered = self.0.buffered();
+        reader.seek(SeekFrom::Start(0)).await?;
+        let mut chunk = vec![0; 8 * 1024];
+        loop {
+            let n = reader.read(&mut chunk).await?;
+            if n == 0 {
+                break;
+            }
+            self.blocking(|| buffered.push(&chunk[..n]))?;
+        }
+        self.blocking(|| buffered.seek(SeekFrom::Start(0)))?;
+        Ok(buffered)
+    }
+
+    // Runs synchronous work on buffers, which may be spilled to disk, in `block_in_place` if the
+    // runtime allows it, so that other tasks are not held up meanwhile.
+    fn blocking<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        match Handle::try_current() {
+            Ok(handle)
+                if self.0.spills() && handle.runtime_flavor() == RuntimeFlavor::MultiThread =>
+            {
+                tokio::task::block_in_place(f)
+            }
+            _ => f(),
+        }
+    }
+
+    // Works out the range to copy with a scanner, reading the contents only if the skips need them.
+    async fn scan_range<RS>(&self, reader: &mut RS, pos: PartPos) -> Result<PartRange>
+    where
+        RS: AsyncRead + AsyncSeek + Unpin,
+    {
+        let mut scanner = self.0.scanner(pos);
+        if scanner.needs_contents() {
+            reader.seek(SeekFrom::Start(0)).await?;
+            let mut chunk = vec![0; 8 * 1024];
+            loop {
+                let n = reader.read(&mut chunk).await?;
+                if n == 0 {
+                    break;
+                }
+                scanner.feed(&chunk[..n])?;
+            }
+            return scanner.finish();
+        }
+
+        let len = reader.seek(SeekFrom::End(0)).await?;
+        let terminator = self.0.terminator();
+        let width = len.min(terminator.width() as u64);
+        let mut last = vec![0; width as usize];
+        if width > 0 {
+            reader.seek(SeekFrom::End(-(width as i64))).await?;
+            reader.read_exact(&mut last).await?;
+        }
+        scanner.finish_with(len, terminator.ends(&last))
+    }
+}
+
+/// An asynchronous counterpart of [`FileMerger`], working with [`tokio::fs::File`]s.
+///
+/// This type is only available if the `tokio` feature is enabled.
+///
+/// [`FileMerger`]: crate::FileMerger
+#[derive(Clone, Debug, Default)]
+pub struct AsyncFileMerger<'a>(AsyncRsMerger<'a>);
+
+impl<'a> AsyncFileMerger<'a> {
+    /// Creates a new `AsyncFileMerger` builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Configures this merger to skip partial of contents from the head of each file.
+    pub fn skip_head(&mut self, skip: Skip<'a>) -> &mut Self {
+        self.0.skip_head(skip);
+        self
+    }
+
+    /// Configures this merger to skip partial of contents from the tail of each file.
+    pub fn skip_tail(&mut self, skip: Skip<'a>) -> &mut Self {
+        self.0.skip_tail(skip);
+        self
+    }
+
+    /// Configures this merger to fill some padding before, between or after the file contents.
+    pub fn pad_with(&mut self, padding: Pad<'a>) -> &mut Self {
+        self.0.pad_with(padding);
+        self
+    }
+
+    /// Configures this merger to force the presence of ending newline after each file.
+    pub fn force_ending_newline(&mut self, newline: impl Into<NewlineStyle>) -> &mut Self {
+        self.0.force_ending_newline(newline);
+        self
+    }
+
+    /// Configures the line terminators that end lines of each file.
+    pub fn line_terminator(&mut self, terminator: Terminator<'a>) -> &mut Self {
+        self.0.line_terminator(terminator);
+        self
+    }
+
+    /// Configures this merger to check that the contents kept from each file are made of whole
+    /// records of the given size.
+    pub fn check_records(&mut self, size: usize) -> &mut Self {
+        self.0.check_records(size);
+        self
+    }
+
+    /// Configures this merger to treat files as CSV in the given format, keeping the header of the
+    /// first file only.
+    pub fn csv_headers(&mut self, csv: Csv) -> &mut Self {
+        self.0.csv_headers(csv);
+        self
+    }
+
+    /// Configures this merger to rewrite the records of CSV files so that they hold the given
+    /// columns.
+    pub fn csv_columns(&mut self, columns: Columns<'a>) -> &mut Self {
+        self.0.csv_columns(columns);
+        self
+    }
+
+    /// Sets the field that fills columns a CSV file lacks.
+    pub fn missing_fields(&mut self, field: &'a [u8]) -> &mut Self {
+        self.0.missing_fields(field);
+        self
+    }
+
+    /// Configures this merger to start each file at a multiple of the given alignment.
+    pub fn align_parts(&mut self, alignment: u64) -> &mut Self {
+        self.0.align_parts(alignment);
+        self
+    }
+
+    /// Configures this merger to end the merged output at a multiple of the given alignment.
+    pub fn align_output(&mut self, alignment: u64) -> &mut Self {
+        self.0.align_output(alignment);
+        self
+    }
+
+    /// Configures this merger to fill the merged output up to the given size.
+    pub fn image_size(&mut self, size: u64) -> &mut Self {
+        self.0.image_size(size);
+        self
+    }
+
+    /// Configures the byte written to align files and to fill images.
+    pub fn fill_byte(&mut self, fill: u8) -> &mut Self {
+        self.0.fill_byte(fill);
+        self
+    }
+
+    /// Configures this merger to work out an index of the merged output.
+    pub fn emit_index(&mut self, placement: IndexPlacement) -> &mut Self {
+        self.0.emit_index(placement);
+        self
+    }
+
+    /// Configures this merger to rewrite every line ending into the given newline.
+    pub fn normalize_newlines(&mut self, newline: impl Into<NewlineStyle>) -> &mut Self {
+        self.0.normalize_newlines(newline);
+        self
+    }
+
+    /// Configures this merger to write the given byte order mark at the top of the output.
+    pub fn emit_bom(&mut self, bom: Bom) -> &mut Self {
+        self.0.emit_bom(bom);
+        self
+    }
+
+    /// Configures this merger to keep or drop lines of each file.
+    pub fn filter_lines(&mut self, filter: LineFilter<'a>) -> &mut Self {
+        self.0.filter_lines(filter);
+        self
+    }
+
+    /// Configures this merger to decode files into UTF-8.
+    #[cfg(feature = "encoding_rs")]
+    pub fn decode_sources(&mut self, decode: Decode<'a>) -> &mut Self {
+        self.0.decode_sources(decode);
+        self
+    }
+
+    /// Configures this merger to encode the merged output into the given encoding.
+    #[cfg(feature = "encoding_rs")]
+    pub fn encode_output(&mut self, encoding: &'static Encoding) -> &mut Self {
+        self.0.encode_output(encoding);
+        self
+    }
+
+    /// Configures the maximum number of bytes this merger may hold in memory while buffering a
+    /// file.
+    pub fn buffer_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.buffer_limit(limit);
+        self
+    }
+
+    /// Configures this merger to spill buffered files into an anonymous temporary file once they
+    /// exceed the buffer limit.
+    ///
+    /// This method is only available if the `tempfile` feature is enabled.
+    #[cfg(feature = "tempfile")]
+    pub fn spill_to_disk(&mut self, spill: bool) -> &mut Self {
+        self.0.spill_to_disk(spill);
+        self
+    }
+
+    /// Configures the maximum number of files that [`with_paths`] and its variants may keep open
+    /// at the same time. Defaults to 1.
+    ///
+    /// See [`FileMerger::max_open_files`].
+    ///
+    /// [`with_paths`]: AsyncFileMerger::with_paths
+    /// [`FileMerger::max_open_files`]: crate::FileMerger::max_open_files
+    pub fn max_open_files(&mut self, limit: usize) -> &mut Self {
+        self.0 .0.set_max_open_files(limit);
+        self
+    }
+
+    /// Opens the given file paths and merges file contents into the given writer according to the
+    /// given configrations.
+    ///
+    /// See [`FileMerger::with_paths`]. Every path is checked before anything is written, but files
+    /// are only opened when they are about to be written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given path vector is
+    /// empty;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidPath`] if the given paths contain invalid
+    /// path.
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given sources;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    ///
+    /// [`FileMerger::with_paths`]: crate::FileMerger::with_paths
+    pub async fn with_paths<P, W>(&self, paths: Vec<P>, writer: &mut W) -> Result<()>
+    where
+        P: AsRef<Path>,
+        W: AsyncWrite + Unpin,
+    {
+        self.with_paths_with_report(paths, writer).await.map(|_| ())
+    }
+
+    /// Does the same as [`with_paths`], returning a [`MergeReport`] telling where each file
+    /// landed in the output.
+    ///
+    /// [`with_paths`]: AsyncFileMerger::with_paths
+    pub async fn with_paths_with_report<P, W>(
+        &self,
+        paths: Vec<P>,
+        writer: &mut W,
+    ) -> Result<MergeReport>
+    where
+        P: AsRef<Path>,
+        W: AsyncWrite + Unpin,
+    {
+        let len = paths.len();
+        if len == 0 {
+            return Err(ErrorKind::NothingPassed);
+        }
+        for (i, p) in paths.iter().enumerate() {
+            if !is_file(p.as_ref()).await {
+                return Err(ErrorKind::InvalidPath(i));
+            }
+        }
+
+        // Reads the header of every file up front if their columns are united.
+        let mut union = None;
+        if let Some(csv) = self.0 .0.union_csv() {
+            let mut columns = Vec::new();
+            for p in &paths {
+                let mut file = File::open(p).await?;
+                csv::unite(&mut columns, self.0.read_header(csv, &mut file).await?);
+            }
+            union = Some(columns);
+        }
+
+        // Opens each file right before writing its contents, and as many upcoming files as
+        // allowed.
+        let mut paths = paths.into_iter().map(|p| p.as_ref().to_path_buf());
+        let mut files = VecDeque::new();
+        let mut merger = Cow::Borrowed(&self.0);
+        let mut sink = Sink::new(writer, &self.0 .0);
+        let mut report = MergeReport::default();
+        for i in 0..len {
+            while files.len() < self.0 .0.max_open_files() {
+                match paths.next() {
+                    Some(path) => files.push_back((File::open(&path).await?, path)),
+                    None => break,
+                }
+            }
+
+            let (mut file, path) = match files.pop_front() {
+                Some(opened) => opened,
+                None => break,
+            };
+            if i == 0 {
+                merger = self.0.resolved(&mut file).await?;
+                if let Some(union) = union.take() {
+                    merger.to_mut().0.set_union(union);
+                }
+            }
+            let pos = PartPos::of(i, len);
+            merger
+                .write_part(&mut file, &mut sink, &mut report, pos, Some(path))
+                .await?;
+        }
+        merger.write_end(&mut sink, &mut report).await?;
+
+        Ok(report)
+    }
+
+    /// Opens every file path given if path points to a regular file, and then merges file contents
+    /// into the given writer according to the given configrations.
+    ///
+    /// See [`FileMerger::with_paths_lossy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given path vector is
+    /// empty;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given sources;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    ///
+    /// [`FileMerger::with_paths_lossy`]: crate::FileMerger::with_paths_lossy
+    pub async fn with_paths_lossy<P, W>(&self, paths: Vec<P>, writer: &mut W) -> Result<()>
+    where
+        P: AsRef<Path>,
+        W: AsyncWrite + Unpin,
+    {
+        self.with_paths_lossy_with_report(paths, writer)
+            .await
+            .map(|_| ())
+    }
+
+    /// Does the same as [`with_paths_lossy`], returning a [`MergeReport`] telling where each file
+    /// landed in the output.
+    ///
+    /// [`with_paths_lossy`]: AsyncFileMerger::with_paths_lossy
+    pub async fn with_paths_lossy_with_report<P, W>(
+        &self,
+        paths: Vec<P>,
+        writer: &mut W,
+    ) -> Result<MergeReport>
+    where
+        P: AsRef<Path>,
+        W: AsyncWrite + Unpin,
+    {
+        // Dumps any path that does not point to a regular file..
+        let mut sources = Vec::with_capacity(paths.len());
+        for p in paths {
+            if is_file(p.as_ref()).await {
+                sources.push(p);
+            }
+        }
+
+        self.with_paths_with_report(sources, writer).await
+    }
+
+    /// Reads sequentially from the given files and merges their contents into the given writer
+    /// according to the given configrations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given path vector is
+    /// empty;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given sources;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    pub async fn with_files<W>(&self, files: Vec<File>, writer: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.with_files_with_report(files, writer).await.map(|_| ())
+    }
+
+    /// Does the same as [`with_files`], returning a [`MergeReport`] telling where each file
+    /// landed in the output.
+    ///
+    /// [`with_files`]: AsyncFileMerger::with_files
+    pub async fn with_files_with_report<W>(
+        &self,
+        files: Vec<File>,
+        writer: &mut W,
+    ) -> Result<MergeReport>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.0.merge_sources_into_with_report(files, writer).await
+    }
+}
+
+// Returns `true` if the given path points to a regular file.
+async fn is_file(path: &Path) -> bool {
+    fs::metadata(path).await.map(|m| m.is_file()).unwrap_or(false)
+}
+
+// Renders the output synchronously through the `Tracker` of the sync mergers, which works out
+// the checksums of the index, and hands the rendered bytes on to the async writer.
+struct Sink<'w, W> {
+    tracker: Tracker<Vec<u8>>,
+    writer: &'w mut W,
+}
+
+impl<'w, W: AsyncWrite + Unpin> Sink<'w, W> {
+    fn new(writer: &'w mut W, merger: &RsMerger<'_>) -> Self {
+        Sink {
+            tracker: Tracker::new(Vec::new(), merger),
+            writer,
+        }
+    }
+
+    // Writes out what has been rendered so far.
+    async fn flush(&mut self) -> Result<()> {
+        let buf = self.tracker.get_mut();
+        self.writer.write_all(buf).await?;
+        buf.clear();
+        Ok(())
+    }
+
+    async fn write_all(&mut self, bytes: &[u8]) -> Result<()> {
+        self.tracker.write_all(bytes)?;
+        self.flush().await
+    }
+
+    // Writes the given number of fill bytes, one chunk at a time.
+    async fn write_fill(&mut self, fill: u8, mut len: u64) -> Result<()> {
+        let chunk = [fill; 8 * 1024];
+        while len > 0 {
+            let n = len.min(chunk.len() as u64) as usize;
+            self.write_all(&chunk[..n]).await?;
+            len -= n as u64;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,402 @@
+//! Header and column handling of CSV sources.
+use crate::error::{ErrorKind, Result};
+use crate::rewrite::Rewrite;
+
+use std::io::{self, Read};
+use std::mem;
+
+/// The format of CSV sources, see [`RsMerger::csv_headers`].
+///
+/// Records end with `\n` or `\r\n` outside of quoted fields, so a quoted field may hold line
+/// terminators and delimiters of its own. Inside a quoted field, a doubled quote stands for one.
+///
+/// # Examples
+///
+/// ```
+/// use admerge::Csv;
+///
+/// // Semicolon-separated values, quoted with single quotes.
+/// let csv = Csv::new().delimiter(b';').quote(b'\'');
+/// ```
+///
+/// [`RsMerger::csv_headers`]: crate::RsMerger::csv_headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Csv {
+    delimiter: u8,
+    quote: u8,
+}
+
+impl Default for Csv {
+    fn default() -> Self {
+        Csv {
+            delimiter: b',',
+            quote: b'"',
+        }
+    }
+}
+
+impl Csv {
+    /// Creates the format of comma-separated values, quoted with double quotes.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the byte that separates fields. Defaults to `,`.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets the byte that quotes fields. Defaults to `"`.
+    pub fn quote(mut self, quote: u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    // Reads the first record of the given source, failing if it is longer than `limit` bytes.
+    //
+    // A quoted field that is never closed runs to the end of the source.
+    pub(crate) fn header<R: Read>(&self, reader: &mut R, limit: usize) -> Result<Header> {
+        let mut scan = HeaderScan::new(*self, limit);
+        let mut buf = [0; 8 * 1024];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => return Ok(scan.finish()),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if let Some(header) = scan.feed(&buf[..n])? {
+                return Ok(header);
+            }
+        }
+    }
+
+    // Appends the given field to `out`, quoting it if needed.
+    pub(crate) fn write_field(&self, out: &mut Vec<u8>, field: &[u8]) {
+        let special = |&b: &u8| b == self.delimiter || b == self.quote || b == b'\r' || b == b'\n';
+        if !field.iter().any(special) {
+            out.extend_from_slice(field);
+            return;
+        }
+
+        out.push(self.quote);
+        for &b in field {
+            if b == self.quote {
+                out.push(b);
+            }
+            out.push(b);
+        }
+        out.push(self.quote);
+    }
+}
+
+/// The columns of merged CSV sources, see [`RsMerger::csv_columns`].
+///
+/// [`RsMerger::csv_columns`]: crate::RsMerger::csv_columns
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy)]
+pub enum Columns<'a> {
+    /// Every column of any source, in the order they first appear.
+    Union,
+    /// The given columns, in the given order. Other columns are dropped.
+    Only(&'a [&'a str]),
+}
+
+// Works out the union of the columns of the given CSV sources, in the order they first appear.
+pub(crate) fn union<R, I>(csv: Csv, sources: I, limit: usize) -> Result<Vec<Vec<u8>>>
+where
+    R: Read,
+    I: IntoIterator<Item = Result<R>>,
+{
+    let mut columns = Vec::new();
+    for source in sources {
+        unite(&mut columns, csv.header(&mut source?, limit)?);
+    }
+    Ok(columns)
+}
+
+// Appends the columns of the given header that are not in `columns` yet.
+pub(crate) fn unite(columns: &mut Vec<Vec<u8>>, header: Header) {
+    for field in header.fields {
+        if !columns.contains(&field) {
+            columns.push(field);
+        }
+    }
+}
+
+// Reads the first record of a CSV source chunk by chunk, see `Csv::header`.
+pub(crate) struct HeaderScan(Parser);
+
+impl HeaderScan {
+    pub(crate) fn new(csv: Csv, limit: usize) -> Self {
+        HeaderScan(Parser::new(csv, limit))
+    }
+
+    // Feeds the next chunk of the source, returning the header once it is complete.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Result<Option<Header>> {
+        Ok(self.0.feed(chunk)?.map(|(_, record)| record.into()))
+    }
+
+    // Returns the header of a source that ended before its first record did.
+    pub(crate) fn finish(&mut self) -> Header {
+        self.0.finish().map(Header::from).unwrap_or_default()
+    }
+}
+
+// Splits CSV bytes held in memory into records.
+pub(crate) struct Records<'b> {
+    parser: Parser,
+    bytes: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> Records<'b> {
+    pub(crate) fn new(csv: Csv, bytes: &'b [u8]) -> Self {
+        Records {
+            parser: Parser::new(csv, usize::MAX),
+            bytes,
+            pos: 0,
+        }
+    }
+
+    // Returns the next record and its offset, if any.
+    pub(crate) fn next(&mut self) -> Option<(usize, Record)> {
+        let start = self.pos;
+        let record = match self.parser.feed(&self.bytes[start..]) {
+            Ok(Some((len, record))) => {
+                self.pos += len;
+                Some(record)
+            }
+            // Records are not bounded, so only the end of the bytes stops the parser.
+            Ok(None) | Err(_) => {
+                self.pos = self.bytes.len();
+                self.parser.finish()
+            }
+        };
+        record.map(|record| (start, record))
+    }
+}
+
+// One record of a CSV source.
+pub(crate) struct Record {
+    // Length of the record, its terminator included.
+    pub(crate) len: u64,
+    // Fields of the record, unquoted.
+    pub(crate) fields: Vec<Vec<u8>>,
+    pub(crate) terminator: &'static [u8],
+    // Whether the record ends inside a quoted field, which is only the case for the last one.
+    pub(crate) open: bool,
+}
+
+// Splits the contents of a CSV source into records, chunk by chunk.
+struct Parser {
+    csv: Csv,
+    limit: usize,
+    // The record being parsed.
+    len: u64,
+    fields: Vec<Vec<u8>>,
+    field: Vec<u8>,
+    quoted: bool,
+    // Whether the last byte was a quote inside a quoted field, which either closes the field
+    // or starts a doubled quote.
+    closing: bool,
+    // Whether the last byte was a `\r` outside of quoted fields.
+    cr: bool,
+}
+
+impl Parser {
+    fn new(csv: Csv, limit: usize) -> Self {
+        Parser {
+            csv,
+            limit,
+            len: 0,
+            fields: Vec::new(),
+            field: Vec::new(),
+            quoted: false,
+            closing: false,
+            cr: false,
+        }
+    }
+
+    // Feeds the given chunk up to the end of the first record completed, returning the number of
+    // bytes fed and the record, if any.
+    fn feed(&mut self, chunk: &[u8]) -> Result<Option<(usize, Record)>> {
+        for (i, &b) in chunk.iter().enumerate() {
+            self.len += 1;
+            if self.len > self.limit as u64 {
+                return Err(ErrorKind::BufferExceeded(self.limit));
+            }
+
+            if self.quoted {
+                match (self.closing, b == self.csv.quote) {
+                    (false, true) => self.closing = true,
+                    (false, false) => self.field.push(b),
+                    (true, true) => {
+                        self.closing = false;
+                        self.field.push(b);
+                    }
+                    (true, false) => {
+                        self.closing = false;
+                        self.quoted = false;
+                    }
+                }
+                if self.quoted {
+                    continue;
+                }
+            }
+
+            if self.cr {
+                self.cr = false;
+                if b == b'\n' {
+                    return Ok(Some((i + 1, self.take(b"\r\n"))));
+                }
+                self.field.push(b'\r');
+            }
+            match b {
+                b'\n' => return Ok(Some((i + 1, self.take(b"\n")))),
+                b'\r' => self.cr = true,
+                _ if b == self.csv.delimiter => self.fields.push(mem::take(&mut self.field)),
+                _ if b == self.csv.quote && self.field.is_empty() => self.quoted = true,
+                _ => self.field.push(b),
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Returns the last record, which lacks a terminator, if any.
+    fn finish(&mut self) -> Option<Record> {
+        let open = self.quoted && !self.closing;
+        let record = match (self.len, self.cr) {
+            (0, _) => None,
+            (_, true) => Some(self.take(b"\r")),
+            (_, false) => Some(self.take(b"")),
+        };
+        record.map(|record| Record { open, ..record })
+    }
+
+    fn take(&mut self, terminator: &'static [u8]) -> Record {
+        self.fields.push(mem::take(&mut self.field));
+        self.quoted = false;
+        self.closing = false;
+        self.cr = false;
+        Record {
+            len: mem::take(&mut self.len),
+            fields: mem::take(&mut self.fields),
+            terminator,
+            open: false,
+        }
+    }
+}
+
+/// Rewrites the records of a CSV source so that they hold the given columns.
+///
+/// Fields are matched to columns by the names in the header of the source, and columns the
+/// source lacks are filled with the given default. The header itself is rewritten into the
+/// given columns, or dropped.
+pub(crate) struct ColumnRewriter<'c> {
+    parser: Parser,
+    columns: Vec<&'c [u8]>,
+    missing: &'c [u8],
+    header: bool,
+    // For each column, the index of the field that holds it, once the header is read.
+    mapping: Option<Vec<Option<usize>>>,
+}
+
+impl<'c> ColumnRewriter<'c> {
+    // Creates a rewriter that keeps the rewritten header if `header` is set, and fails on records
+    // longer than `limit` bytes.
+    pub(crate) fn new(
+        csv: Csv,
+        columns: Vec<&'c [u8]>,
+        missing: &'c [u8],
+        header: bool,
+        limit: usize,
+    ) -> Self {
+        ColumnRewriter {
+            parser: Parser::new(csv, limit),
+            columns,
+            missing,
+            header,
+            mapping: None,
+        }
+    }
+
+    fn rewrite(&mut self, record: Record, out: &mut Vec<u8>) {
+        let csv = self.parser.csv;
+        let mapping = match &self.mapping {
+            Some(mapping) => mapping,
+            None => {
+                let fields = &record.fields;
+                let mapping = self
+                    .columns
+                    .iter()
+                    .map(|column| fields.iter().position(|field| field == column));
+                self.mapping = Some(mapping.collect());
+                if self.header {
+                    for (i, column) in self.columns.iter().enumerate() {
+                        if i > 0 {
+                            out.push(csv.delimiter);
+                        }
+                        csv.write_field(out, column);
+                    }
+                    out.extend_from_slice(record.terminator);
+                }
+                return;
+            }
+        };
+
+        // Blank lines are kept as they are.
+        if let [field] = &record.fields[..] {
+            if field.is_empty() {
+                out.extend_from_slice(record.terminator);
+                return;
+            }
+        }
+
+        for (i, index) in mapping.iter().enumerate() {
+            if i > 0 {
+                out.push(csv.delimiter);
+            }
+            let field = index.and_then(|index| record.fields.get(index));
+            csv.write_field(out, field.map_or(self.missing, Vec::as_slice));
+        }
+        out.extend_from_slice(record.terminator);
+    }
+}
+
+impl<'c> Rewrite for ColumnRewriter<'c> {
+    fn push(&mut self, mut chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        while let Some((len, record)) = self.parser.feed(chunk)? {
+            chunk = &chunk[len..];
+            self.rewrite(record, out);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        if let Some(record) = self.parser.finish() {
+            self.rewrite(record, out);
+        }
+        Ok(())
+    }
+}
+
+/// The first record of a CSV source.
+#[derive(Debug, Default)]
+pub(crate) struct Header {
+    // Length of the record, its terminator included.
+    pub(crate) len: u64,
+    // Fields of the record, unquoted.
+    pub(crate) fields: Vec<Vec<u8>>,
+}
+
+impl From<Record> for Header {
+    fn from(record: Record) -> Self {
+        Header {
+            len: record.len,
+            fields: record.fields,
+        }
+    }
+}
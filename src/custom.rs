@@ -0,0 +1,183 @@
+//! User-defined skip rules.
+use crate::error::Result;
+use crate::merge::{PartPos, Terminator};
+use crate::util::{self, ReadSeek};
+
+use std::fmt;
+use std::io::{Read, SeekFrom};
+
+/// A user-defined rule that works out how many bytes to skip from a source, see [`Skip::Custom`].
+///
+/// This trait is implemented for every closure that takes a [`SkipSource`] and returns the
+/// number of bytes to skip.
+///
+/// # Examples
+///
+/// Skips a header whose length is given by its first byte.
+///
+/// ```
+/// use admerge::{RsMerger, Side, Skip, SkipSource, Result};
+/// use std::io::Cursor;
+///
+/// fn main() -> Result<()> {
+///     let rule = |source: &mut SkipSource<'_>| -> Result<usize> {
+///         match source.side() {
+///             Side::Head => {
+///                 let mut len = [0];
+///                 source.read_at(0, &mut len)?;
+///                 Ok(1 + len[0] as usize)
+///             }
+///             Side::Tail => Ok(0),
+///         }
+///     };
+///
+///     let c1 = Cursor::new(&b"\x02ab 11\n"[..]);
+///     let c2 = Cursor::new(&b"\x03abc 21\n"[..]);
+///     let mut buf = Vec::new();
+///
+///     let mut merger = RsMerger::new();
+///     merger.skip_head(Skip::Custom(&rule));
+///     merger.merge_sources_into(vec![c1, c2], &mut buf)?;
+///     assert_eq!(buf, b" 11\n 21\n");
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`Skip::Custom`]: crate::Skip::Custom
+pub trait SkipRule {
+    /// Returns the number of bytes to skip from the head or the tail of the given source,
+    /// depending on [`SkipSource::side`].
+    fn skip(&self, source: &mut SkipSource<'_>) -> Result<usize>;
+}
+
+impl<F> SkipRule for F
+where
+    F: Fn(&mut SkipSource<'_>) -> Result<usize>,
+{
+    fn skip(&self, source: &mut SkipSource<'_>) -> Result<usize> {
+        self(source)
+    }
+}
+
+impl fmt::Debug for dyn SkipRule + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SkipRule")
+    }
+}
+
+/// The side of a source a [`SkipRule`] is applied to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+    /// The rule was passed by `skip_head`.
+    Head,
+    /// The rule was passed by `skip_tail`.
+    Tail,
+}
+
+/// A view of a source given to a [`SkipRule`].
+///
+/// When a [`Skip::Chain`] is used, the view only covers the contents left by the previous skips.
+///
+/// [`Skip::Chain`]: crate::Skip::Chain
+pub struct SkipSource<'s> {
+    reader: &'s mut dyn ReadSeek,
+    len: u64,
+    endn: bool,
+    side: Side,
+    pos: PartPos,
+}
+
+impl<'s> SkipSource<'s> {
+    pub(crate) fn new(
+        reader: &'s mut dyn ReadSeek,
+        side: Side,
+        pos: PartPos,
+        terminator: Terminator<'_>,
+    ) -> Result<Self> {
+        let endn = util::endswith_newline(&mut &mut *reader, terminator)?;
+        let len = reader.seek(SeekFrom::End(0))?;
+        Ok(SkipSource {
+            reader,
+            len,
+            endn,
+            side,
+            pos,
+        })
+    }
+
+    /// Returns the length of this source.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if this source is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the side of this source the rule is applied to.
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    /// Returns the position of this source among the given sources.
+    pub fn position(&self) -> PartPos {
+        self.pos
+    }
+
+    /// Returns `true` if this source ends with a newline, that is one of the configured line
+    /// terminators, see [`RsMerger::line_terminator`].
+    ///
+    /// [`RsMerger::line_terminator`]: crate::RsMerger::line_terminator
+    pub fn ends_with_newline(&self) -> bool {
+        self.endn
+    }
+
+    /// Reads bytes starting at the given offset into the given buffer, returning how many bytes
+    /// were read.
+    ///
+    /// Fewer bytes than the buffer holds are read only if the end of this source is reached.
+    pub fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        if offset >= self.len {
+            return Ok(0);
+        }
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let max = (self.len - offset).min(buf.len() as u64);
+        let mut read = 0;
+        let mut take = (&mut self.reader).take(max);
+        loop {
+            match take.read(&mut buf[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        Ok(read)
+    }
+
+    /// Returns the offset of the first occurrence of the given bytes in this source.
+    pub fn find(&mut self, bytes: &[u8]) -> Result<Option<u64>> {
+        if bytes.is_empty() {
+            return Ok(Some(0));
+        }
+        Ok(util::find(bytes, &mut self.reader)?.map(|pos| pos as u64))
+    }
+
+    /// Returns the offset of the last occurrence of the given bytes in this source.
+    pub fn rfind(&mut self, bytes: &[u8]) -> Result<Option<u64>> {
+        if bytes.is_empty() {
+            return Ok(Some(self.len));
+        }
+        Ok(util::rfind(bytes, &mut self.reader)?.map(|pos| pos as u64))
+    }
+}
+
+impl fmt::Debug for SkipSource<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SkipSource")
+            .field("len", &self.len)
+            .field("side", &self.side)
+            .field("position", &self.pos)
+            .finish()
+    }
+}
@@ -0,0 +1,206 @@
+//! Transcoding of sources and merged output.
+use crate::error::Result;
+use crate::rewrite::Rewrite;
+
+use std::io::{self, Write};
+use std::mem;
+use std::str;
+
+use encoding_rs::{CoderResult, Decoder, Encoder, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Controls how sources are decoded before merging, see [`RsMerger::decode_sources`].
+///
+/// This type is only available if the `encoding_rs` feature is enabled.
+///
+/// [`RsMerger::decode_sources`]: crate::RsMerger::decode_sources
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy)]
+pub enum Decode<'a> {
+    /// Decode every source with the given encoding, removing its byte order mark if present.
+    All(&'static Encoding),
+    /// Decode each source with the encoding at its index, removing its byte order mark if
+    /// present. Sources beyond the end of the slice are decoded as UTF-8.
+    Each(&'a [&'static Encoding]),
+    /// Detect the encoding of each source from its byte order mark, falling back to the given
+    /// encoding if there is none. The byte order mark is removed.
+    Auto(&'static Encoding),
+}
+
+impl<'a> Decode<'a> {
+    // Creates a decoder for the source at the given index.
+    pub(crate) fn decoder(&self, index: usize) -> Decoder {
+        match *self {
+            Decode::All(encoding) => encoding.new_decoder_with_bom_removal(),
+            Decode::Each(encodings) => encodings
+                .get(index)
+                .unwrap_or(&UTF_8)
+                .new_decoder_with_bom_removal(),
+            Decode::Auto(fallback) => fallback.new_decoder(),
+        }
+    }
+}
+
+// Decodes the bytes of a source into UTF-8.
+impl Rewrite for Decoder {
+    fn push(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        decode(self, chunk, out, false);
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        decode(self, &[], out, true);
+        Ok(())
+    }
+}
+
+// Decodes the given bytes, appending the UTF-8 to `out`.
+fn decode(decoder: &mut Decoder, bytes: &[u8], out: &mut Vec<u8>, last: bool) {
+    let start = out.len();
+    let n = bytes.len();
+    let capacity = decoder.max_utf8_buffer_length(n).unwrap_or(n * 3 + 16);
+    out.resize(start + capacity, 0);
+    let (_, read, written, _) = decoder.decode_to_utf8(bytes, &mut out[start..], last);
+    debug_assert_eq!(read, n);
+    out.truncate(start + written);
+}
+
+/// A writer that encodes the UTF-8 written to it into the given encoding.
+///
+/// Malformed UTF-8 is replaced by U+FFFD, and characters the target encoding cannot represent
+/// are replaced by HTML numeric character references.
+pub(crate) struct EncodeWriter<W> {
+    inner: W,
+    target: Target,
+    // Bytes of an incomplete character at the end of what was written so far.
+    pending: Vec<u8>,
+    buf: Vec<u8>,
+    count: u64,
+}
+
+enum Target {
+    Utf8,
+    // `encoding_rs` only decodes UTF-16, so it is encoded by hand.
+    Utf16 { big_endian: bool },
+    Other(Encoder),
+}
+
+impl<W: Write> EncodeWriter<W> {
+    pub(crate) fn new(inner: W, encoding: &'static Encoding) -> Self {
+        let target = if encoding == UTF_8 {
+            Target::Utf8
+        } else if encoding == UTF_16LE || encoding == UTF_16BE {
+            Target::Utf16 {
+                big_endian: encoding == UTF_16BE,
+            }
+        } else {
+            Target::Other(encoding.new_encoder())
+        };
+        EncodeWriter {
+            inner,
+            target,
+            pending: Vec::new(),
+            buf: vec![0; 8 * 1024],
+            count: 0,
+        }
+    }
+
+    // Encodes anything left, returning the number of bytes written to the underlying writer.
+    pub(crate) fn finish(&mut self) -> Result<u64> {
+        if !self.pending.is_empty() {
+            self.pending.clear();
+            self.encode("\u{fffd}", false)?;
+        }
+        self.encode("", true)?;
+        Ok(self.count)
+    }
+
+    fn encode(&mut self, s: &str, last: bool) -> io::Result<()> {
+        match &mut self.target {
+            Target::Utf8 => {
+                self.inner.write_all(s.as_bytes())?;
+                self.count += s.len() as u64;
+            }
+            Target::Utf16 { big_endian } => {
+                let big_endian = *big_endian;
+                let mut bytes = Vec::with_capacity(s.len() * 2);
+                for unit in s.encode_utf16() {
+                    match big_endian {
+                        true => bytes.extend_from_slice(&unit.to_be_bytes()),
+                        false => bytes.extend_from_slice(&unit.to_le_bytes()),
+                    }
+                }
+                self.inner.write_all(&bytes)?;
+                self.count += bytes.len() as u64;
+            }
+            Target::Other(encoder) => {
+                let mut input = s;
+                loop {
+                    let (result, read, written, _) =
+                        encoder.encode_from_utf8(input, &mut self.buf, last);
+                    self.inner.write_all(&self.buf[..written])?;
+                    self.count += written as u64;
+                    input = &input[read..];
+                    if let CoderResult::InputEmpty = result {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W> AsMut<W> for EncodeWriter<W> {
+    fn as_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for EncodeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut pending = mem::take(&mut self.pending);
+        pending.extend_from_slice(buf);
+
+        let mut start = 0;
+        while start < pending.len() {
+            match str::from_utf8(&pending[start..]) {
+                Ok(s) => {
+                    self.encode(s, false)?;
+                    start = pending.len();
+                }
+                Err(e) => {
+                    let valid = start + e.valid_up_to();
+                    // The prefix was just checked to be valid.
+                    let s = str::from_utf8(&pending[start..valid]).unwrap();
+                    self.encode(s, false)?;
+                    match e.error_len() {
+                        Some(len) => {
+                            self.encode("\u{fffd}", false)?;
+                            start = valid + len;
+                        }
+                        // The last character is incomplete, keeps it for the next write.
+                        None => {
+                            start = valid;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        pending.drain(..start);
+        self.pending = pending;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// Returns the number of bytes the given UTF-8 takes once encoded into the given encoding.
+pub(crate) fn encoded_len(encoding: &'static Encoding, bytes: &[u8]) -> Result<u64> {
+    let mut writer = EncodeWriter::new(io::sink(), encoding);
+    writer.write_all(bytes)?;
+    writer.finish()
+}
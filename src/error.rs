@@ -11,6 +11,7 @@ use std::result::Result as StdResult;
 pub type Result<T> = StdResult<T, ErrorKind>;
 
 /// The concrete type of an error.
+#[non_exhaustive]
 #[derive(Error, Debug)]
 pub enum ErrorKind {
     /// Occurs if the given input to be merged is empty.
@@ -23,10 +24,74 @@ pub enum ErrorKind {
     )]
     InvalidSkip,
 
+    /// Occurs if the bytes a merge needs to hold in memory grow beyond the configured limit.
+    #[error("the buffered bytes exceeded the limit of {0} bytes")]
+    BufferExceeded(usize),
+
+    /// Occurs if the method called cannot honour the configured options.
+    #[error("{0} is not supported with the configured options")]
+    Unsupported(&'static str),
+
     /// Occurs if the given path is not a valid file path.
     #[error("the path provided at index {0} is not a valid file path")]
     InvalidPath(usize),
 
+    /// Occurs if the contents kept from the source at the given index are not made of whole
+    /// records, see [`RsMerger::check_records`].
+    ///
+    /// [`RsMerger::check_records`]: crate::RsMerger::check_records
+    #[error("the source at index {0} does not hold a whole number of records")]
+    PartialRecord(usize),
+
+    /// Occurs if the CSV header of the source at the given index does not match the one of the
+    /// first source, see [`RsMerger::csv_headers`].
+    ///
+    /// [`RsMerger::csv_headers`]: crate::RsMerger::csv_headers
+    #[error("the header of the source at index {0} does not match the one of the first source")]
+    HeaderMismatch(usize),
+
+    /// Occurs if the merged output would be larger than the configured image size, see
+    /// [`RsMerger::image_size`].
+    ///
+    /// [`RsMerger::image_size`]: crate::RsMerger::image_size
+    #[error("the merged output does not fit in an image of {0} bytes")]
+    ImageOverflow(u64),
+
+    /// Occurs if an index being read is malformed, see [`Index`]. Holds the byte offset the
+    /// problem was found at, as precisely as known.
+    ///
+    /// [`Index`]: crate::Index
+    #[error("the index is malformed at byte offset {0}")]
+    InvalidIndex(u64),
+
+    /// Occurs if a merged output ends before the part at the given index does, see
+    /// [`Splitter`].
+    ///
+    /// [`Splitter`]: crate::Splitter
+    #[error("the merged output ends before the part at index {0} does")]
+    Truncated(usize),
+
+    /// Occurs if the part at the given index of a merged output does not match its checksum, see
+    /// [`Splitter`].
+    ///
+    /// [`Splitter`]: crate::Splitter
+    #[error("the part at index {0} does not match its checksum")]
+    ChecksumMismatch(usize),
+
+    /// Occurs if the source at the given index does not hold valid JSON, see [`JsonMerger`].
+    /// Holds the index of the source and the byte offset the problem was found at.
+    ///
+    /// [`JsonMerger`]: crate::JsonMerger
+    #[error("the source at index {0} is not valid JSON at byte offset {1}")]
+    InvalidJson(usize, u64),
+
+    /// Occurs if a line of the JSON Lines source at the given index is not valid JSON, see
+    /// [`JsonMerger::lenient`]. Holds the index of the source and the line number, starting at 1.
+    ///
+    /// [`JsonMerger::lenient`]: crate::JsonMerger::lenient
+    #[error("line {1} of the source at index {0} is not valid JSON")]
+    InvalidRecord(usize, u64),
+
     /// Represents an error that originates from [`ByteSeeker`].
     ///
     /// [`ByteSeeker`]: byteseeker::ByteSeeker
@@ -0,0 +1,394 @@
+//! Tables of contents of merged outputs.
+use crate::csv::{Csv, Records};
+use crate::error::{ErrorKind, Result};
+use crate::json::{self, Value};
+use crate::merge::{PartPos, PartRange, RsMerger};
+
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Where the index of a merge goes, see [`RsMerger::emit_index`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexPlacement {
+    /// The index is only returned by [`MergeReport::index`], to be written as a sidecar file by
+    /// [`Index::write_json`] or [`Index::write_csv`].
+    ///
+    /// [`MergeReport::index`]: crate::MergeReport::index
+    Sidecar,
+    /// The index is also appended to the merged output as a footer, which
+    /// [`Index::read_footer`] reads back.
+    Footer,
+}
+
+/// The table of contents of a merged output, telling where each source landed in it.
+///
+/// An index is returned by [`MergeReport::index`] once [`RsMerger::emit_index`] is configured,
+/// and can be written and read back in the following formats:
+///
+/// - JSON, by [`write_json`] and [`from_json`]: an object whose `entries` member is an array of
+///   objects with the `name`, `offset`, `len` and `crc32` members described by [`IndexEntry`].
+///   Other members are ignored when reading.
+/// - CSV, by [`write_csv`] and [`from_csv`]: a `name,offset,len,crc32` header followed by one
+///   record per entry. Names are quoted as described by RFC 4180 when needed.
+/// - A footer at the end of the merged output, by [`RsMerger::emit_index`] and [`read_footer`]:
+///   the JSON index, then its length as an 8-byte little-endian integer, then the 8 bytes of
+///   `ADMINDEX`.
+///
+/// # Examples
+///
+/// ```
+/// use admerge::{RsMerger, Index, IndexPlacement, Result};
+/// use std::io::Cursor;
+///
+/// fn main() -> Result<()> {
+///     let mut buf = Vec::new();
+///
+///     let mut merger = RsMerger::new();
+///     merger.emit_index(IndexPlacement::Footer);
+///
+///     let sources = vec![Cursor::new("first\n"), Cursor::new("second\n")];
+///     merger.merge_sources_into(sources, &mut buf)?;
+///
+///     let index = Index::read_footer(&mut Cursor::new(&buf))?;
+///     let entry = &index.entries()[1];
+///     assert_eq!(entry.name, "1");
+///     assert_eq!(entry.offset, 6);
+///     assert_eq!(entry.len, 7);
+///     assert_eq!(&buf[6..13], b"second\n");
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`MergeReport::index`]: crate::MergeReport::index
+/// [`write_json`]: Index::write_json
+/// [`from_json`]: Index::from_json
+/// [`write_csv`]: Index::write_csv
+/// [`from_csv`]: Index::from_csv
+/// [`read_footer`]: Index::read_footer
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Index {
+    entries: Vec<IndexEntry>,
+}
+
+/// One source of a merged output, see [`Index`].
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// The path of this source as given, or its index among the given sources if it was not
+    /// opened from a path.
+    pub name: String,
+    /// Offset of the kept contents of this source in the merged output.
+    pub offset: u64,
+    /// Length of the kept contents of this source in the merged output.
+    pub len: u64,
+    /// CRC-32 (as used by zlib and PNG) of the kept contents of this source in the merged
+    /// output.
+    pub crc32: u32,
+}
+
+const FOOTER_MAGIC: &[u8; 8] = b"ADMINDEX";
+const CSV_HEADER: &str = "name,offset,len,crc32";
+
+impl Index {
+    pub(crate) fn new(entries: Vec<IndexEntry>) -> Self {
+        Index { entries }
+    }
+
+    /// Returns the entries of this index, in merge order.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Writes this index as JSON into the given writer.
+    pub fn write_json<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(b"{\"entries\":[")?;
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
+            }
+            writer.write_all(b"\n{\"name\":")?;
+            json::write_str(writer, &entry.name)?;
+            write!(
+                writer,
+                ",\"offset\":{},\"len\":{},\"crc32\":{}}}",
+                entry.offset, entry.len, entry.crc32
+            )?;
+        }
+        writer.write_all(b"\n]}\n")?;
+        Ok(())
+    }
+
+    /// Writes this index as CSV into the given writer.
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writeln!(writer, "{}", CSV_HEADER)?;
+        let mut name = Vec::new();
+        for entry in &self.entries {
+            name.clear();
+            Csv::new().write_field(&mut name, entry.name.as_bytes());
+            writer.write_all(&name)?;
+            writeln!(writer, ",{},{},{}", entry.offset, entry.len, entry.crc32)?;
+        }
+        Ok(())
+    }
+
+    /// Parses an index written by [`write_json`].
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidIndex`] if the given bytes are not such an
+    /// index.
+    ///
+    /// [`write_json`]: Index::write_json
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        let value = json::parse(bytes).map_err(|at| ErrorKind::InvalidIndex(at as u64))?;
+        let invalid = || ErrorKind::InvalidIndex(0);
+        let items = match value.get("entries") {
+            Some(Value::Array(items)) => items,
+            _ => return Err(invalid()),
+        };
+
+        let mut entries = Vec::with_capacity(items.len());
+        for item in items {
+            let field = |key| item.get(key).and_then(Value::as_u64).ok_or_else(invalid);
+            let name = item
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(invalid)?;
+            entries.push(IndexEntry {
+                name: name.to_owned(),
+                offset: field("offset")?,
+                len: field("len")?,
+                crc32: field("crc32")?.try_into().map_err(|_| invalid())?,
+            });
+        }
+
+        Ok(Index { entries })
+    }
+
+    /// Parses an index written by [`write_csv`].
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidIndex`] if the given bytes are not such an
+    /// index.
+    ///
+    /// [`write_csv`]: Index::write_csv
+    pub fn from_csv(bytes: &[u8]) -> Result<Self> {
+        let mut records = Records::new(Csv::new(), bytes);
+        let header = CSV_HEADER.as_bytes().split(|&b| b == b',');
+        match records.next() {
+            Some((_, record)) if record.fields.iter().eq(header) => (),
+            _ => return Err(ErrorKind::InvalidIndex(0)),
+        }
+
+        let mut entries = Vec::new();
+        while let Some((at, record)) = records.next() {
+            let invalid = || ErrorKind::InvalidIndex(at as u64);
+            if record.open {
+                return Err(invalid());
+            }
+            let fields = record
+                .fields
+                .into_iter()
+                .map(String::from_utf8)
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?;
+            let (name, offset, len, crc32) = match fields.as_slice() {
+                [name, offset, len, crc32] => (name, offset, len, crc32),
+                _ => return Err(invalid()),
+            };
+            entries.push(IndexEntry {
+                name: name.clone(),
+                offset: offset.parse().map_err(|_| invalid())?,
+                len: len.parse().map_err(|_| invalid())?,
+                crc32: crc32.parse().map_err(|_| invalid())?,
+            });
+        }
+
+        Ok(Index { entries })
+    }
+
+    /// Reads the index appended as a footer to the given merged output, see
+    /// [`IndexPlacement::Footer`].
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidIndex`] if the given output does not end
+    /// with such an index.
+    pub fn read_footer<RS: Read + Seek>(reader: &mut RS) -> Result<Self> {
+        let end = reader.seek(SeekFrom::End(0))?;
+        if end < 16 {
+            return Err(ErrorKind::InvalidIndex(0));
+        }
+
+        let mut tail = [0; 16];
+        reader.seek(SeekFrom::Start(end - 16))?;
+        reader.read_exact(&mut tail)?;
+        if &tail[8..] != FOOTER_MAGIC {
+            return Err(ErrorKind::InvalidIndex(end - 8));
+        }
+        let mut len = [0; 8];
+        len.copy_from_slice(&tail[..8]);
+        let len = u64::from_le_bytes(len);
+        let start = match (end - 16).checked_sub(len) {
+            Some(start) => start,
+            None => return Err(ErrorKind::InvalidIndex(end - 16)),
+        };
+
+        let mut json = Vec::new();
+        reader.seek(SeekFrom::Start(start))?;
+        reader.take(len).read_to_end(&mut json)?;
+        Index::from_json(&json).map_err(|e| match e {
+            ErrorKind::InvalidIndex(at) => ErrorKind::InvalidIndex(start + at),
+            e => e,
+        })
+    }
+
+    // Writes this index as a footer, returning the number of bytes written.
+    pub(crate) fn write_footer<W: Write>(&self, writer: &mut W) -> Result<u64> {
+        let mut json = Vec::new();
+        self.write_json(&mut json)?;
+        writer.write_all(&json)?;
+        writer.write_all(&(json.len() as u64).to_le_bytes())?;
+        writer.write_all(FOOTER_MAGIC)?;
+        Ok(json.len() as u64 + 16)
+    }
+}
+
+/// A CRC-32 hasher, using the polynomial of zlib and PNG.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Crc32(u32);
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = match c & 1 {
+                1 => 0xedb8_8320 ^ (c >> 1),
+                _ => c >> 1,
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Crc32(!0)
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = CRC_TABLE[((self.0 ^ b as u32) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    pub(crate) fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+/// A writer that works out the checksums of the parts written through it.
+///
+/// Bytes after the contents of a part, i.e. its ending newline and padding, are only known once
+/// the part is written, so the last few bytes written are held back from the checksum until then.
+pub(crate) struct Tracker<W> {
+    inner: W,
+    enabled: bool,
+    // Number of bytes written so far.
+    offset: u64,
+    // Offset of the contents of the current part, and of the first byte not yet checksummed.
+    start: u64,
+    hashed: u64,
+    // Bytes that may still turn out not to belong to the contents of the current part.
+    held: VecDeque<u8>,
+    lag: usize,
+    crc: Crc32,
+}
+
+impl<W: Write> Tracker<W> {
+    pub(crate) fn new(inner: W, merger: &RsMerger<'_>) -> Self {
+        Tracker {
+            inner,
+            enabled: merger.index_placement().is_some(),
+            offset: 0,
+            start: u64::MAX,
+            hashed: 0,
+            held: VecDeque::new(),
+            lag: 0,
+            crc: Crc32::new(),
+        }
+    }
+
+    // Starts checksumming the part about to be written.
+    pub(crate) fn begin(&mut self, merger: &RsMerger<'_>, pos: PartPos) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let bom = merger.bom_before(pos).unwrap_or_default().len() as u64;
+        let before = merger.output_len(merger.padding_before(pos).unwrap_or_default())?;
+        let newline = merger.output_len(merger.ending_newline(false).unwrap_or_default())?;
+        let after = merger.output_len(merger.padding_after(pos).unwrap_or_default())?;
+        self.start = self.offset + bom + before;
+        self.hashed = self.start;
+        self.lag = (newline + after) as usize;
+        self.held.clear();
+        self.crc = Crc32::new();
+        Ok(())
+    }
+
+    // Finishes checksumming the part just written.
+    pub(crate) fn end(&mut self, range: &mut PartRange) {
+        // Nothing is written for a part whose header does not match.
+        if !self.enabled || range.mismatch {
+            return;
+        }
+
+        let rest = (self.start + range.written - self.hashed) as usize;
+        let (a, b) = self.held.as_slices();
+        let n = rest.min(a.len());
+        self.crc.update(&a[..n]);
+        self.crc.update(&b[..rest - n]);
+        range.checksum = Some(self.crc.finish());
+        self.start = u64::MAX;
+        self.held.clear();
+    }
+
+    // Returns the writer written through.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for Tracker<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        let end = self.offset + n as u64;
+        if self.enabled && end > self.start {
+            let from = self.start.saturating_sub(self.offset) as usize;
+            self.held.extend(&buf[from..n]);
+            if self.held.len() > self.lag {
+                let over = self.held.len() - self.lag;
+                let (a, b) = self.held.as_slices();
+                let k = over.min(a.len());
+                self.crc.update(&a[..k]);
+                self.crc.update(&b[..over - k]);
+                self.held.drain(..over);
+                self.hashed += over as u64;
+            }
+        }
+        self.offset = end;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,286 @@
+//! A minimal JSON reader and writer, enough for the documents this crate emits and checks.
+use std::io::{self, Write};
+
+/// A parsed JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    // Numbers are kept as written, as they may not fit in any primitive type.
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    // Members are kept in order, duplicates included.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    // Returns the value of the first member with the given key, if this is an object.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    // Returns this value as an unsigned integer, if it is one.
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    // Returns this value as a string, if it is one.
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a whole JSON document, surrounded by optional whitespace.
+///
+/// On failure, returns the offset of the first byte that is not valid JSON.
+pub(crate) fn parse(bytes: &[u8]) -> Result<Value, usize> {
+    let mut parser = Parser { bytes, pos: 0 };
+    parser.skip_ws();
+    let value = parser.value(0)?;
+    parser.skip_ws();
+    match parser.pos == bytes.len() {
+        true => Ok(value),
+        false => Err(parser.pos),
+    }
+}
+
+// Nesting deeper than this is rejected rather than risking a stack overflow.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> Parser<'b> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, b: u8) -> Result<(), usize> {
+        match self.peek() == Some(b) {
+            true => {
+                self.pos += 1;
+                Ok(())
+            }
+            false => Err(self.pos),
+        }
+    }
+
+    fn literal(&mut self, word: &[u8], value: Value) -> Result<Value, usize> {
+        match self.bytes[self.pos..].starts_with(word) {
+            true => {
+                self.pos += word.len();
+                Ok(value)
+            }
+            false => Err(self.pos),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, usize> {
+        if depth > MAX_DEPTH {
+            return Err(self.pos);
+        }
+
+        match self.peek() {
+            Some(b'n') => self.literal(b"null", Value::Null),
+            Some(b't') => self.literal(b"true", Value::Bool(true)),
+            Some(b'f') => self.literal(b"false", Value::Bool(false)),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_ws();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    self.skip_ws();
+                    items.push(self.value(depth + 1)?);
+                    self.skip_ws();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Value::Array(items));
+                        }
+                        _ => return Err(self.pos),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                self.skip_ws();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                }
+                loop {
+                    self.skip_ws();
+                    let key = self.string()?;
+                    self.skip_ws();
+                    self.eat(b':')?;
+                    self.skip_ws();
+                    members.push((key, self.value(depth + 1)?));
+                    self.skip_ws();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Value::Object(members));
+                        }
+                        _ => return Err(self.pos),
+                    }
+                }
+            }
+            _ => Err(self.pos),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, usize> {
+        let start = self.pos;
+        let digits = |p: &mut Self| {
+            let from = p.pos;
+            while let Some(b'0'..=b'9') = p.peek() {
+                p.pos += 1;
+            }
+            match p.pos > from {
+                true => Ok(()),
+                false => Err(p.pos),
+            }
+        };
+
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            _ => digits(self)?,
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            digits(self)?;
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            digits(self)?;
+        }
+
+        // Only ASCII was consumed.
+        let number = String::from_utf8_lossy(&self.bytes[start..self.pos]);
+        Ok(Value::Number(number.into_owned()))
+    }
+
+    fn string(&mut self) -> Result<String, usize> {
+        let start = self.pos;
+        self.eat(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            let b = self.peek().ok_or(self.pos)?;
+            match b {
+                b'"' => {
+                    self.pos += 1;
+                    break;
+                }
+                b'\\' => {
+                    self.pos += 1;
+                    let escaped = match self.peek().ok_or(self.pos)? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let at = self.pos - 1;
+                            self.pos += 1;
+                            let unit = self.hex4()?;
+                            let code = match unit {
+                                0xd800..=0xdbff => {
+                                    // A high surrogate must be followed by a low one.
+                                    self.literal(b"\\u", Value::Null).map_err(|_| at)?;
+                                    match self.hex4()? {
+                                        low @ 0xdc00..=0xdfff => {
+                                            0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00)
+                                        }
+                                        _ => return Err(at),
+                                    }
+                                }
+                                0xdc00..=0xdfff => return Err(at),
+                                unit => unit,
+                            };
+                            let c = char::from_u32(code).ok_or(at)?;
+                            let mut buf = [0; 4];
+                            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                            continue;
+                        }
+                        _ => return Err(self.pos),
+                    };
+                    self.pos += 1;
+                    let mut buf = [0; 4];
+                    out.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                }
+                0x00..=0x1f => return Err(self.pos),
+                _ => {
+                    out.push(b);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        // Strings that are not valid UTF-8 are reported at their opening quote.
+        String::from_utf8(out).map_err(|_| start)
+    }
+
+    fn hex4(&mut self) -> Result<u32, usize> {
+        let digits = self.bytes.get(self.pos..self.pos + 4).ok_or(self.pos)?;
+        let mut value = 0;
+        for (i, &d) in digits.iter().enumerate() {
+            let digit = (d as char).to_digit(16).ok_or(self.pos + i)?;
+            value = value * 16 + digit;
+        }
+        self.pos += 4;
+        Ok(value)
+    }
+}
+
+/// Writes the given string as a JSON string, quotes included.
+pub(crate) fn write_str<W: Write>(writer: &mut W, s: &str) -> io::Result<()> {
+    writer.write_all(b"\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => writer.write_all(b"\\\"")?,
+            '\\' => writer.write_all(b"\\\\")?,
+            '\n' => writer.write_all(b"\\n")?,
+            '\r' => writer.write_all(b"\\r")?,
+            '\t' => writer.write_all(b"\\t")?,
+            c if (c as u32) < 0x20 => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{}", c)?,
+        }
+    }
+    writer.write_all(b"\"")
+}
@@ -0,0 +1,522 @@
+//! Merging of JSON sources.
+use crate::error::{ErrorKind, Result};
+use crate::json;
+use crate::stream;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+/// A merger that merges JSON sources into one, either their top-level arrays into one array or
+/// their lines into one JSON Lines output, see [`JsonFormat`].
+///
+/// By default, each source must hold one JSON array, surrounded by optional whitespace. The
+/// elements of every array are streamed one by one into the merged array, in order, with the
+/// whitespace between them dropped. Empty arrays add nothing, and merging nothing but empty
+/// arrays writes `[]`.
+///
+/// Each element, or record, is checked to be valid JSON before it is written. Only one element
+/// is held in memory at a time, see [`buffer_limit`].
+///
+/// # Examples
+///
+/// ```
+/// use admerge::{JsonMerger, Result};
+///
+/// fn main() -> Result<()> {
+///     let r1: &[u8] = b"[1, {\"a\": [2]}]\n";
+///     let r2: &[u8] = b" [ ] ";
+///     let r3: &[u8] = b"[\"x\"]";
+///     let mut buf = Vec::new();
+///
+///     let merger = JsonMerger::new();
+///     let report = merger.merge_readers_into_with_report(vec![r1, r2, r3], &mut buf)?;
+///     assert_eq!(buf, b"[1,{\"a\": [2]},\"x\"]");
+///     assert_eq!(report.values(), 3);
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`buffer_limit`]: JsonMerger::buffer_limit
+#[derive(Clone, Debug)]
+pub struct JsonMerger {
+    format: JsonFormat,
+    lenient: bool,
+    buffer_limit: usize,
+}
+
+impl Default for JsonMerger {
+    fn default() -> Self {
+        JsonMerger {
+            format: JsonFormat::Array,
+            lenient: false,
+            buffer_limit: stream::DEFAULT_BUFFER_LIMIT,
+        }
+    }
+}
+
+/// The layout of JSON sources and of their merged output, see [`JsonMerger::format`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonFormat {
+    /// Each source holds one top-level array, merged into one array.
+    Array,
+    /// Each line of a source holds one JSON value, also known as JSON Lines or NDJSON.
+    ///
+    /// Lines may end with `\n` or `\r\n`. Blank lines are dropped, and every record merged is
+    /// written without surrounding whitespace and followed by exactly one `\n`.
+    Lines,
+}
+
+/// A summary of a finished JSON merge, returned by [`JsonMerger::merge_readers_into_with_report`]
+/// and [`JsonMerger::with_paths_with_report`].
+#[derive(Clone, Debug, Default)]
+pub struct JsonReport {
+    values: u64,
+    len: u64,
+    rejected: Vec<RejectedRecord>,
+}
+
+/// A record that was dropped for not being valid JSON, see [`JsonMerger::lenient`].
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RejectedRecord {
+    /// Index of the source holding the record among the given sources.
+    pub index: usize,
+    /// Line number of the record in its source, starting at 1.
+    pub line: u64,
+}
+
+impl JsonReport {
+    /// Returns the number of values merged.
+    pub fn values(&self) -> u64 {
+        self.values
+    }
+
+    /// Returns the records dropped for not being valid JSON, in merge order, see
+    /// [`JsonMerger::lenient`].
+    pub fn rejected(&self) -> &[RejectedRecord] {
+        &self.rejected
+    }
+
+    /// Returns the total number of bytes written.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if nothing was written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl JsonMerger {
+    /// Creates a new `JsonMerger` builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Configures the layout of sources and of the merged output. Defaults to
+    /// `JsonFormat::Array`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{JsonFormat, JsonMerger, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let r1: &[u8] = b"{\"id\": 1}\r\n\n  {\"id\": 2}";
+    ///     let r2: &[u8] = b"{\"id\": 3}\n";
+    ///     let mut buf = Vec::new();
+    ///
+    ///     let mut merger = JsonMerger::new();
+    ///     merger.format(JsonFormat::Lines);
+    ///     merger.merge_readers_into(vec![r1, r2], &mut buf)?;
+    ///     assert_eq!(buf, b"{\"id\": 1}\n{\"id\": 2}\n{\"id\": 3}\n");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn format(&mut self, format: JsonFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    /// Configures this merger to drop the records of `JsonFormat::Lines` sources that are not
+    /// valid JSON, listing them in [`JsonReport::rejected`], instead of failing. Defaults to
+    /// `false`.
+    ///
+    /// Sources in the `JsonFormat::Array` layout are always checked strictly, as there is no
+    /// telling where an invalid element ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{JsonFormat, JsonMerger, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let r1: &[u8] = b"1\n2 3\n4\n";
+    ///     let r2: &[u8] = b"{\n5\n";
+    ///     let mut buf = Vec::new();
+    ///
+    ///     let mut merger = JsonMerger::new();
+    ///     merger.format(JsonFormat::Lines).lenient(true);
+    ///     let report = merger.merge_readers_into_with_report(vec![r1, r2], &mut buf)?;
+    ///     assert_eq!(buf, b"1\n4\n5\n");
+    ///
+    ///     let lines: Vec<_> = report.rejected().iter().map(|r| (r.index, r.line)).collect();
+    ///     assert_eq!(lines, [(0, 2), (1, 1)]);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn lenient(&mut self, lenient: bool) -> &mut Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Configures the maximum length of one element or line, which is held in memory until
+    /// checked. Defaults to 8 MiB.
+    ///
+    /// Merges fail with an error variant of [`ErrorKind::BufferExceeded`] once an element or
+    /// line grows beyond this limit.
+    pub fn buffer_limit(&mut self, limit: usize) -> &mut Self {
+        self.buffer_limit = limit;
+        self
+    }
+
+    /// Merges the given sources into the given writer, reading each source once from start to
+    /// end.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given source vector is
+    /// empty;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidJson`] if a source does not hold a valid
+    /// JSON array, with the index of the source and the byte offset the problem was found at;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidRecord`] if a line of a JSON Lines source
+    /// is not valid JSON and this merger is not lenient, with the index of the source and the
+    /// line number;
+    ///
+    /// Returns an error variant of [`ErrorKind::BufferExceeded`] if an element or line is longer
+    /// than the buffer limit;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    ///
+    /// Everything merged until an error is found is left in the writer.
+    pub fn merge_readers_into<R, W>(&self, sources: Vec<R>, writer: &mut W) -> Result<()>
+    where
+        R: Read,
+        W: Write,
+    {
+        self.merge_readers_into_with_report(sources, writer)
+            .map(|_| ())
+    }
+
+    /// Does the same as [`merge_readers_into`], returning a [`JsonReport`] telling how many
+    /// values were merged and which records were rejected.
+    ///
+    /// [`merge_readers_into`]: JsonMerger::merge_readers_into
+    pub fn merge_readers_into_with_report<R, W>(
+        &self,
+        sources: Vec<R>,
+        writer: &mut W,
+    ) -> Result<JsonReport>
+    where
+        R: Read,
+        W: Write,
+    {
+        self.merge(sources.into_iter().map(Ok), writer)
+    }
+
+    /// Opens every file path given, and merges file contents into the given writer.
+    ///
+    /// Every path is checked before anything is written, but files are only opened when they are
+    /// about to be merged.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use admerge::{JsonMerger, Result};
+    /// use std::fs::File;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut file = File::create("merged.json")?;
+    ///     JsonMerger::new().with_paths(vec!["foo.json", "bar.json"], &mut file)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidPath`] if a given path does not point to
+    /// a regular file;
+    ///
+    /// See [`JsonMerger::merge_readers_into`] for the other errors returned.
+    pub fn with_paths<P, W>(&self, paths: Vec<P>, writer: &mut W) -> Result<()>
+    where
+        P: AsRef<Path>,
+        W: Write,
+    {
+        self.with_paths_with_report(paths, writer).map(|_| ())
+    }
+
+    /// Does the same as [`with_paths`], returning a [`JsonReport`] telling how many values were
+    /// merged and which records were rejected.
+    ///
+    /// [`with_paths`]: JsonMerger::with_paths
+    pub fn with_paths_with_report<P, W>(&self, paths: Vec<P>, writer: &mut W) -> Result<JsonReport>
+    where
+        P: AsRef<Path>,
+        W: Write,
+    {
+        if let Some(i) = paths.iter().position(|p| !p.as_ref().is_file()) {
+            return Err(ErrorKind::InvalidPath(i));
+        }
+
+        let files = paths.into_iter().map(|p| Ok(File::open(p)?));
+        self.merge(files, writer)
+    }
+}
+
+// Private APIs
+impl JsonMerger {
+    fn merge<R, I, W>(&self, sources: I, writer: &mut W) -> Result<JsonReport>
+    where
+        R: Read,
+        I: ExactSizeIterator<Item = Result<R>>,
+        W: Write,
+    {
+        if sources.len() == 0 {
+            return Err(ErrorKind::NothingPassed);
+        }
+
+        let mut report = JsonReport::default();
+        match self.format {
+            JsonFormat::Array => {
+                report.write(writer, b"[")?;
+                for (i, source) in sources.enumerate() {
+                    self.merge_array(i, &mut Source::new(source?), writer, &mut report)?;
+                }
+                report.write(writer, b"]")?;
+            }
+            JsonFormat::Lines => {
+                for (i, source) in sources.enumerate() {
+                    self.merge_lines(i, &mut Source::new(source?), writer, &mut report)?;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // Writes the records held by the lines of the source at the given index.
+    fn merge_lines<R, W>(
+        &self,
+        index: usize,
+        source: &mut Source<R>,
+        writer: &mut W,
+        report: &mut JsonReport,
+    ) -> Result<()>
+    where
+        R: Read,
+        W: Write,
+    {
+        let mut line = 0;
+        while let Some(bytes) = source.line(self.buffer_limit)? {
+            line += 1;
+            let record = trim(&bytes);
+            if record.is_empty() {
+                continue;
+            }
+
+            if json::parse(record).is_err() {
+                match self.lenient {
+                    true => report.rejected.push(RejectedRecord { index, line }),
+                    false => return Err(ErrorKind::InvalidRecord(index, line)),
+                }
+                continue;
+            }
+            report.write(writer, record)?;
+            report.write(writer, b"\n")?;
+            report.values += 1;
+        }
+
+        Ok(())
+    }
+
+    // Writes the elements of the array held by the source at the given index.
+    fn merge_array<R, W>(
+        &self,
+        index: usize,
+        source: &mut Source<R>,
+        writer: &mut W,
+        report: &mut JsonReport,
+    ) -> Result<()>
+    where
+        R: Read,
+        W: Write,
+    {
+        let invalid = |offset| ErrorKind::InvalidJson(index, offset);
+
+        source.skip_ws()?;
+        if source.peek()? != Some(b'[') {
+            return Err(invalid(source.offset));
+        }
+        source.bump();
+        source.skip_ws()?;
+
+        if source.peek()? == Some(b']') {
+            source.bump();
+        } else {
+            loop {
+                source.skip_ws()?;
+                let start = source.offset;
+                let element = source.element(self.buffer_limit)?;
+                json::parse(&element).map_err(|at| invalid(start + at as u64))?;
+                if report.values > 0 {
+                    report.write(writer, b",")?;
+                }
+                report.write(writer, &element)?;
+                report.values += 1;
+
+                match source.peek()? {
+                    Some(b',') => source.bump(),
+                    Some(b']') => {
+                        source.bump();
+                        break;
+                    }
+                    _ => return Err(invalid(source.offset)),
+                }
+            }
+        }
+
+        // Nothing but whitespace may follow the array.
+        source.skip_ws()?;
+        match source.peek()? {
+            None => Ok(()),
+            Some(_) => Err(invalid(source.offset)),
+        }
+    }
+}
+
+impl JsonReport {
+    fn write<W: Write>(&mut self, writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+        writer.write_all(bytes)?;
+        self.len += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+// A source read byte by byte, keeping track of the offset of the next byte.
+struct Source<R> {
+    reader: BufReader<R>,
+    offset: u64,
+}
+
+impl<R: Read> Source<R> {
+    fn new(reader: R) -> Self {
+        Source {
+            reader: BufReader::new(reader),
+            offset: 0,
+        }
+    }
+
+    // Returns the next byte without consuming it, or `None` at the end of the source.
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buf) => return Ok(buf.first().copied()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Consumes the byte just peeked.
+    fn bump(&mut self) {
+        self.reader.consume(1);
+        self.offset += 1;
+    }
+
+    // Reads one line, its terminator included, or returns `None` at the end of the source.
+    fn line(&mut self, limit: usize) -> Result<Option<Vec<u8>>> {
+        let mut line = Vec::new();
+        loop {
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if buf.is_empty() {
+                return Ok(Some(line).filter(|line| !line.is_empty()));
+            }
+
+            let (n, done) = match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => (i + 1, true),
+                None => (buf.len(), false),
+            };
+            if line.len() + n > limit {
+                return Err(ErrorKind::BufferExceeded(limit));
+            }
+            line.extend_from_slice(&buf[..n]);
+            self.reader.consume(n);
+            self.offset += n as u64;
+            if done {
+                return Ok(Some(line));
+            }
+        }
+    }
+
+    fn skip_ws(&mut self) -> io::Result<()> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+            self.bump();
+        }
+        Ok(())
+    }
+
+    // Reads one element of an array, up to the `,` or `]` that follows it outside of any string
+    // or nested value, with trailing whitespace dropped. The element is not checked.
+    fn element(&mut self, limit: usize) -> Result<Vec<u8>> {
+        let mut element = Vec::new();
+        let mut depth = 0usize;
+        let mut string = false;
+        let mut escaped = false;
+        while let Some(b) = self.peek()? {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' if string => escaped = true,
+                b'"' => string = !string,
+                _ if string => {}
+                b'[' | b'{' => depth += 1,
+                b',' | b']' if depth == 0 => break,
+                b']' | b'}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            if element.len() == limit {
+                return Err(ErrorKind::BufferExceeded(limit));
+            }
+            element.push(b);
+            self.bump();
+        }
+
+        let len = trim(&element).len();
+        element.truncate(len);
+        Ok(element)
+    }
+}
+
+// Strips the given bytes of leading and trailing JSON whitespace.
+fn trim(mut bytes: &[u8]) -> &[u8] {
+    while let [b' ' | b'\t' | b'\n' | b'\r', rest @ ..] = bytes {
+        bytes = rest;
+    }
+    while let [rest @ .., b' ' | b'\t' | b'\n' | b'\r'] = bytes {
+        bytes = rest;
+    }
+    bytes
+}
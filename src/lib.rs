@@ -2,7 +2,12 @@
 //!
 //! The main entities of this crate are [`RsMerger`] and [`FileMerger`]. The former works on any
 //! source that implemnts [`Read`] and [`Seek`] traits; the latter one is mostly identical with the
-//! former, but provides addtional methods to work with [`Path`]s and [`File`]s.
+//! former, but provides addtional methods to work with [`Path`]s and [`File`]s. Sources that only
+//! implement [`Read`], such as pipes or standard input, can be merged by [`merge_readers_into`].
+//! A [`MergedReader`] exposes the merged contents through [`Read`] and [`Seek`] without writing
+//! them anywhere. [`RsSplitter`] and [`FileSplitter`] go the other way around, cutting one source
+//! into many parts. [`JsonMerger`] merges the top-level arrays of JSON sources into one array,
+//! or the records of JSON Lines sources into one.
 //!
 //! # Behaviours
 //!
@@ -75,6 +80,17 @@
 //! ------------------------------
 //! ```
 //!
+//! # Features
+//!
+//! - `encoding_rs`: allows decoding sources from and encoding the output into other character
+//!   encodings than UTF-8, see `RsMerger::decode_sources`. The `Encoding` type is re-exported.
+//! - `regex`: provides [`Skip`] variants that skip up to the first or last match of a regular
+//!   expression, such as `Skip::UntilMatch`. The `Regex` type they take is re-exported.
+//! - `tempfile`: allows buffers that outgrow their limit to spill into anonymous temporary files,
+//!   see `RsMerger::spill_to_disk`.
+//! - `tokio`: provides `AsyncRsMerger` and `AsyncFileMerger`, asynchronous counterparts of
+//!   [`RsMerger`] and [`FileMerger`] that work with `tokio`'s I/O traits.
+//!
 //! [`Read`]: std::io::Read
 //! [`Seek`]: std::io::Seek
 //! [`Path`]: std::path::Path
@@ -85,9 +101,47 @@
 //! [`skip_tail`]: RsMerger::skip_tail
 //! [`force_ending_newline`]: RsMerger::force_ending_newline
 //! [`merge_sources_into`]: RsMerger::merge_sources_into
+//! [`merge_readers_into`]: RsMerger::merge_readers_into
+#[cfg(feature = "tokio")]
+mod async_merge;
+mod csv;
+mod custom;
+#[cfg(feature = "encoding_rs")]
+mod encoding;
 mod error;
+mod index;
+mod json;
+mod json_merge;
+mod lines;
 mod merge;
+mod normalize;
+mod plan;
+mod reader;
+mod report;
+mod rewrite;
+mod split;
+mod splitter;
+mod stream;
 mod util;
 
+#[cfg(feature = "tokio")]
+pub use async_merge::*;
+pub use csv::*;
+pub use custom::*;
+#[cfg(feature = "encoding_rs")]
+pub use encoding::*;
 pub use error::*;
+pub use index::*;
+pub use json_merge::*;
+pub use lines::*;
 pub use merge::*;
+pub use plan::*;
+pub use reader::*;
+pub use report::*;
+pub use split::*;
+pub use splitter::*;
+
+#[cfg(feature = "encoding_rs")]
+pub use encoding_rs::Encoding;
+#[cfg(feature = "regex")]
+pub use regex::bytes::Regex;
@@ -0,0 +1,249 @@
+//! Line-level filtering of the kept contents of each source.
+use crate::error::{ErrorKind, Result};
+use crate::merge::{PartRange, Terminator};
+use crate::stream::{self, Ends};
+
+use std::fmt;
+use std::io::{self, Write};
+use std::mem;
+use std::ops::Range;
+
+#[cfg(feature = "regex")]
+use regex::bytes::Regex;
+
+/// Controls which lines of each source are kept, see [`RsMerger::filter_lines`].
+///
+/// Lines end with the terminators given by [`RsMerger::line_terminator`], and are tested
+/// without them, so empty lines can be matched by an empty predicate or the regex `^$`. With the
+/// default [`Terminator::Lf`], a `\r` before the `\n` is left out as well.
+///
+/// [`RsMerger::filter_lines`]: crate::RsMerger::filter_lines
+/// [`RsMerger::line_terminator`]: crate::RsMerger::line_terminator
+#[non_exhaustive]
+#[derive(Clone, Copy)]
+pub enum LineFilter<'a> {
+    /// Keep only lines that contain the given byte pattern.
+    KeepContaining(&'a [u8]),
+    /// Drop lines that contain the given byte pattern.
+    DropContaining(&'a [u8]),
+    /// Keep only lines that match the given regex.
+    ///
+    /// This variant is only available if the `regex` feature is enabled.
+    #[cfg(feature = "regex")]
+    KeepMatching(&'a Regex),
+    /// Drop lines that match the given regex.
+    ///
+    /// This variant is only available if the `regex` feature is enabled.
+    #[cfg(feature = "regex")]
+    DropMatching(&'a Regex),
+    /// Keep only lines for which the given predicate returns `true`.
+    KeepIf(&'a dyn Fn(&[u8]) -> bool),
+    /// Drop lines for which the given predicate returns `true`.
+    DropIf(&'a dyn Fn(&[u8]) -> bool),
+}
+
+impl<'a> LineFilter<'a> {
+    // Returns `true` if the given line, stripped of its terminator, should be kept.
+    fn keeps(&self, line: &[u8]) -> bool {
+        match *self {
+            LineFilter::KeepContaining(bytes) => contains(line, bytes),
+            LineFilter::DropContaining(bytes) => !contains(line, bytes),
+            #[cfg(feature = "regex")]
+            LineFilter::KeepMatching(regex) => regex.is_match(line),
+            #[cfg(feature = "regex")]
+            LineFilter::DropMatching(regex) => !regex.is_match(line),
+            LineFilter::KeepIf(f) => f(line),
+            LineFilter::DropIf(f) => !f(line),
+        }
+    }
+}
+
+impl fmt::Debug for LineFilter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineFilter::KeepContaining(bytes) => {
+                f.debug_tuple("KeepContaining").field(bytes).finish()
+            }
+            LineFilter::DropContaining(bytes) => {
+                f.debug_tuple("DropContaining").field(bytes).finish()
+            }
+            #[cfg(feature = "regex")]
+            LineFilter::KeepMatching(regex) => f.debug_tuple("KeepMatching").field(regex).finish(),
+            #[cfg(feature = "regex")]
+            LineFilter::DropMatching(regex) => f.debug_tuple("DropMatching").field(regex).finish(),
+            LineFilter::KeepIf(_) => f.write_str("KeepIf(..)"),
+            LineFilter::DropIf(_) => f.write_str("DropIf(..)"),
+        }
+    }
+}
+
+fn contains(line: &[u8], bytes: &[u8]) -> bool {
+    bytes.is_empty() || line.windows(bytes.len()).any(|w| w == bytes)
+}
+
+/// Splits the kept contents of one part into lines and passes on the lines a [`LineFilter`] keeps.
+pub(crate) struct Sieve<'a> {
+    filter: LineFilter<'a>,
+    terminator: Terminator<'a>,
+    ends: Ends<'a>,
+    // The unfinished line at the end of what was fed so far.
+    line: Vec<u8>,
+    limit: usize,
+    // Source offset of the first byte of `line`.
+    offset: u64,
+    // Source ranges of the kept lines, adjacent lines coalesced; only recorded if requested.
+    runs: Option<Vec<Range<u64>>>,
+    written: u64,
+    dropped: u64,
+    endn: bool,
+}
+
+impl<'a> Sieve<'a> {
+    // Creates a sieve for contents starting at the given source offset.
+    pub(crate) fn new(
+        filter: LineFilter<'a>,
+        terminator: Terminator<'a>,
+        offset: u64,
+        limit: usize,
+    ) -> Self {
+        Sieve {
+            filter,
+            terminator,
+            ends: Ends::new(terminator),
+            line: Vec::new(),
+            limit,
+            offset,
+            runs: None,
+            written: 0,
+            dropped: 0,
+            endn: false,
+        }
+    }
+
+    // Makes this sieve record the source ranges of the kept lines.
+    pub(crate) fn record_runs(mut self) -> Self {
+        self.runs = Some(Vec::new());
+        self
+    }
+
+    // Feeds the next chunk of contents, writing completed lines that are kept.
+    pub(crate) fn feed<W: Write + ?Sized>(&mut self, chunk: &[u8], out: &mut W) -> Result<()> {
+        // Terminators end at or after the start of the chunk, as a `\r` held back by `Ends` is
+        // the last byte before it.
+        let base = self.offset + self.line.len() as u64;
+        let mut ends = Vec::new();
+        self.ends.feed(base, chunk, |end| {
+            ends.push((end - base) as usize);
+            true
+        });
+
+        let mut from = 0;
+        for to in ends {
+            let line = &chunk[from..to];
+            if self.line.is_empty() {
+                self.sift(line, out)?;
+            } else {
+                let mut buf = mem::take(&mut self.line);
+                buf.extend_from_slice(line);
+                self.sift(&buf, out)?;
+                buf.clear();
+                self.line = buf;
+            }
+            from = to;
+        }
+
+        self.line.extend_from_slice(&chunk[from..]);
+        if self.line.len() > self.limit {
+            return Err(ErrorKind::BufferExceeded(self.limit));
+        }
+        Ok(())
+    }
+
+    // Handles the last line, which may lack a terminator, and records the outcome in `range`.
+    pub(crate) fn finish<W: Write + ?Sized>(
+        &mut self,
+        range: &mut PartRange,
+        out: &mut W,
+    ) -> Result<()> {
+        if !self.line.is_empty() {
+            let line = mem::take(&mut self.line);
+            self.sift(&line, out)?;
+        }
+        range.endn = self.endn;
+        range.written = self.written;
+        range.dropped = self.dropped;
+        Ok(())
+    }
+
+    // Returns the recorded source ranges of the kept lines.
+    pub(crate) fn into_runs(self) -> Vec<Range<u64>> {
+        self.runs.unwrap_or_default()
+    }
+
+    // Wraps the given writer so that everything written to it goes through this sieve.
+    pub(crate) fn writer<'s, W: Write>(&'s mut self, writer: &'s mut W) -> SieveWriter<'s, 'a, W> {
+        SieveWriter {
+            sieve: self,
+            writer,
+        }
+    }
+
+    // Returns the given line without its terminator.
+    fn content<'l>(&self, line: &'l [u8]) -> &'l [u8] {
+        match (self.terminator, line) {
+            (Terminator::Lf | Terminator::Any, [rest @ .., b'\r', b'\n'])
+            | (Terminator::Crlf, [rest @ .., b'\r', b'\n'])
+            | (Terminator::Lf | Terminator::Any, [rest @ .., b'\n'])
+            | (Terminator::Cr | Terminator::Any, [rest @ .., b'\r']) => rest,
+            (Terminator::Delimiter(delimiter), _) if !delimiter.is_empty() => {
+                line.strip_suffix(delimiter).unwrap_or(line)
+            }
+            _ => line,
+        }
+    }
+
+    fn sift<W: Write + ?Sized>(&mut self, line: &[u8], out: &mut W) -> Result<()> {
+        if line.len() > self.limit {
+            return Err(ErrorKind::BufferExceeded(self.limit));
+        }
+
+        let start = self.offset;
+        self.offset += line.len() as u64;
+
+        let content = self.content(line);
+        if !self.filter.keeps(content) {
+            self.dropped += 1;
+            return Ok(());
+        }
+
+        out.write_all(line)?;
+        self.written += line.len() as u64;
+        self.endn = content.len() < line.len();
+        if let Some(runs) = &mut self.runs {
+            match runs.last_mut() {
+                Some(run) if run.end == start => run.end = self.offset,
+                _ => runs.push(start..self.offset),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A writer that passes everything written to it through a [`Sieve`].
+///
+/// Errors other than I/O ones are passed through `io::Error`, see `stream::recover`.
+pub(crate) struct SieveWriter<'s, 'a, W> {
+    sieve: &'s mut Sieve<'a>,
+    writer: &'s mut W,
+}
+
+impl<'s, 'a, W: Write> Write for SieveWriter<'s, 'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sieve.feed(buf, self.writer).map_err(stream::into_io)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
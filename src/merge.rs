@@ -1,13 +1,32 @@
 //! Definition of various mergers.
 
+use crate::csv::{self, ColumnRewriter, Columns, Csv};
+use crate::custom::{Side, SkipRule, SkipSource};
+#[cfg(feature = "encoding_rs")]
+use crate::encoding::{self, Decode, EncodeWriter};
 use crate::error::{ErrorKind, Result};
-use crate::util;
+use crate::index::{IndexPlacement, Tracker};
+use crate::lines::{LineFilter, Sieve};
+use crate::normalize::{self, Normalizer};
+use crate::plan::MergePlan;
+use crate::reader::MergedReader;
+use crate::report::MergeReport;
+use crate::rewrite::{Pipeline, RewriteReader};
+use crate::stream::{self, Buffered, Filter, HeadScan, Scanner, Spool, TailScan};
+use crate::util::{self, ReadSeek, Window};
 
+use std::borrow::Cow;
+use std::collections::VecDeque;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read, Seek, Write};
+use std::io::{self, Read, Seek, Write};
+use std::ops::Range;
 use std::path::Path;
 
 use byteseeker::ByteSeeker;
+#[cfg(feature = "encoding_rs")]
+use encoding_rs::Encoding;
+#[cfg(feature = "regex")]
+use regex::bytes::Regex;
 
 /// A Merger that can merge multiple sources that implement [`Read`] and [`Seek`] into one.
 ///
@@ -72,12 +91,36 @@ pub struct RsMerger<'a> {
     opts: RsMergerOptions<'a>,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 struct RsMergerOptions<'a> {
     skip_head: Option<Skip<'a>>,
     skip_tail: Option<Skip<'a>>,
     padding: Option<Pad<'a>>,
-    newline: Option<Newline>,
+    newline: Option<NewlineStyle>,
+    terminator: Terminator<'a>,
+    record_size: Option<usize>,
+    align_parts: Option<u64>,
+    align_output: Option<u64>,
+    image_size: Option<u64>,
+    fill: u8,
+    index: Option<IndexPlacement>,
+    bom: Option<Bom>,
+    line_filter: Option<LineFilter<'a>>,
+    csv: Option<Csv>,
+    // The fields of the header of the first source, once read by `RsMerger::resolved`.
+    csv_header: Option<Vec<Vec<u8>>>,
+    columns: Option<Columns<'a>>,
+    // The union of the columns of all sources, once read before merging.
+    union: Option<Vec<Vec<u8>>>,
+    missing: &'a [u8],
+    normalize: Option<NewlineStyle>,
+    #[cfg(feature = "encoding_rs")]
+    decode: Option<Decode<'a>>,
+    #[cfg(feature = "encoding_rs")]
+    encode: Option<&'static Encoding>,
+    buffer_limit: usize,
+    spill: bool,
+    max_open_files: usize,
 }
 
 /// Controls the skip behaviour when merging sources.
@@ -89,7 +132,17 @@ pub enum Skip<'a> {
     /// Keep the contents of the first part untouched (or the last part if passed by `skip_tail`),
     /// but skip a given number of bytes from the rest parts.
     BytesOnce(usize),
+    /// Skip a number of fixed-size records from each part, given as `Records(count, size)`.
+    ///
+    /// This skips `count * size` bytes, see [`RsMerger::check_records`] to make sure the
+    /// contents kept are made of whole records.
+    Records(usize, usize),
+    /// Keep the contents of the first part untouched (or the last part if passed by `skip_tail`),
+    /// but skip a given number of fixed-size records from the rest parts.
+    RecordsOnce(usize, usize),
     /// Skip a number of lines from each part.
+    ///
+    /// Lines end with the terminators given by [`RsMerger::line_terminator`], `\n` by default.
     Lines(usize),
     /// Keep the contents of the first part untouched (or the last part if passed by `skip_tail`),
     /// but skip a given number of lines from the rest parts.
@@ -103,6 +156,43 @@ pub enum Skip<'a> {
     /// Skip a sequence of bytes until reaching a given byte pattern from each part.
     /// The given byte pattern will not be skipped.
     Before(&'a [u8]),
+    /// Skip a byte order mark (see [`Bom`]) at the start of each part, if any.
+    ///
+    /// Chain it before other skips, e.g. `Skip::Chain(&[Skip::Bom, Skip::Lines(1)])`, so that they
+    /// apply to the contents after the BOM. Has no effect if passed by `skip_tail`.
+    Bom,
+    /// Keep the byte order mark of the first part, but skip the byte order marks of the rest
+    /// parts. Has no effect if passed by `skip_tail`.
+    BomOnce,
+    /// Apply the given skips one after another, each one to the contents left by the previous
+    /// ones.
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] when merging if a skip cannot be
+    /// applied to the contents left.
+    Chain(&'a [Skip<'a>]),
+    /// Skip the number of bytes given by a user-defined [`SkipRule`] from each part.
+    ///
+    /// As rules may look at any byte of a source, sources that only implement [`Read`] are
+    /// buffered in full before the rule is applied, see [`merge_readers_into`].
+    ///
+    /// [`merge_readers_into`]: RsMerger::merge_readers_into
+    Custom(&'a dyn SkipRule),
+    /// Skip a sequence of bytes until reaching the first match of a given regex from each part
+    /// (or from the last match to the end if passed by `skip_tail`).
+    /// The match will be skipped.
+    ///
+    /// Sources are searched line by line, so a match never spans a newline. Requires the `regex`
+    /// feature.
+    #[cfg(feature = "regex")]
+    UntilMatch(&'a Regex),
+    /// Skip a sequence of bytes until reaching the first match of a given regex from each part
+    /// (or from the end of the last match to the end if passed by `skip_tail`).
+    /// The match will not be skipped.
+    ///
+    /// Sources are searched line by line, so a match never spans a newline. Requires the `regex`
+    /// feature.
+    #[cfg(feature = "regex")]
+    BeforeMatch(&'a Regex),
 }
 
 /// Configures where padding will be filled when merging sources.
@@ -122,16 +212,144 @@ pub enum Pad<'a> {
     Custom(Option<&'a [u8]>, Option<&'a [u8]>, Option<&'a [u8]>),
 }
 
+/// A byte order mark, which may start sources exported by some tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bom {
+    /// The UTF-8 BOM, `EF BB BF`.
+    Utf8,
+    /// The UTF-16 little-endian BOM, `FF FE`.
+    Utf16Le,
+    /// The UTF-16 big-endian BOM, `FE FF`.
+    Utf16Be,
+}
+
+impl Bom {
+    /// Returns the BOM the given bytes start with, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::Bom;
+    ///
+    /// assert_eq!(Bom::detect(b"\xef\xbb\xbfheader"), Some(Bom::Utf8));
+    /// assert_eq!(Bom::detect(b"\xff\xfe\x68\x00"), Some(Bom::Utf16Le));
+    /// assert_eq!(Bom::detect(b"header"), None);
+    /// ```
+    pub fn detect(bytes: &[u8]) -> Option<Bom> {
+        match bytes {
+            [0xef, 0xbb, 0xbf, ..] => Some(Bom::Utf8),
+            [0xff, 0xfe, ..] => Some(Bom::Utf16Le),
+            [0xfe, 0xff, ..] => Some(Bom::Utf16Be),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes of this BOM.
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            Bom::Utf8 => b"\xef\xbb\xbf",
+            Bom::Utf16Le => b"\xff\xfe",
+            Bom::Utf16Be => b"\xfe\xff",
+        }
+    }
+}
+
 /// The style of a newline, either unix-style `LF` or dos-style `CRLF`.
-#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum Newline {
+    #[default]
     Lf,
     Crlf,
 }
 
-impl Default for Newline {
-    fn default() -> Self {
-        Newline::Lf
+impl Newline {
+    // Returns the bytes of this newline.
+    pub(crate) fn as_bytes(self) -> &'static [u8] {
+        match self {
+            Newline::Lf => b"\n",
+            Newline::Crlf => b"\r\n",
+        }
+    }
+}
+
+/// The newline style to force or normalise to, either a fixed [`Newline`] or one detected from
+/// the sources.
+///
+/// Every [`Newline`] converts into a fixed style, so `Newline::Lf` may be given wherever a
+/// `NewlineStyle` is expected.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewlineStyle {
+    /// The given newline.
+    Fixed(Newline),
+    /// The dominant style among the line endings of the first source, `CRLF` if most of them
+    /// are `\r\n` and `LF` otherwise.
+    ///
+    /// The first source is read once more to detect the style, and [`merge_readers_into`]
+    /// buffers it to do so. The detected style is given by [`MergeReport::newline`].
+    ///
+    /// [`merge_readers_into`]: RsMerger::merge_readers_into
+    /// [`MergeReport::newline`]: crate::MergeReport::newline
+    Auto,
+}
+
+impl NewlineStyle {
+    // Returns the fixed newline of this style, or `None` until `Auto` is resolved, see
+    // `RsMerger::resolved`.
+    pub(crate) fn fixed(self) -> Option<Newline> {
+        match self {
+            NewlineStyle::Fixed(newline) => Some(newline),
+            NewlineStyle::Auto => None,
+        }
+    }
+}
+
+impl From<Newline> for NewlineStyle {
+    fn from(newline: Newline) -> Self {
+        NewlineStyle::Fixed(newline)
+    }
+}
+
+/// The line terminators that end lines, see [`RsMerger::line_terminator`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Terminator<'a> {
+    /// Lines end with `\n`, so `\r\n` ends a line as well.
+    #[default]
+    Lf,
+    /// Lines end with `\r\n`. A `\n` or `\r` on its own does not end a line.
+    Crlf,
+    /// Lines end with `\r`, as in classic Mac OS files.
+    Cr,
+    /// Lines end with any of `\r\n`, `\n` or a lone `\r`.
+    Any,
+    /// Records end with the given byte sequence, such as `b"\0"` or the ASCII record separator
+    /// `b"\x1e"`. An empty delimiter ends no record.
+    ///
+    /// Occurrences are matched from left to right, so `\0\0\0` holds one `\0\0` delimiter.
+    Delimiter(&'a [u8]),
+}
+
+impl<'a> Terminator<'a> {
+    // Returns the length of the longest terminator of this set.
+    pub(crate) fn width(self) -> usize {
+        match self {
+            Terminator::Lf | Terminator::Cr => 1,
+            Terminator::Crlf | Terminator::Any => 2,
+            Terminator::Delimiter(bytes) => bytes.len(),
+        }
+    }
+
+    // Returns `true` if the given bytes end with a terminator of this set.
+    pub(crate) fn ends(self, bytes: &[u8]) -> bool {
+        match self {
+            Terminator::Lf => bytes.ends_with(b"\n"),
+            Terminator::Crlf => bytes.ends_with(b"\r\n"),
+            Terminator::Cr => bytes.ends_with(b"\r"),
+            Terminator::Any => bytes.ends_with(b"\n") || bytes.ends_with(b"\r"),
+            Terminator::Delimiter(delimiter) => !delimiter.is_empty() && bytes.ends_with(delimiter),
+        }
     }
 }
 
@@ -142,6 +360,28 @@ impl<'a> Default for RsMerger<'a> {
             skip_tail: None,
             padding: None,
             newline: None,
+            terminator: Terminator::Lf,
+            record_size: None,
+            align_parts: None,
+            align_output: None,
+            image_size: None,
+            fill: 0,
+            index: None,
+            bom: None,
+            line_filter: None,
+            csv: None,
+            csv_header: None,
+            columns: None,
+            union: None,
+            missing: b"",
+            normalize: None,
+            #[cfg(feature = "encoding_rs")]
+            decode: None,
+            #[cfg(feature = "encoding_rs")]
+            encode: None,
+            buffer_limit: stream::DEFAULT_BUFFER_LIMIT,
+            spill: false,
+            max_open_files: 1,
         };
         RsMerger { opts }
     }
@@ -175,6 +415,9 @@ impl<'a> RsMerger<'a> {
 
     /// Configures this merger to skip partial of contents from the head of each source.
     ///
+    /// Calling this method again replaces the previous skip. To apply several skips one after
+    /// another, pass a [`Skip::Chain`].
+    ///
     /// # Examples
     ///
     /// Keeps the first given source untouched, but skips first line from the rest sources.
@@ -211,6 +454,9 @@ impl<'a> RsMerger<'a> {
 
     /// Configures this merger to skip partial of contents from the tail of each source.
     ///
+    /// Calling this method again replaces the previous skip. To apply several skips one after
+    /// another, pass a [`Skip::Chain`].
+    ///
     /// # Examples
     ///
     /// Keeps the last given source untouched, but skips last line from the rest sources.
@@ -283,9 +529,168 @@ impl<'a> RsMerger<'a> {
         self
     }
 
+    /// Configures this merger to start each source at a multiple of the given alignment, in
+    /// bytes, of the merged output.
+    ///
+    /// Fill bytes, see [`fill_byte`], are written before each source as needed, ahead of its
+    /// padding. The offsets each source landed at are given by the returned [`MergeReport`].
+    /// An alignment of zero or one disables alignment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, Result};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let c1 = Cursor::new(&b"boot"[..]);
+    ///     let c2 = Cursor::new(&b"kernel"[..]);
+    ///     let c3 = Cursor::new(&b"rootfs"[..]);
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.align_parts(8).fill_byte(0xff);
+    ///
+    ///     // Merges sources into one.
+    ///     let report = merger.merge_sources_into_with_report(vec![c1, c2, c3], &mut buf)?;
+    ///     assert_eq!(buf, b"boot\xff\xff\xff\xffkernel\xff\xffrootfs");
+    ///     assert_eq!(report.parts()[2].output, 16..22);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`fill_byte`]: RsMerger::fill_byte
+    pub fn align_parts(&mut self, alignment: u64) -> &mut Self {
+        self.opts.align_parts = Some(alignment);
+        self
+    }
+
+    /// Configures this merger to end the merged output at a multiple of the given alignment, in
+    /// bytes, by writing fill bytes after the last source. An alignment of zero or one disables
+    /// alignment.
+    ///
+    /// See [`align_parts`] and [`fill_byte`].
+    ///
+    /// [`align_parts`]: RsMerger::align_parts
+    /// [`fill_byte`]: RsMerger::fill_byte
+    pub fn align_output(&mut self, alignment: u64) -> &mut Self {
+        self.opts.align_output = Some(alignment);
+        self
+    }
+
+    /// Configures this merger to write fill bytes after the last source until the merged output
+    /// is exactly the given size, in bytes, as a fixed-size image would.
+    ///
+    /// Returns an error variant of [`ErrorKind::ImageOverflow`] when merging if the merged
+    /// output would be larger than the given size. As sources are written as they come, the
+    /// ones merged before the overflow was found have already been written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, ErrorKind, Result};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.align_parts(4).image_size(12);
+    ///
+    ///     // Merges sources into one.
+    ///     let sources = vec![Cursor::new("ab"), Cursor::new("cde")];
+    ///     merger.merge_sources_into(sources, &mut buf)?;
+    ///     assert_eq!(buf, b"ab\0\0cde\0\0\0\0\0");
+    ///
+    ///     // The merged output does not fit.
+    ///     let sources = vec![Cursor::new("abcdefghij"), Cursor::new("k")];
+    ///     let result = merger.merge_sources_into(sources, &mut Vec::new());
+    ///     assert!(matches!(result, Err(ErrorKind::ImageOverflow(12))));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn image_size(&mut self, size: u64) -> &mut Self {
+        self.opts.image_size = Some(size);
+        self
+    }
+
+    /// Configures the byte written to align sources and to fill images, see [`align_parts`],
+    /// [`align_output`] and [`image_size`]. Defaults to `0`.
+    ///
+    /// Fill bytes are written as they are, even if the merged output is transcoded.
+    ///
+    /// [`align_parts`]: RsMerger::align_parts
+    /// [`align_output`]: RsMerger::align_output
+    /// [`image_size`]: RsMerger::image_size
+    pub fn fill_byte(&mut self, fill: u8) -> &mut Self {
+        self.opts.fill = fill;
+        self
+    }
+
+    /// Configures this merger to work out an [`Index`] of the merged output, listing the offset,
+    /// length and CRC-32 of the contents of each source.
+    ///
+    /// The index is returned by [`MergeReport::index`], and appended to the merged output as
+    /// well with [`IndexPlacement::Footer`]. The footer comes after every other byte, including
+    /// the fill bytes given by [`image_size`]. As the footer is only known once the merge is
+    /// done, [`plan`] and [`merged_reader`] fail with [`ErrorKind::Unsupported`] once it is
+    /// configured.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, Index, IndexPlacement, Pad, Result};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let c1 = Cursor::new("first");
+    ///     let c2 = Cursor::new("second");
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.pad_with(Pad::Between(b"\n"));
+    ///     merger.emit_index(IndexPlacement::Sidecar);
+    ///
+    ///     // Merges sources into one, and writes the index aside.
+    ///     let report = merger.merge_sources_into_with_report(vec![c1, c2], &mut buf)?;
+    ///     let mut csv = Vec::new();
+    ///     report.index().unwrap().write_csv(&mut csv)?;
+    ///     assert_eq!(
+    ///         std::str::from_utf8(&csv).unwrap(),
+    ///         "name,offset,len,crc32\n0,0,5,2456940119\n1,6,6,3055489385\n"
+    ///     );
+    ///
+    ///     // Reads the index back.
+    ///     let index = Index::from_csv(&csv)?;
+    ///     assert_eq!(index.entries()[1].offset, 6);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`Index`]: crate::Index
+    /// [`image_size`]: RsMerger::image_size
+    /// [`plan`]: RsMerger::plan
+    /// [`merged_reader`]: RsMerger::merged_reader
+    pub fn emit_index(&mut self, placement: IndexPlacement) -> &mut Self {
+        self.opts.index = Some(placement);
+        self
+    }
+
     /// Configures this merger to force the presence of ending newline after each source.
     ///
-    /// Noting that ending newlines are given after sources, not after paddings.
+    /// Noting that ending newlines are given after sources, not after paddings. A source is
+    /// considered to end with a newline if it ends with one of the [`line_terminator`]s. With a
+    /// [`Terminator::Delimiter`] configured, the delimiter itself is appended in place of the
+    /// given newline to sources that do not end with it.
+    ///
+    /// Either a [`Newline`] or [`NewlineStyle::Auto`] may be given, the latter to use the
+    /// dominant style of the first source.
     ///
     /// # Examples
     ///
@@ -314,139 +719,1722 @@ impl<'a> RsMerger<'a> {
     ///     Ok(())
     /// }
     /// ```
-    pub fn force_ending_newline(&mut self, newline: Newline) -> &mut Self {
-        self.opts.newline = Some(newline);
+    ///
+    /// [`line_terminator`]: RsMerger::line_terminator
+    pub fn force_ending_newline(&mut self, newline: impl Into<NewlineStyle>) -> &mut Self {
+        self.opts.newline = Some(newline.into());
         self
     }
 
-    /// Merges the given sources into the given writer according to the given configurations.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given source vector is
-    /// empty;
-    ///
-    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
-    /// applied to the given sources;
+    /// Configures the line terminators that end lines of each source. Defaults to
+    /// [`Terminator::Lf`].
     ///
-    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    /// The terminators are used by [`Skip::Lines`] and [`Skip::LinesOnce`], on both sides, and
+    /// to tell whether a source already ends with a newline when [`force_ending_newline`] is set.
+    /// Records of any other shape, such as NUL-separated ones, are handled by
+    /// [`Terminator::Delimiter`].
     ///
     /// # Examples
     ///
     /// ```
-    /// use admerge::{RsMerger, Skip, Pad, Newline, Result};
+    /// use admerge::{RsMerger, Newline, Skip, Terminator, Result};
     /// use std::io::Cursor;
     ///
     /// fn main() -> Result<()> {
-    ///     // Cursor implements `Read` and `Seek`.
-    ///     let mut c1 = Cursor::new(" record 1\n date created: 2000/01/01\n");
-    ///     let mut c2 = Cursor::new(" record 2\n date created: 2000/01/01\n");
-    ///     let mut c3 = Cursor::new(" record 3\n date created: 2000/01/01\n");
+    ///     let c1 = Cursor::new("header\r 1\r");
+    ///     let c2 = Cursor::new("header\r\n 2\r\n footer");
     ///     let mut buf = Vec::new();
     ///
     ///     // Configures merger.
     ///     let mut merger = RsMerger::new();
+    ///     merger.line_terminator(Terminator::Any);
+    ///     merger.skip_head(Skip::LinesOnce(1));
     ///     merger.skip_tail(Skip::LinesOnce(1));
-    ///     merger.pad_with(Pad::Before(b"header\n"));
     ///     merger.force_ending_newline(Newline::Lf);
     ///
     ///     // Merges sources into one.
-    ///     merger.merge_sources_into(vec![&mut c1, &mut c2, &mut c3], &mut buf)?;
+    ///     merger.merge_sources_into(vec![c1, c2], &mut buf)?;
     ///     assert_eq!(
     ///         std::str::from_utf8(&buf).unwrap(),
-    ///         "header\n record 1\n record 2\n record 3\n date created: 2000/01/01\n"
+    ///         "header\r 2\r\n footer\n"
     ///     );
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub fn merge_sources_into<RS, W>(
-        &self,
-        mut sources: Vec<RS>,
-        writer: &mut W,
-    ) -> Result<()>
-    where
-        RS: Read + Seek,
-        W: Write,
-    {
-        let len = sources.len();
-        if len == 0 {
-            return Err(ErrorKind::NothingPassed);
-        }
+    ///
+    /// [`force_ending_newline`]: RsMerger::force_ending_newline
+    pub fn line_terminator(&mut self, terminator: Terminator<'a>) -> &mut Self {
+        self.opts.terminator = terminator;
+        self
+    }
 
-        // Merge first part.
-        self.write_contents(&mut sources[0], writer, PartPos::Start)?;
-        // Merge inner parts.
-        for mut source in sources.iter_mut().take(len - 1).skip(1) {
-            self.write_contents(&mut source, writer, PartPos::Inside)?;
-        }
-        // Merge last part.
-        if len > 1 {
-            self.write_contents(&mut sources[len - 1], writer, PartPos::End)?;
-        }
+    /// Configures this merger to check that the contents kept from each source are made of
+    /// whole records of the given size, in bytes.
+    ///
+    /// The check applies to the contents left by the skips, before paddings and ending newlines
+    /// are added. A size of zero disables the check.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, ErrorKind, Skip, Result};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let c1 = Cursor::new(&b"HDR\x00\x01\x00\x02"[..]);
+    ///     let c2 = Cursor::new(&b"HDR\x00\x03\x00"[..]);
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.skip_head(Skip::Records(1, 3));
+    ///     merger.check_records(2);
+    ///
+    ///     // The second source ends with a partial record.
+    ///     let result = merger.merge_sources_into(vec![c1, c2], &mut buf);
+    ///     assert!(matches!(result, Err(ErrorKind::PartialRecord(1))));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn check_records(&mut self, size: usize) -> &mut Self {
+        self.opts.record_size = Some(size);
+        self
+    }
 
-        Ok(())
+    /// Configures this merger to treat sources as CSV in the given format, keeping the header of
+    /// the first source only.
+    ///
+    /// The header is the first record of a source, which may span several lines if a quoted
+    /// field holds line terminators. The header of every other source is skipped once checked
+    /// against the header of the first source: both must hold the same fields, though they may
+    /// be quoted or terminated differently. Empty sources are left alone.
+    ///
+    /// Sources are read in full before their headers are skipped, so [`merge_readers_into`]
+    /// buffers each source, see [`buffer_limit`] and `spill_to_disk`.
+    ///
+    /// # Errors
+    ///
+    /// Merges fail with an error variant of [`ErrorKind::HeaderMismatch`] once they reach a
+    /// source whose header does not match, without writing anything from that source.
+    ///
+    /// Merges fail with an error variant of [`ErrorKind::InvalidSkip`] if [`skip_head`] or
+    /// [`skip_tail`] is configured as well, as the header takes the place of the head skip and
+    /// the tail skip could cut a quoted field in half.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{Csv, ErrorKind, RsMerger, Result};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let c1 = Cursor::new("id,\"long\nname\"\n1,foo\n");
+    ///     let c2 = Cursor::new("id,\"long\nname\"\n2,bar\n");
+    ///     let c3 = Cursor::new("id,name\n3,baz\n");
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.csv_headers(Csv::new());
+    ///
+    ///     // Merges sources into one.
+    ///     merger.merge_sources_into(vec![c1.clone(), c2.clone()], &mut buf)?;
+    ///     assert_eq!(
+    ///         std::str::from_utf8(&buf).unwrap(),
+    ///         "id,\"long\nname\"\n1,foo\n2,bar\n"
+    ///     );
+    ///
+    ///     // The third source has another header.
+    ///     let result = merger.merge_sources_into(vec![c1, c2, c3], &mut Vec::new());
+    ///     assert!(matches!(result, Err(ErrorKind::HeaderMismatch(2))));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`skip_head`]: RsMerger::skip_head
+    /// [`skip_tail`]: RsMerger::skip_tail
+    /// [`merge_readers_into`]: RsMerger::merge_readers_into
+    /// [`buffer_limit`]: RsMerger::buffer_limit
+    pub fn csv_headers(&mut self, csv: Csv) -> &mut Self {
+        self.opts.csv = Some(csv);
+        self
     }
-}
 
-// Indicates the relative position.
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-enum PartPos {
-    Start,
-    Inside,
-    End,
-}
+    /// Configures this merger to rewrite the records of CSV sources so that they hold the given
+    /// columns, in order.
+    ///
+    /// The fields of each record are matched to columns by the names in the header of its
+    /// source, and columns a source lacks are filled with the default set by
+    /// [`missing_fields`]. The merged output starts with one header listing the columns, and
+    /// sources are then rewritten record by record. Sources are read in the format given to
+    /// [`csv_headers`], or as comma-separated values otherwise.
+    ///
+    /// [`Columns::Union`] reads the header of every source before merging, so
+    /// [`merge_readers_into`] fails with [`ErrorKind::Unsupported`] for it. As offsets in
+    /// rewritten contents do not map back to offsets in the sources, [`plan`] and
+    /// [`merged_reader`] fail with [`ErrorKind::Unsupported`] once columns are configured.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{Columns, RsMerger, Result};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let c1 = Cursor::new("id,name\n1,foo\n");
+    ///     let c2 = Cursor::new("name,id,size\nbar,2,10\n");
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.csv_columns(Columns::Union);
+    ///     merger.missing_fields(b"0");
+    ///
+    ///     // Merges sources into one.
+    ///     merger.merge_sources_into(vec![c1, c2], &mut buf)?;
+    ///     assert_eq!(
+    ///         std::str::from_utf8(&buf).unwrap(),
+    ///         "id,name,size\n1,foo,0\n2,bar,10\n"
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`missing_fields`]: RsMerger::missing_fields
+    /// [`csv_headers`]: RsMerger::csv_headers
+    /// [`merge_readers_into`]: RsMerger::merge_readers_into
+    /// [`plan`]: RsMerger::plan
+    /// [`merged_reader`]: RsMerger::merged_reader
+    pub fn csv_columns(&mut self, columns: Columns<'a>) -> &mut Self {
+        self.opts.columns = Some(columns);
+        self
+    }
 
-// Private methods
-impl<'a> RsMerger<'a> {
-    // Writes the contents (entire or partial) of one part into the writer.
-    fn write_contents<RS, W>(
-        &self,
-        reader: &mut RS,
-        writer: &mut W,
-        pos: PartPos,
-    ) -> Result<()>
-    where
-        RS: Read + Seek,
-        W: Write,
+    /// Sets the field that fills columns a CSV source lacks, see [`csv_columns`]. Defaults to an
+    /// empty field.
+    ///
+    /// [`csv_columns`]: RsMerger::csv_columns
+    pub fn missing_fields(&mut self, field: &'a [u8]) -> &mut Self {
+        self.opts.missing = field;
+        self
+    }
+
+    /// Configures this merger to rewrite every line ending into the given newline.
+    ///
+    /// Line endings of sources (`\n`, `\r\n` or a lone `\r`) are rewritten as sources are read,
+    /// so skips and line filters see the rewritten contents. Line endings of paddings and the
+    /// newlines given by [`force_ending_newline`] are rewritten as well.
+    ///
+    /// Line endings may be rewritten into the dominant style of the first source by giving
+    /// [`NewlineStyle::Auto`].
+    ///
+    /// As offsets in rewritten contents do not map back to offsets in the sources, [`plan`] and
+    /// [`merged_reader`] fail with [`ErrorKind::Unsupported`] once normalisation is configured.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, Newline, Pad, Result};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let c1 = Cursor::new(" line 1\r\n line 2\n");
+    ///     let c2 = Cursor::new(" line 3\r line 4");
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.normalize_newlines(Newline::Crlf);
+    ///     merger.pad_with(Pad::Between(b"--\n"));
+    ///     merger.force_ending_newline(Newline::Lf);
+    ///
+    ///     // Merges sources into one.
+    ///     merger.merge_sources_into(vec![c1, c2], &mut buf)?;
+    ///     assert_eq!(
+    ///         std::str::from_utf8(&buf).unwrap(),
+    ///         " line 1\r\n line 2\r\n--\r\n line 3\r\n line 4\r\n"
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`force_ending_newline`]: RsMerger::force_ending_newline
+    /// [`plan`]: RsMerger::plan
+    /// [`merged_reader`]: RsMerger::merged_reader
+    pub fn normalize_newlines(&mut self, newline: impl Into<NewlineStyle>) -> &mut Self {
+        self.opts.normalize = Some(newline.into());
+        self
+    }
+
+    /// Configures this merger to write the given byte order mark at the top of the output.
+    ///
+    /// The BOM is written before any padding. Combine it with [`Skip::Bom`] so that the BOMs of
+    /// the sources themselves do not end up in the middle of the output.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, Bom, Skip, Result};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let c1 = Cursor::new(&b"\xef\xbb\xbfheader\n 1\n"[..]);
+    ///     let c2 = Cursor::new(&b"header\n 2\n"[..]);
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.skip_head(Skip::Chain(&[Skip::Bom, Skip::LinesOnce(1)]));
+    ///     merger.emit_bom(Bom::Utf8);
+    ///
+    ///     // Merges sources into one.
+    ///     merger.merge_sources_into(vec![c1, c2], &mut buf)?;
+    ///     assert_eq!(buf, b"\xef\xbb\xbfheader\n 1\n 2\n");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn emit_bom(&mut self, bom: Bom) -> &mut Self {
+        self.opts.bom = Some(bom);
+        self
+    }
+
+    /// Configures this merger to keep or drop lines of each source, wherever they are.
+    ///
+    /// The filter is applied to what is left after [`skip_head`] and [`skip_tail`], to lines
+    /// ended by the [`line_terminator`]s, and original line endings are preserved. With a filter
+    /// set, [`force_ending_newline`] looks at whether the filtered contents end with a newline,
+    /// rather than the source.
+    ///
+    /// Every line must be held in memory while it is tested, so a line longer than the
+    /// [`buffer_limit`] makes merging fail with [`ErrorKind::BufferExceeded`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, LineFilter, Newline, Result};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let c1 = Cursor::new("INFO start\nDEBUG x = 1\r\nINFO done\nDEBUG exit");
+    ///     let c2 = Cursor::new("DEBUG y = 2\nINFO again\n");
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.filter_lines(LineFilter::DropContaining(b"DEBUG"));
+    ///     merger.force_ending_newline(Newline::Lf);
+    ///
+    ///     // Merges sources into one.
+    ///     merger.merge_sources_into(vec![c1, c2], &mut buf)?;
+    ///     assert_eq!(
+    ///         std::str::from_utf8(&buf).unwrap(),
+    ///         "INFO start\nINFO done\nINFO again\n"
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`skip_head`]: RsMerger::skip_head
+    /// [`skip_tail`]: RsMerger::skip_tail
+    /// [`line_terminator`]: RsMerger::line_terminator
+    /// [`force_ending_newline`]: RsMerger::force_ending_newline
+    /// [`buffer_limit`]: RsMerger::buffer_limit
+    pub fn filter_lines(&mut self, filter: LineFilter<'a>) -> &mut Self {
+        self.opts.line_filter = Some(filter);
+        self
+    }
+
+    /// Configures this merger to decode sources into UTF-8 before anything else is done.
+    ///
+    /// Skips, line filters, paddings and ending newlines then operate on decoded text, so their
+    /// byte patterns are given in UTF-8. Decoding is streamed while contents are copied. Unless
+    /// [`encode_output`] is also set, the merged output is UTF-8.
+    ///
+    /// As offsets in decoded text do not map back to offsets in the sources, [`plan`] and
+    /// [`merged_reader`] fail with [`ErrorKind::Unsupported`] once transcoding is configured.
+    ///
+    /// This method is only available if the `encoding_rs` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, Decode, Skip, Result};
+    /// use encoding_rs::{UTF_16LE, UTF_8, WINDOWS_1252};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let c1 = Cursor::new(b"name\ncaf\xe9\n".to_vec());
+    ///     let c2 = Cursor::new(b"\xff\xfen\0a\0m\0e\0\n\0n\0a\0\xef\0v\0e\0\n\0".to_vec());
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let encodings = [WINDOWS_1252, UTF_16LE];
+    ///     let mut merger = RsMerger::new();
+    ///     merger.decode_sources(Decode::Each(&encodings));
+    ///     merger.skip_head(Skip::LinesOnce(1));
+    ///
+    ///     // Merges sources into one.
+    ///     merger.merge_sources_into(vec![c1, c2], &mut buf)?;
+    ///     assert_eq!(std::str::from_utf8(&buf).unwrap(), "name\ncafé\nnaïve\n");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`encode_output`]: RsMerger::encode_output
+    /// [`plan`]: RsMerger::plan
+    /// [`merged_reader`]: RsMerger::merged_reader
+    #[cfg(feature = "encoding_rs")]
+    pub fn decode_sources(&mut self, decode: Decode<'a>) -> &mut Self {
+        self.opts.decode = Some(decode);
+        self
+    }
+
+    /// Configures this merger to encode the merged output into the given encoding.
+    ///
+    /// Everything but the BOM given by [`emit_bom`] is encoded, including paddings. Sources are
+    /// decoded as if [`decode_sources`] was given `Decode::Auto(UTF_8)`, unless configured
+    /// otherwise. Characters the given encoding cannot represent are replaced by HTML numeric
+    /// character references.
+    ///
+    /// This method is only available if the `encoding_rs` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, Bom, Pad, Result};
+    /// use encoding_rs::UTF_16LE;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let r1: &[u8] = "é".as_bytes();
+    ///     let r2: &[u8] = b"\xef\xbb\xbfx";
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.encode_output(UTF_16LE).emit_bom(Bom::Utf16Le);
+    ///     merger.pad_with(Pad::Between(b"|"));
+    ///
+    ///     // Merges sources into one.
+    ///     merger.merge_readers_into(vec![r1, r2], &mut buf)?;
+    ///     assert_eq!(buf, b"\xff\xfe\xe9\0|\0x\0");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`emit_bom`]: RsMerger::emit_bom
+    /// [`decode_sources`]: RsMerger::decode_sources
+    #[cfg(feature = "encoding_rs")]
+    pub fn encode_output(&mut self, encoding: &'static Encoding) -> &mut Self {
+        self.opts.encode = Some(encoding);
+        self
+    }
+
+    /// Configures the maximum number of bytes [`merge_readers_into`] may hold in memory while
+    /// looking behind for the tail skip of a source. Defaults to 8 MiB.
+    ///
+    /// Bytes that may still be cut by [`skip_tail`] cannot be written until the end of the
+    /// source is reached, so they are held back. For example, [`Skip::Bytes(n)`] holds back
+    /// `n` bytes, and [`Skip::Lines(n)`] holds back the last `n` lines read so far.
+    ///
+    /// This option has no effect on [`merge_sources_into`], which seeks instead, except that
+    /// lines tested by [`filter_lines`], and lines searched by `Skip::UntilMatch` and
+    /// `Skip::BeforeMatch`, must fit within this limit in every merging method.
+    ///
+    /// [`merge_readers_into`]: RsMerger::merge_readers_into
+    /// [`merge_sources_into`]: RsMerger::merge_sources_into
+    /// [`skip_tail`]: RsMerger::skip_tail
+    /// [`filter_lines`]: RsMerger::filter_lines
+    /// [`Skip::Bytes(n)`]: Skip::Bytes
+    /// [`Skip::Lines(n)`]: Skip::Lines
+    pub fn buffer_limit(&mut self, limit: usize) -> &mut Self {
+        self.opts.buffer_limit = limit;
+        self
+    }
+
+    /// Configures [`merge_readers_into`] to spill held back bytes into an anonymous temporary
+    /// file once they exceed the [`buffer_limit`], instead of failing with
+    /// [`ErrorKind::BufferExceeded`].
+    ///
+    /// Bytes kept by a [`Skip::Chain`] for its upcoming skips are never spilled, and still fail
+    /// once they exceed the limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, Skip, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     // Byte slices implement `Read`, but not `Seek`.
+    ///     let r1: &[u8] = b"record 1\nfooter\n";
+    ///     let r2: &[u8] = b"record 2\nfooter\n";
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.skip_tail(Skip::Until(b"footer"));
+    ///     merger.buffer_limit(4).spill_to_disk(true);
+    ///
+    ///     // Merges sources into one.
+    ///     merger.merge_readers_into(vec![r1, r2], &mut buf)?;
+    ///     assert_eq!(std::str::from_utf8(&buf).unwrap(), "record 1\nrecord 2\n");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// This method is only available if the `tempfile` feature is enabled.
+    ///
+    /// [`merge_readers_into`]: RsMerger::merge_readers_into
+    /// [`buffer_limit`]: RsMerger::buffer_limit
+    #[cfg(feature = "tempfile")]
+    pub fn spill_to_disk(&mut self, spill: bool) -> &mut Self {
+        self.opts.spill = spill;
+        self
+    }
+
+    /// Merges the given sources into the given writer according to the given configurations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given source vector is
+    /// empty;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given sources;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, Skip, Pad, Newline, Result};
+    /// use std::io::Cursor;
+    ///
+    /// fn main() -> Result<()> {
+    ///     // Cursor implements `Read` and `Seek`.
+    ///     let mut c1 = Cursor::new(" record 1\n date created: 2000/01/01\n");
+    ///     let mut c2 = Cursor::new(" record 2\n date created: 2000/01/01\n");
+    ///     let mut c3 = Cursor::new(" record 3\n date created: 2000/01/01\n");
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.skip_tail(Skip::LinesOnce(1));
+    ///     merger.pad_with(Pad::Before(b"header\n"));
+    ///     merger.force_ending_newline(Newline::Lf);
+    ///
+    ///     // Merges sources into one.
+    ///     merger.merge_sources_into(vec![&mut c1, &mut c2, &mut c3], &mut buf)?;
+    ///     assert_eq!(
+    ///         std::str::from_utf8(&buf).unwrap(),
+    ///         "header\n record 1\n record 2\n record 3\n date created: 2000/01/01\n"
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn merge_sources_into<RS, W>(&self, sources: Vec<RS>, writer: &mut W) -> Result<()>
+    where
+        RS: Read + Seek,
+        W: Write,
+    {
+        self.merge_sources_into_with_report(sources, writer)
+            .map(|_| ())
+    }
+
+    /// Does the same as [`merge_sources_into`], returning a [`MergeReport`] telling where each
+    /// source landed in the output.
+    ///
+    /// [`merge_sources_into`]: RsMerger::merge_sources_into
+    pub fn merge_sources_into_with_report<RS, W>(
+        &self,
+        mut sources: Vec<RS>,
+        writer: &mut W,
+    ) -> Result<MergeReport>
+    where
+        RS: Read + Seek,
+        W: Write,
+    {
+        let len = sources.len();
+        if len == 0 {
+            return Err(ErrorKind::NothingPassed);
+        }
+
+        let mut merger = self.resolved(&mut sources[0])?;
+        let sources_iter = sources.iter_mut().map(|source| {
+            util::seek_to_start(source)?;
+            Ok(source)
+        });
+        if let Some(union) = self.union(sources_iter)? {
+            merger.to_mut().opts.union = Some(union);
+        }
+
+        let mut writer = Tracker::new(writer, &merger);
+        let mut report = MergeReport::default();
+        for (i, source) in sources.iter_mut().enumerate() {
+            let pos = PartPos::of(i, len);
+            merger.write_fill(&mut writer, merger.fill_before(report.len()))?;
+            writer.begin(&merger, pos)?;
+            let mut range = merger.write_contents(source, &mut writer, pos, i)?;
+            writer.end(&mut range);
+            report.push(&merger, range, pos, None)?;
+        }
+        merger.write_fill(&mut writer, report.finish(&merger)?)?;
+        merger.write_footer(&mut writer, &mut report)?;
+
+        Ok(report)
+    }
+
+    /// Merges the given sources, which only need to implement [`Read`], into the given writer
+    /// according to the given configurations.
+    ///
+    /// This method produces the same output as [`merge_sources_into`], but reads each source
+    /// exactly once from start to end, so it works on pipes, standard input, sockets or
+    /// decompressors. Bytes that may still be cut by [`skip_tail`] are held back in a look-behind
+    /// buffer, see [`buffer_limit`] and `spill_to_disk`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given source vector is
+    /// empty;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given sources;
+    ///
+    /// Returns an error variant of [`ErrorKind::BufferExceeded`] if the look-behind buffer
+    /// exceeds its limit and spilling to disk is disabled;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use admerge::{RsMerger, Skip, Newline, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     // Byte slices implement `Read`, but not `Seek`.
+    ///     let r1: &[u8] = b"header\n record 1\n date created: 2000/01/01\n";
+    ///     let r2: &[u8] = b"header\n record 2\n date created: 2000/01/01\n";
+    ///     let mut buf = Vec::new();
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = RsMerger::new();
+    ///     merger.skip_head(Skip::LinesOnce(1));
+    ///     merger.skip_tail(Skip::Lines(1));
+    ///     merger.force_ending_newline(Newline::Lf);
+    ///
+    ///     // Merges sources into one.
+    ///     merger.merge_readers_into(vec![r1, r2], &mut buf)?;
+    ///     assert_eq!(
+    ///         std::str::from_utf8(&buf).unwrap(),
+    ///         "header\n record 1\n record 2\n"
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`Read`]: std::io::Read
+    /// [`merge_sources_into`]: RsMerger::merge_sources_into
+    /// [`skip_tail`]: RsMerger::skip_tail
+    /// [`buffer_limit`]: RsMerger::buffer_limit
+    pub fn merge_readers_into<R, W>(&self, sources: Vec<R>, writer: &mut W) -> Result<()>
+    where
+        R: Read,
+        W: Write,
+    {
+        self.merge_readers_into_with_report(sources, writer)
+            .map(|_| ())
+    }
+
+    /// Does the same as [`merge_readers_into`], returning a [`MergeReport`] telling where each
+    /// source landed in the output.
+    ///
+    /// [`merge_readers_into`]: RsMerger::merge_readers_into
+    pub fn merge_readers_into_with_report<R, W>(
+        &self,
+        mut sources: Vec<R>,
+        writer: &mut W,
+    ) -> Result<MergeReport>
+    where
+        R: Read,
+        W: Write,
+    {
+        let len = sources.len();
+        if len == 0 {
+            return Err(ErrorKind::NothingPassed);
+        }
+        if let Some(Columns::Union) = self.opts.columns {
+            return Err(ErrorKind::Unsupported("merge_readers_into"));
+        }
+
+        // The first source is buffered if the newline style or CSV header is read from it.
+        let mut first = None;
+        let merger = match self.resolves() {
+            true => {
+                let mut buffered = self.buffered();
+                buffered.fill_from(&mut sources[0])?;
+                let merger = self.resolved(&mut buffered)?;
+                first = Some(buffered);
+                merger
+            }
+            false => Cow::Borrowed(self),
+        };
+
+        let mut writer = Tracker::new(writer, &merger);
+        let mut report = MergeReport::default();
+        for (i, source) in sources.iter_mut().enumerate() {
+            let pos = PartPos::of(i, len);
+            merger.write_fill(&mut writer, merger.fill_before(report.len()))?;
+            writer.begin(&merger, pos)?;
+            let mut range = match (i, &mut first) {
+                (0, Some(buffered)) => {
+                    merger.write_stream_contents(buffered, &mut writer, pos, i)?
+                }
+                _ => merger.write_stream_contents(source, &mut writer, pos, i)?,
+            };
+            writer.end(&mut range);
+            report.push(&merger, range, pos, None)?;
+        }
+        merger.write_fill(&mut writer, report.finish(&merger)?)?;
+        merger.write_footer(&mut writer, &mut report)?;
+
+        Ok(report)
+    }
+
+    /// Works out what merging the given sources would write, without writing anything.
+    ///
+    /// The returned [`MergePlan`] lists the kept and skipped byte ranges of each source, the
+    /// paddings and newlines surrounding it, and where it would land in the merged output. The
+    /// sources are left in place so that they can be merged afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given source slice is
+    /// empty;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given sources;
+    ///
+    /// Returns an error variant of [`ErrorKind::Unsupported`] if sources are transcoded or
+    /// their line endings normalised;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    pub fn plan<RS>(&self, sources: &mut [RS]) -> Result<MergePlan<'a>>
+    where
+        RS: Read + Seek,
+    {
+        self.check_offsets("plan")?;
+        let count = sources.len();
+        if count == 0 {
+            return Err(ErrorKind::NothingPassed);
+        }
+
+        let merger = self.resolved(&mut sources[0])?;
+        let mut plan = MergePlan::default();
+        for (i, source) in sources.iter_mut().enumerate() {
+            let pos = PartPos::of(i, count);
+            let mut range = merger.part_range(source, pos)?;
+            let runs = merger.part_runs(source, &mut range)?;
+            plan.push(&merger, range, runs, pos)?;
+        }
+        plan.finish(&merger)?;
+
+        Ok(plan)
+    }
+
+    /// Creates a [`MergedReader`] that lazily reads the merged contents of the given sources.
+    ///
+    /// The kept range of every source is worked out up front, so the returned reader knows the
+    /// total length of the merged stream and supports seeking without reading anything ahead.
+    /// The sources should not be modified while the reader is in use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given source vector is
+    /// empty;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given sources;
+    ///
+    /// Returns an error variant of [`ErrorKind::Unsupported`] if sources are transcoded or
+    /// their line endings normalised;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    pub fn merged_reader<RS>(&self, sources: Vec<RS>) -> Result<MergedReader<'a, RS>>
+    where
+        RS: Read + Seek,
+    {
+        self.check_offsets("merged_reader")?;
+        MergedReader::new(self, sources)
+    }
+}
+
+/// The position of a source among the sources being merged.
+///
+/// When merging a single source, that source is at the `Start`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PartPos {
+    /// The first source.
+    Start,
+    /// Any source but the first and the last.
+    Inside,
+    /// The last source.
+    End,
+}
+
+impl<'a> Skip<'a> {
+    // Returns `true` if this skip needs random access to the whole source.
+    pub(crate) fn is_custom(&self) -> bool {
+        match self {
+            Skip::Custom(_) => true,
+            Skip::Chain(skips) => skips.iter().any(Skip::is_custom),
+            _ => false,
+        }
+    }
+}
+
+impl PartPos {
+    // Works out the position of the part at the given index among `count` parts.
+    pub(crate) fn of(index: usize, count: usize) -> Self {
+        match index {
+            0 => PartPos::Start,
+            _ if index == count - 1 => PartPos::End,
+            _ => PartPos::Inside,
+        }
+    }
+}
+
+// The range of one part to be written.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PartRange {
+    // Position to start reading.
+    pub(crate) start: u64,
+    // Position to end reading.
+    pub(crate) end: u64,
+    // Whether this part ends with a newline.
+    pub(crate) endn: bool,
+    // Length of the whole part.
+    pub(crate) len: u64,
+    // Number of bytes written, less than `end - start` if lines were filtered out.
+    pub(crate) written: u64,
+    // Number of lines filtered out.
+    pub(crate) dropped: u64,
+    // Checksum of the bytes written, if worked out.
+    pub(crate) checksum: Option<u32>,
+    // Whether this part starts with a CSV header that differs from the one of the first part.
+    pub(crate) mismatch: bool,
+}
+
+/// One part written from a source that is fed chunk by chunk, see `RsMerger::stream_part`.
+pub(crate) struct StreamPart<'m, 'a> {
+    merger: &'m RsMerger<'a>,
+    pos: PartPos,
+    filter: Filter<'a>,
+    // Only the kept contents reach the sieve, so its offsets are not meaningful.
+    sieve: Option<Sieve<'a>>,
+}
+
+impl<'m, 'a> StreamPart<'m, 'a> {
+    // Feeds the next chunk of the source, writing out every byte known to be kept.
+    pub(crate) fn feed<W: Write>(&mut self, chunk: &[u8], writer: &mut W) -> Result<()> {
+        match &mut self.sieve {
+            None => self.filter.feed(chunk, writer),
+            Some(sieve) => self
+                .filter
+                .feed(chunk, &mut sieve.writer(writer))
+                .map_err(stream::recover),
+        }
+    }
+
+    // Writes out the rest of this part, along with the ending newline and the padding after it,
+    // and returns its range.
+    pub(crate) fn finish<W: Write>(self, writer: &mut W) -> Result<PartRange> {
+        let range = match self.sieve {
+            None => self.filter.finish(writer)?,
+            Some(mut sieve) => {
+                let mut range = self
+                    .filter
+                    .finish(&mut sieve.writer(writer))
+                    .map_err(stream::recover)?;
+                sieve.finish(&mut range, writer)?;
+                range
+            }
+        };
+
+        // Should we writer ending newline?
+        self.merger.write_ending_newline(writer, range.endn)?;
+
+        // Writes padding after this source.
+        self.merger.write_padding_after(writer, self.pos)?;
+
+        Ok(range)
+    }
+}
+
+// Private methods
+impl<'a> RsMerger<'a> {
+    // Writes the contents (entire or partial) of the part at the given index into the writer.
+    fn write_contents<RS, W>(
+        &self,
+        reader: &mut RS,
+        writer: &mut W,
+        pos: PartPos,
+        index: usize,
+    ) -> Result<PartRange>
+    where
+        RS: Read + Seek,
+        W: Write,
+    {
+        if self.rewrites() {
+            util::seek_start(0, reader)?;
+            return self.write_rewritten(reader, writer, pos, index);
+        }
+
+        self.write_bom(writer, pos)?;
+        self.write_seekable_part(reader, writer, pos)
+    }
+
+    // Writes one part, apart from the BOM, from a source that can seek into the writer.
+    fn write_seekable_part<RS, W>(
+        &self,
+        reader: &mut RS,
+        writer: &mut W,
+        pos: PartPos,
+    ) -> Result<PartRange>
+    where
+        RS: Read + Seek,
+        W: Write,
+    {
+        // Nothing is written for a part whose header does not match, see `check_part`.
+        let mut range = self.part_range(reader, pos)?;
+        if range.mismatch {
+            return Ok(range);
+        }
+
+        // Writes padding before this source.
+        self.write_padding_before(writer, pos)?;
+
+        // Reads the desired contents.
+        if range.end > range.start {
+            util::seek_start(range.start, reader)?;
+            let bytes_count = range.end - range.start;
+            let copied = match self.sieve(range.start) {
+                None => io::copy(&mut reader.take(bytes_count), writer)?,
+                Some(mut sieve) => {
+                    let mut sink = sieve.writer(writer);
+                    let copied = io::copy(&mut reader.take(bytes_count), &mut sink)
+                        .map_err(|e| stream::recover(e.into()))?;
+                    sieve.finish(&mut range, writer)?;
+                    copied
+                }
+            };
+            if copied != bytes_count {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+        } else if self.filters_lines() {
+            range.endn = false;
+        }
+
+        // Should we writer ending newline?
+        self.write_ending_newline(writer, range.endn)?;
+
+        // Writes padding after this source.
+        self.write_padding_after(writer, pos)?;
+
+        Ok(range)
+    }
+
+    // Works out the range of one part to be written according to the skip options.
+    pub(crate) fn part_range<RS>(&self, reader: &mut RS, pos: PartPos) -> Result<PartRange>
+    where
+        RS: Read + Seek,
+    {
+        // Needs to know if the reader stream ends with a newline or not.
+        let terminator = self.opts.terminator;
+        let endn = util::endswith_newline(reader, terminator)?;
+
+        // Gets the stream length of the given reader;
+        let stream_len = util::seek_to_end(reader)? as usize;
+
+        // Resets the cursor first.
+        util::seek_to_start(reader)?;
+
+        // Keeps the entire contents if neither `skip_head` nor `skip_tail` is set.
+        let csv = self.header_csv().is_some();
+        if self.opts.skip_head.is_none() && self.opts.skip_tail.is_none() && !csv {
+            let end = stream_len as u64;
+            return Ok(PartRange {
+                start: 0,
+                end,
+                endn,
+                len: end,
+                written: end,
+                dropped: 0,
+                checksum: None,
+                mismatch: false,
+            });
+        }
+
+        // Position to start reading, past the header if sources are CSV.
+        let mut mismatch = false;
+        let start = match (&self.header_csv(), &self.opts.skip_head) {
+            (Some(_), _) if pos == PartPos::Start => 0,
+            (Some(csv), _) => {
+                util::seek_to_start(reader)?;
+                let header = csv.header(reader, self.opts.buffer_limit)?;
+                if let Some(fields) = &self.opts.csv_header {
+                    mismatch = header.len > 0 && header.fields != *fields;
+                }
+                header.len as usize
+            }
+            (None, None) => 0,
+            (None, Some(skip)) => {
+                head_start(skip, reader, pos, terminator, self.opts.buffer_limit)?
+            }
+        };
+
+        // Position to end reading.
+        //
+        // Only bytes before this position will be read.
+        let end = match &self.opts.skip_tail {
+            None => stream_len,
+            Some(skip) => tail_end(skip, reader, pos, terminator, self.opts.buffer_limit)?,
+        };
+
+        if end < start {
+            return Err(ErrorKind::InvalidSkip);
+        }
+
+        Ok(PartRange {
+            start: start as u64,
+            end: end as u64,
+            endn,
+            len: stream_len as u64,
+            written: (end - start) as u64,
+            dropped: 0,
+            checksum: None,
+            mismatch,
+        })
+    }
+
+    // Writes the contents of the part at the given index that can only be read sequentially
+    // into the writer.
+    pub(crate) fn write_stream_contents<R, W>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+        pos: PartPos,
+        index: usize,
+    ) -> Result<PartRange>
+    where
+        R: Read,
+        W: Write,
+    {
+        if self.rewrites() {
+            return self.write_rewritten(reader, writer, pos, index);
+        }
+
+        self.write_bom(writer, pos)?;
+        self.write_stream_part(reader, writer, pos)
+    }
+
+    // Writes one part, apart from the BOM, from a source that can only be read sequentially
+    // into the writer.
+    fn write_stream_part<R, W>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+        pos: PartPos,
+    ) -> Result<PartRange>
+    where
+        R: Read,
+        W: Write,
+    {
+        // Custom skips may look at any byte, so they are applied to a buffered copy.
+        if self.needs_buffering() {
+            let mut buffered = self.buffered();
+            buffered.fill_from(reader)?;
+            return self.write_seekable_part(&mut buffered, writer, pos);
+        }
+
+        let mut part = self.stream_part(writer, pos)?;
+        let mut buf = vec![0; 8 * 1024];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            part.feed(&buf[..n], writer)?;
+        }
+        part.finish(writer)
+    }
+
+    // Starts writing one part, apart from the BOM, from a source that is fed chunk by chunk.
+    // The padding before it is written right away.
+    pub(crate) fn stream_part<W: Write>(
+        &self,
+        writer: &mut W,
+        pos: PartPos,
+    ) -> Result<StreamPart<'_, 'a>> {
+        self.write_padding_before(writer, pos)?;
+        let filter = Filter::new(
+            self.scanner(pos),
+            Spool::new(self.opts.buffer_limit, self.opts.spill),
+        );
+        Ok(StreamPart {
+            merger: self,
+            pos,
+            filter,
+            sieve: self.sieve(0),
+        })
+    }
+
+    // Returns `true` if sources are decoded or the output is encoded.
+    #[cfg(feature = "encoding_rs")]
+    pub(crate) fn transcodes(&self) -> bool {
+        self.opts.decode.is_some() || self.opts.encode.is_some()
+    }
+
+    // Returns `true` if the newline style is detected from the first source.
+    pub(crate) fn detects_newline(&self) -> bool {
+        let auto = Some(NewlineStyle::Auto);
+        self.opts.newline == auto || self.opts.normalize == auto
+    }
+
+    // Returns a copy of this merger that uses the given newline wherever `NewlineStyle::Auto` was
+    // configured.
+    pub(crate) fn with_newline(&self, newline: Newline) -> RsMerger<'a> {
+        let mut merger = self.clone();
+        for opt in [&mut merger.opts.newline, &mut merger.opts.normalize] {
+            if *opt == Some(NewlineStyle::Auto) {
+                *opt = Some(NewlineStyle::Fixed(newline));
+            }
+        }
+        merger
+    }
+
+    // Returns `true` if anything is read from the first source before merging, see `resolved`.
+    pub(crate) fn resolves(&self) -> bool {
+        self.detects_newline() || self.header_csv().is_some()
+    }
+
+    // Returns the format of CSV sources whose headers are checked and skipped by their ranges,
+    // which is left to `ColumnRewriter` once columns are rewritten.
+    pub(crate) fn header_csv(&self) -> Option<Csv> {
+        self.opts.csv.filter(|_| self.opts.columns.is_none())
+    }
+
+    // Fails if skips are configured along with CSV headers, see `csv_headers`.
+    pub(crate) fn check_csv_skips(&self) -> Result<()> {
+        let skips = self.opts.skip_head.is_some() || self.opts.skip_tail.is_some();
+        if self.header_csv().is_some() && skips {
+            return Err(ErrorKind::InvalidSkip);
+        }
+
+        Ok(())
+    }
+
+    // Returns the format of CSV sources whose columns are united, if configured.
+    pub(crate) fn union_csv(&self) -> Option<Csv> {
+        match self.opts.columns {
+            Some(Columns::Union) => Some(self.opts.csv.unwrap_or_default()),
+            _ => None,
+        }
+    }
+
+    // Works out the union of the columns of the given sources, if configured.
+    pub(crate) fn union<R, I>(&self, sources: I) -> Result<Option<Vec<Vec<u8>>>>
+    where
+        R: Read,
+        I: IntoIterator<Item = Result<R>>,
+    {
+        match self.union_csv() {
+            Some(csv) => csv::union(csv, sources, self.opts.buffer_limit).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // Resolves `NewlineStyle::Auto` and reads the CSV header from the given first source, which is
+    // rewound afterwards.
+    pub(crate) fn resolved<RS>(&self, first: &mut RS) -> Result<Cow<'_, RsMerger<'a>>>
+    where
+        RS: Read + Seek,
+    {
+        self.check_csv_skips()?;
+        if !self.resolves() {
+            return Ok(Cow::Borrowed(self));
+        }
+
+        let mut merger = match self.detects_newline() {
+            true => {
+                util::seek_start(0, first)?;
+                self.with_newline(normalize::detect(first)?)
+            }
+            false => self.clone(),
+        };
+        if let Some(csv) = self.header_csv() {
+            util::seek_start(0, first)?;
+            let header = csv.header(first, self.opts.buffer_limit)?;
+            merger.opts.csv_header = Some(header.fields);
+        }
+        util::seek_start(0, first)?;
+        Ok(Cow::Owned(merger))
+    }
+
+    // Returns the line terminators that end lines of sources.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn terminator(&self) -> Terminator<'a> {
+        self.opts.terminator
+    }
+
+    // Sets the fields of the header of the first source, once read asynchronously.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn set_csv_header(&mut self, fields: Vec<Vec<u8>>) {
+        self.opts.csv_header = Some(fields);
+    }
+
+    // Sets the union of the columns of all sources, once read asynchronously.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn set_union(&mut self, columns: Vec<Vec<u8>>) {
+        self.opts.union = Some(columns);
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) fn limit(&self) -> usize {
+        self.opts.buffer_limit
+    }
+
+    // Returns `true` if buffers may be spilled to disk.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn spills(&self) -> bool {
+        self.opts.spill
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) fn max_open_files(&self) -> usize {
+        self.opts.max_open_files
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) fn set_max_open_files(&mut self, limit: usize) {
+        self.opts.max_open_files = limit.max(1);
+    }
+
+    // Returns the style of the newlines forced or normalised, if any.
+    pub(crate) fn newline_style(&self) -> Option<Newline> {
+        self.normalized()
+            .or(self.opts.newline.and_then(NewlineStyle::fixed))
+    }
+
+    // Returns the style newlines are normalised to, if any.
+    fn normalized(&self) -> Option<Newline> {
+        self.opts.normalize.and_then(NewlineStyle::fixed)
+    }
+
+    // Returns `true` if sources are not copied as they are, but transcoded or normalised.
+    pub(crate) fn rewrites(&self) -> bool {
+        #[cfg(feature = "encoding_rs")]
+        if self.transcodes() {
+            return true;
+        }
+
+        self.opts.normalize.is_some() || self.opts.columns.is_some()
+    }
+
+    // Writes one part into the writer, rewriting the source on the fly. Everything written but
+    // the BOM is rewritten alike.
+    fn write_rewritten<R, W>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+        pos: PartPos,
+        index: usize,
+    ) -> Result<PartRange>
+    where
+        R: Read,
+        W: Write,
     {
-        // Writes padding before this source.
-        self.write_padding_before(writer, pos)?;
+        self.write_bom(writer, pos)?;
+        let mut reader = RewriteReader::new(reader, self.rewriter(pos, index));
 
-        // Needs to know if the reader stream ends with a newline or not.
-        let endn = util::endswith_newline(reader)?;
+        #[cfg(feature = "encoding_rs")]
+        if self.transcodes() {
+            let mut writer = self.encoder(writer);
+            let range = self
+                .write_stream_part(&mut reader, &mut writer, pos)
+                .map_err(stream::recover)?;
+            let total = writer.finish()?;
+            return self.encoded_range(range, total, pos);
+        }
 
-        // Gets the stream length of the given reader;
-        let stream_len = util::seek_to_end(reader)? as usize;
+        self.write_stream_part(&mut reader, writer, pos)
+            .map_err(stream::recover)
+    }
 
-        // Resets the cursor first.
-        util::seek_to_start(reader)?;
+    // Creates the rewrites of the source at the given index: decoding, rewriting the columns of
+    // CSV sources and normalising line endings, in this order, each only if configured.
+    pub(crate) fn rewriter(&self, pos: PartPos, index: usize) -> Pipeline<'_> {
+        let mut pipeline = Pipeline::default();
+
+        #[cfg(feature = "encoding_rs")]
+        if self.transcodes() {
+            let decode = self.opts.decode.unwrap_or(Decode::Auto(encoding_rs::UTF_8));
+            pipeline.push_stage(decode.decoder(index));
+        }
+        #[cfg(not(feature = "encoding_rs"))]
+        let _ = index;
+
+        let columns: Option<Vec<&[u8]>> = match self.opts.columns {
+            None => None,
+            Some(Columns::Only(columns)) => Some(columns.iter().map(|c| c.as_bytes()).collect()),
+            Some(Columns::Union) => Some(
+                self.opts
+                    .union
+                    .iter()
+                    .flatten()
+                    .map(Vec::as_slice)
+                    .collect(),
+            ),
+        };
+        if let Some(columns) = columns {
+            pipeline.push_stage(ColumnRewriter::new(
+                self.opts.csv.unwrap_or_default(),
+                columns,
+                self.opts.missing,
+                pos == PartPos::Start,
+                self.opts.buffer_limit,
+            ));
+        }
+
+        if let Some(newline) = self.normalized() {
+            pipeline.push_stage(Normalizer::new(newline.as_bytes()));
+        }
+
+        pipeline
+    }
+
+    // Wraps the given writer so that everything written to it is encoded into the output
+    // encoding.
+    #[cfg(feature = "encoding_rs")]
+    pub(crate) fn encoder<W: Write>(&self, writer: W) -> EncodeWriter<W> {
+        EncodeWriter::new(writer, self.output_encoding())
+    }
+
+    // Updates the range of one part written through an encoder, given the number of encoded
+    // bytes written for it.
+    #[cfg(feature = "encoding_rs")]
+    pub(crate) fn encoded_range(
+        &self,
+        mut range: PartRange,
+        total: u64,
+        pos: PartPos,
+    ) -> Result<PartRange> {
+        if range.mismatch {
+            return Ok(range);
+        }
+
+        // Works out how many encoded bytes came from the source itself.
+        let newline = self.ending_newline(range.endn).unwrap_or_default();
+        let others = self.output_len(self.padding_before(pos).unwrap_or_default())?
+            + self.output_len(newline)?
+            + self.output_len(self.padding_after(pos).unwrap_or_default())?;
+        range.written = total - others;
+        Ok(range)
+    }
+
+    // Returns the encoding of the merged output.
+    #[cfg(feature = "encoding_rs")]
+    fn output_encoding(&self) -> &'static Encoding {
+        self.opts.encode.unwrap_or(encoding_rs::UTF_8)
+    }
+
+    // Returns the given padding or newline as it is written, before any encoding.
+    fn output_bytes<'b>(&self, bytes: &'b [u8]) -> Cow<'b, [u8]> {
+        match self.normalized() {
+            Some(newline) => normalize::normalize(bytes, newline.as_bytes()),
+            None => Cow::Borrowed(bytes),
+        }
+    }
+
+    // Returns the number of bytes the given padding or newline takes in the merged output.
+    pub(crate) fn output_len(&self, bytes: &[u8]) -> Result<u64> {
+        let bytes = self.output_bytes(bytes);
+
+        #[cfg(feature = "encoding_rs")]
+        if self.transcodes() {
+            return encoding::encoded_len(self.output_encoding(), &bytes);
+        }
+
+        Ok(bytes.len() as u64)
+    }
+
+    // Fails if sources are rewritten, as offsets then refer to rewritten contents, or if an
+    // index is appended to the output, as its contents are only known once merged.
+    pub(crate) fn check_offsets(&self, method: &'static str) -> Result<()> {
+        if self.rewrites() || self.opts.index == Some(IndexPlacement::Footer) {
+            return Err(ErrorKind::Unsupported(method));
+        }
+
+        Ok(())
+    }
+
+    // Returns `true` if sources must be buffered in full before their skips can be applied.
+    pub(crate) fn needs_buffering(&self) -> bool {
+        let head = self.opts.skip_head.as_ref().is_some_and(Skip::is_custom);
+        let tail = self.opts.skip_tail.as_ref().is_some_and(Skip::is_custom);
+        head || tail || self.header_csv().is_some()
+    }
+
+    pub(crate) fn filters_lines(&self) -> bool {
+        self.opts.line_filter.is_some()
+    }
+
+    // Creates a sieve for the kept contents starting at the given offset, if lines are filtered.
+    pub(crate) fn sieve(&self, start: u64) -> Option<Sieve<'a>> {
+        let filter = self.opts.line_filter?;
+        Some(Sieve::new(
+            filter,
+            self.opts.terminator,
+            start,
+            self.opts.buffer_limit,
+        ))
+    }
+
+    // Returns the ranges of the given part that will be written, updating the part to account
+    // for filtered out lines.
+    pub(crate) fn part_runs<RS>(
+        &self,
+        reader: &mut RS,
+        range: &mut PartRange,
+    ) -> Result<Vec<Range<u64>>>
+    where
+        RS: Read + Seek,
+    {
+        let sieve = match self.sieve(range.start) {
+            None if range.end > range.start => {
+                let kept = range.start..range.end;
+                return Ok(vec![kept]);
+            }
+            None => return Ok(Vec::new()),
+            Some(sieve) => sieve,
+        };
+
+        let mut sieve = sieve.record_runs();
+        util::seek_start(range.start, reader)?;
+        let mut take = reader.take(range.end - range.start);
+        let mut chunk = vec![0; 8 * 1024];
+        loop {
+            let n = match take.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            sieve.feed(&chunk[..n], &mut io::sink())?;
+        }
+        if take.limit() != 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        sieve.finish(range, &mut io::sink())?;
+        Ok(sieve.into_runs())
+    }
+
+    // Creates an empty buffer for a whole source.
+    pub(crate) fn buffered(&self) -> Buffered {
+        Buffered::new(self.opts.buffer_limit, self.opts.spill)
+    }
+
+    // Creates a scanner that tracks the kept range of one part.
+    pub(crate) fn scanner(&self, pos: PartPos) -> Scanner<'a> {
+        let terminator = self.opts.terminator;
+        let limit = self.opts.buffer_limit;
+        Scanner::new(
+            HeadScan::new(self.opts.skip_head.as_ref(), pos, terminator, limit),
+            TailScan::new(self.opts.skip_tail.as_ref(), pos, terminator, limit),
+            terminator,
+        )
+    }
+
+    pub(crate) fn write_bom<W: Write>(&self, writer: &mut W, pos: PartPos) -> Result<()> {
+        if let Some(bom) = self.bom_before(pos) {
+            writer.write_all(bom)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn write_ending_newline<W: Write>(
+        &self,
+        writer: &mut W,
+        endn: bool,
+    ) -> Result<()> {
+        if let Some(newline) = self.ending_newline(endn) {
+            writer.write_all(&self.output_bytes(newline))?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn write_padding_before<W: Write>(
+        &self,
+        writer: &mut W,
+        pos: PartPos,
+    ) -> Result<()> {
+        if let Some(padding) = self.padding_before(pos) {
+            writer.write_all(&self.output_bytes(padding))?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn write_padding_after<W: Write>(
+        &self,
+        writer: &mut W,
+        pos: PartPos,
+    ) -> Result<()> {
+        if let Some(padding) = self.padding_after(pos) {
+            writer.write_all(&self.output_bytes(padding))?;
+        }
+
+        Ok(())
+    }
+
+    // Returns the newline, or record delimiter, to be appended to a part, if any.
+    pub(crate) fn ending_newline(&self, endn: bool) -> Option<&'a [u8]> {
+        match (self.opts.newline, self.opts.terminator) {
+            (Some(_), Terminator::Delimiter(delimiter)) if !endn && !delimiter.is_empty() => {
+                Some(delimiter)
+            }
+            (Some(newline), _) if !endn => {
+                self.normalized().or(newline.fixed()).map(Newline::as_bytes)
+            }
+            _ => None,
+        }
+    }
+
+    // Fails if the part at the given index starts with a CSV header that does not match, or if
+    // the contents kept from it end with a partial record.
+    pub(crate) fn check_part(&self, index: usize, range: &PartRange) -> Result<()> {
+        if range.mismatch {
+            return Err(ErrorKind::HeaderMismatch(index));
+        }
+
+        match self.opts.record_size {
+            Some(size) if size != 0 && (range.end - range.start) % size as u64 != 0 => {
+                Err(ErrorKind::PartialRecord(index))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Returns the number of fill bytes to be written before a part, given the length of the
+    // output written so far.
+    pub(crate) fn fill_before(&self, len: u64) -> u64 {
+        match self.opts.align_parts {
+            Some(alignment) if alignment > 1 => (alignment - len % alignment) % alignment,
+            _ => 0,
+        }
+    }
+
+    // Returns the number of fill bytes to be written after the last part, given the length of
+    // the output written so far.
+    pub(crate) fn fill_end(&self, len: u64) -> Result<u64> {
+        let aligned = match self.opts.align_output {
+            Some(alignment) if alignment > 1 => len + (alignment - len % alignment) % alignment,
+            _ => len,
+        };
+        self.check_image_size(aligned)?;
+        Ok(self.opts.image_size.unwrap_or(aligned) - len)
+    }
+
+    // Fails if the output would grow beyond the configured image size.
+    pub(crate) fn check_image_size(&self, len: u64) -> Result<()> {
+        match self.opts.image_size {
+            Some(size) if len > size => Err(ErrorKind::ImageOverflow(size)),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn fill(&self) -> u8 {
+        self.opts.fill
+    }
+
+    pub(crate) fn write_fill<W: Write>(&self, writer: &mut W, len: u64) -> Result<()> {
+        io::copy(&mut io::repeat(self.opts.fill).take(len), writer)?;
+        Ok(())
+    }
+
+    pub(crate) fn index_placement(&self) -> Option<IndexPlacement> {
+        self.opts.index
+    }
+
+    // Appends the index of the merge to the output, if configured to.
+    pub(crate) fn write_footer<W: Write>(
+        &self,
+        writer: &mut W,
+        report: &mut MergeReport,
+    ) -> Result<()> {
+        if self.opts.index == Some(IndexPlacement::Footer) {
+            if let Some(index) = report.index() {
+                let len = index.write_footer(writer)?;
+                report.push_footer(len);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns the BOM to be written before a part, if any.
+    pub(crate) fn bom_before(&self, pos: PartPos) -> Option<&'static [u8]> {
+        match (self.opts.bom, pos) {
+            (Some(bom), PartPos::Start) => Some(bom.as_bytes()),
+            _ => None,
+        }
+    }
+
+    // Returns the padding to be filled before a part, if any.
+    pub(crate) fn padding_before(&self, pos: PartPos) -> Option<&'a [u8]> {
+        match (self.opts.padding.as_ref()?, pos) {
+            (Pad::Before(padding), PartPos::Start) => Some(padding),
+            (Pad::Custom(Some(padding), _, _), PartPos::Start) => Some(padding),
+            _ => None,
+        }
+    }
 
-        if !self.should_view_contents() {
-            // Just copy the entire contents if viewing into the reader is not required.
-            io::copy(reader, writer)?;
-        } else {
-            // Skips contents if either `skip_head` or `skip_tail` is set.
-            if self.opts.skip_head.is_some() || self.opts.skip_tail.is_some() {
-                let mut seeker = ByteSeeker::new(reader);
-
-                // Position to start reading.
-                seeker.reset();
-                let start = match &self.opts.skip_head {
-                    None => 0,
-                    Some(skip) => match *skip {
-                        Skip::Bytes(n) => n,
-                        Skip::BytesOnce(n) => match pos {
-                            PartPos::Start => 0,
-                            _ => n,
+    // Returns the padding to be filled after a part, if any.
+    pub(crate) fn padding_after(&self, pos: PartPos) -> Option<&'a [u8]> {
+        match (self.opts.padding.as_ref()?, pos) {
+            (Pad::After(padding), PartPos::End) => Some(padding),
+            (Pad::Between(padding), PartPos::Start | PartPos::Inside) => Some(padding),
+            (Pad::Custom(_, Some(padding), _), PartPos::Start | PartPos::Inside) => Some(padding),
+            (Pad::Custom(_, _, Some(padding)), PartPos::End) => Some(padding),
+            _ => None,
+        }
+    }
+}
+
+// Works out the position to start reading the given source according to the given head skip.
+//
+// Takes a trait object as chained skips recurse into windows of the given source.
+pub(crate) fn head_start(
+    skip: &Skip<'_>,
+    mut reader: &mut dyn ReadSeek,
+    pos: PartPos,
+    terminator: Terminator<'_>,
+    limit: usize,
+) -> Result<usize> {
+    let reader = &mut reader;
+    let endn = util::endswith_newline(reader, terminator)?;
+    let stream_len = util::seek_to_end(reader)? as usize;
+    util::seek_to_start(reader)?;
+
+    let mut seeker = ByteSeeker::new(reader);
+    seeker.reset();
+    let start = match *skip {
+        Skip::Bom => bom_len(seeker.get_mut())?,
+        Skip::BomOnce => match pos {
+            PartPos::Start => 0,
+            _ => bom_len(seeker.get_mut())?,
+        },
+        Skip::Bytes(n) => n,
+        Skip::BytesOnce(n) => match pos {
+            PartPos::Start => 0,
+            _ => n,
+        },
+        Skip::Records(n, size) => n.saturating_mul(size),
+        Skip::RecordsOnce(n, size) => match pos {
+            PartPos::Start => 0,
+            _ => n.saturating_mul(size),
+        },
+        // Terminators other than `\n` are looked for by a scanner.
+        Skip::Lines(_) | Skip::LinesOnce(_) if terminator != Terminator::Lf => {
+            let reader = seeker.get_mut();
+            util::seek_to_start(reader)?;
+            let head = HeadScan::new(Some(skip), pos, terminator, limit);
+            stream::scan_head(head, reader, stream_len as u64, endn)? as usize
+        }
+        Skip::Lines(n) => match n {
+            0 => 0,
+            _ => {
+                let pos;
+
+                if !endn && n == 1 {
+                    match seeker.seek_nth(b"\n", 1) {
+                        Ok(idx) => {
+                            pos = idx + 1;
+                        }
+                        Err(e) => match e.kind() {
+                            byteseeker::ErrorKind::ByteNotFound => pos = stream_len,
+                            _ => return Err(e.into()),
+                        },
+                    }
+                } else {
+                    let nth = if endn { n } else { n - 1 };
+                    match seeker.seek_nth(b"\n", nth) {
+                        Ok(idx) => {
+                            if endn {
+                                pos = idx + 1;
+                            } else {
+                                match seeker.seek(b"\n") {
+                                    Ok(idx) => {
+                                        pos = idx + 1;
+                                    }
+                                    Err(e) => match e.kind() {
+                                        byteseeker::ErrorKind::ByteNotFound => pos = stream_len,
+                                        _ => return Err(e.into()),
+                                    },
+                                }
+                            }
+                        }
+                        Err(e) => match e.kind() {
+                            byteseeker::ErrorKind::ByteNotFound => {
+                                return Err(ErrorKind::InvalidSkip);
+                            }
+                            _ => return Err(e.into()),
                         },
-                        Skip::Lines(n) => match n {
-                            0 => 0,
-                            _ => {
-                                let pos;
+                    }
+                }
+
+                pos
+            }
+        },
+        Skip::LinesOnce(n) => match pos {
+            PartPos::Start => 0,
+            _ => match n {
+                0 => 0,
+                _ => {
+                    let pos;
 
-                                if !endn && n == 1 {
-                                    match seeker.seek_nth(b"\n", 1) {
+                    if !endn && n == 1 {
+                        match seeker.seek_nth(b"\n", 1) {
+                            Ok(idx) => {
+                                pos = idx + 1;
+                            }
+                            Err(e) => match e.kind() {
+                                byteseeker::ErrorKind::ByteNotFound => pos = stream_len,
+                                _ => return Err(e.into()),
+                            },
+                        }
+                    } else {
+                        let nth = if endn { n } else { n - 1 };
+                        match seeker.seek_nth(b"\n", nth) {
+                            Ok(idx) => {
+                                if endn {
+                                    pos = idx + 1;
+                                } else {
+                                    match seeker.seek(b"\n") {
                                         Ok(idx) => {
                                             pos = idx + 1;
                                         }
@@ -455,428 +2443,299 @@ impl<'a> RsMerger<'a> {
                                             _ => return Err(e.into()),
                                         },
                                     }
-                                } else {
-                                    let nth = if endn { n } else { n - 1 };
-                                    match seeker.seek_nth(b"\n", nth) {
-                                        Ok(idx) => {
-                                            if endn {
-                                                pos = idx + 1;
-                                            } else {
-                                                match seeker.seek(b"\n") {
-                                                    Ok(idx) => {
-                                                        pos = idx + 1;
-                                                    }
-                                                    Err(e) => match e.kind() {
-                                                        byteseeker::ErrorKind::ByteNotFound => {
-                                                            pos = stream_len
-                                                        }
-                                                        _ => return Err(e.into()),
-                                                    },
-                                                }
-                                            }
-                                        }
-                                        Err(e) => match e.kind() {
-                                            byteseeker::ErrorKind::ByteNotFound => {
-                                                return Err(ErrorKind::InvalidSkip);
-                                            }
-                                            _ => return Err(e.into()),
-                                        },
-                                    }
                                 }
-
-                                pos
                             }
-                        },
-                        Skip::LinesOnce(n) => match pos {
-                            PartPos::Start => 0,
-                            _ => match n {
-                                0 => 0,
-                                _ => {
-                                    let pos;
-
-                                    if !endn && n == 1 {
-                                        match seeker.seek_nth(b"\n", 1) {
-                                            Ok(idx) => {
-                                                pos = idx + 1;
-                                            }
-                                            Err(e) => match e.kind() {
-                                                byteseeker::ErrorKind::ByteNotFound => {
-                                                    pos = stream_len
-                                                }
-                                                _ => return Err(e.into()),
-                                            },
-                                        }
-                                    } else {
-                                        let nth = if endn { n } else { n - 1 };
-                                        match seeker.seek_nth(b"\n", nth) {
-                                            Ok(idx) => {
-                                                if endn {
-                                                    pos = idx + 1;
-                                                } else {
-                                                    match seeker.seek(b"\n") {
-                                                        Ok(idx) => {
-                                                            pos = idx + 1;
-                                                        }
-                                                        Err(e) => match e.kind() {
-                                                            byteseeker::ErrorKind::ByteNotFound => {
-                                                                pos = stream_len
-                                                            }
-                                                            _ => return Err(e.into()),
-                                                        },
-                                                    }
-                                                }
-                                            }
-                                            Err(e) => match e.kind() {
-                                                byteseeker::ErrorKind::ByteNotFound => {
-                                                    return Err(ErrorKind::InvalidSkip);
-                                                }
-                                                _ => return Err(e.into()),
-                                            },
-                                        }
-                                    }
-
-                                    pos
-                                }
-                            },
-                        },
-                        Skip::Until(bytes) => match seeker.seek(bytes) {
-                            Ok(pos) => pos + bytes.len(),
-                            Err(e) => match e.kind() {
-                                byteseeker::ErrorKind::ByteNotFound => {
-                                    stream_len
-                                }
-                                _ => return Err(e.into()),
-                            },
-                        },
-                        Skip::Before(bytes) => match seeker.seek(bytes) {
-                            Ok(pos) => pos,
                             Err(e) => match e.kind() {
                                 byteseeker::ErrorKind::ByteNotFound => {
-                                    stream_len
+                                    return Err(ErrorKind::InvalidSkip);
                                 }
                                 _ => return Err(e.into()),
                             },
-                        },
-                        Skip::Repeats(bytes) => {
-                            let width = bytes.len();
-                            match width {
-                                0 => 0,
-                                _ => {
-                                    let mut buf = vec![0; width];
-                                    buf.resize(width, 0);
-
-                                    let mut reader = seeker.get_mut();
-                                    util::seek_to_start(&mut reader)?;
-                                    let mut bytes_match = 0;
-                                    loop {
-                                        reader.read_exact(&mut buf)?;
-                                        if buf == bytes {
-                                            bytes_match += width;
-                                            if bytes_match == stream_len {
-                                                break;
-                                            }
-                                        } else {
-                                            break;
-                                        }
-                                    }
+                        }
+                    }
 
-                                    bytes_match
-                                }
+                    pos
+                }
+            },
+        },
+        Skip::Until(bytes) | Skip::Before(bytes) if bytes.is_empty() => 0,
+        Skip::Until(bytes) => match util::find(bytes, seeker.get_mut())? {
+            Some(pos) => pos + bytes.len(),
+            None => stream_len,
+        },
+        Skip::Before(bytes) => match util::find(bytes, seeker.get_mut())? {
+            Some(pos) => pos,
+            None => stream_len,
+        },
+        #[cfg(feature = "regex")]
+        Skip::UntilMatch(_) | Skip::BeforeMatch(_) => {
+            let reader = seeker.get_mut();
+            util::seek_to_start(reader)?;
+            let head = HeadScan::new(Some(skip), pos, terminator, limit);
+            stream::scan_head(head, reader, stream_len as u64, endn)? as usize
+        }
+        Skip::Custom(rule) => {
+            let reader = seeker.get_mut();
+            rule.skip(&mut SkipSource::new(reader, Side::Head, pos, terminator)?)?
+        }
+        Skip::Chain(skips) => {
+            // Each skip applies to what the previous ones left.
+            let reader = seeker.get_mut();
+            let mut start = 0;
+            for skip in skips {
+                let len = (stream_len - start) as u64;
+                let mut window = Window::new(&mut *reader, start as u64, len);
+                start += head_start(skip, &mut window, pos, terminator, limit)?;
+                if start > stream_len {
+                    return Err(ErrorKind::InvalidSkip);
+                }
+            }
+            start
+        }
+        Skip::Repeats(bytes) => {
+            let width = bytes.len();
+            match width {
+                0 => 0,
+                _ => {
+                    let mut buf = vec![0; width];
+                    buf.resize(width, 0);
+
+                    let mut reader = seeker.get_mut();
+                    util::seek_to_start(&mut reader)?;
+                    let mut bytes_match = 0;
+                    loop {
+                        // Avoid reading past the end.
+                        if bytes_match + width > stream_len {
+                            break;
+                        }
+                        reader.read_exact(&mut buf)?;
+                        if buf == bytes {
+                            bytes_match += width;
+                            if bytes_match == stream_len {
+                                break;
                             }
+                        } else {
+                            break;
                         }
-                    },
-                };
-
-                // Position to end reading.
-                //
-                // Only bytes before this position will be read.
-                seeker.reset();
-                let end = match &self.opts.skip_tail {
-                    None => util::seek_to_end(reader)? as usize,
-                    Some(skip) => match *skip {
-                        Skip::Bytes(n) => match n > stream_len {
-                            true => return Err(ErrorKind::InvalidSkip),
-                            false => stream_len - n,
-                        },
-                        Skip::BytesOnce(n) => match pos {
-                            PartPos::End => stream_len,
-                            _ => match n > stream_len {
-                                true => return Err(ErrorKind::InvalidSkip),
-                                false => stream_len - n,
-                            },
-                        },
-                        Skip::Lines(n) => match n {
-                            0 => stream_len,
-                            _ => {
-                                let pos;
+                    }
 
-                                // Ignore any ending newline.
-                                if endn {
-                                    seeker.seek_back(b"\n")?;
-                                }
+                    bytes_match
+                }
+            }
+        }
+    };
 
-                                match n {
-                                    1 => match seeker.seek_back(b"\n") {
-                                        Ok(idx) => {
-                                            pos = idx + 1;
-                                        }
-                                        Err(e) => match e.kind() {
-                                            byteseeker::ErrorKind::ByteNotFound => pos = 0,
-                                            _ => return Err(e.into()),
-                                        },
-                                    },
-                                    _ => match seeker.seek_nth_back(b"\n", n - 1) {
-                                        Ok(_) => match seeker.seek_back(b"\n") {
-                                            Ok(idx) => pos = idx + 1,
-                                            Err(e) => match e.kind() {
-                                                byteseeker::ErrorKind::ByteNotFound => pos = 0,
-                                                _ => return Err(e.into()),
-                                            },
-                                        },
-                                        Err(e) => match e.kind() {
-                                            byteseeker::ErrorKind::ByteNotFound => {
-                                                return Err(ErrorKind::InvalidSkip)
-                                            }
-                                            _ => return Err(e.into()),
-                                        },
-                                    },
-                                }
+    Ok(start)
+}
+
+// Returns the length of the byte order mark the given source starts with, if any.
+fn bom_len<RS: Read + Seek>(reader: &mut RS) -> Result<usize> {
+    util::seek_to_start(reader)?;
+    let mut head = Vec::with_capacity(3);
+    reader.take(3).read_to_end(&mut head)?;
+    Ok(Bom::detect(&head).map_or(0, |bom| bom.as_bytes().len()))
+}
+
+// Works out the position to end reading the given source according to the given tail skip.
+//
+// Takes a trait object as chained skips recurse into windows of the given source.
+pub(crate) fn tail_end(
+    skip: &Skip<'_>,
+    mut reader: &mut dyn ReadSeek,
+    pos: PartPos,
+    terminator: Terminator<'_>,
+    limit: usize,
+) -> Result<usize> {
+    let reader = &mut reader;
+    let endn = util::endswith_newline(reader, terminator)?;
+    let stream_len = util::seek_to_end(reader)? as usize;
+    util::seek_to_start(reader)?;
+
+    let mut seeker = ByteSeeker::new(reader);
+    let end = match *skip {
+        Skip::Bom | Skip::BomOnce => stream_len,
+        Skip::Bytes(n) => match n > stream_len {
+            true => return Err(ErrorKind::InvalidSkip),
+            false => stream_len - n,
+        },
+        Skip::BytesOnce(n) => match pos {
+            PartPos::End => stream_len,
+            _ => match n > stream_len {
+                true => return Err(ErrorKind::InvalidSkip),
+                false => stream_len - n,
+            },
+        },
+        Skip::Records(n, size) => match n.saturating_mul(size) {
+            n if n > stream_len => return Err(ErrorKind::InvalidSkip),
+            n => stream_len - n,
+        },
+        Skip::RecordsOnce(n, size) => match pos {
+            PartPos::End => stream_len,
+            _ => match n.saturating_mul(size) {
+                n if n > stream_len => return Err(ErrorKind::InvalidSkip),
+                n => stream_len - n,
+            },
+        },
+        // Terminators other than `\n` are looked for by a scanner, which reads the whole source.
+        Skip::Lines(_) | Skip::LinesOnce(_) if terminator != Terminator::Lf => {
+            let reader = seeker.get_mut();
+            util::seek_to_start(reader)?;
+            let tail = TailScan::new(Some(skip), pos, terminator, limit);
+            stream::scan_tail(tail, reader, stream_len as u64, endn)? as usize
+        }
+        Skip::Lines(n) => match n {
+            0 => stream_len,
+            _ => {
+                let pos;
 
-                                pos
+                // Ignore any ending newline.
+                if endn {
+                    seeker.seek_back(b"\n")?;
+                }
+
+                match n {
+                    1 => match seeker.seek_back(b"\n") {
+                        Ok(idx) => {
+                            pos = idx + 1;
+                        }
+                        Err(e) => match e.kind() {
+                            byteseeker::ErrorKind::ByteNotFound => pos = 0,
+                            _ => return Err(e.into()),
+                        },
+                    },
+                    _ => match seeker.seek_nth_back(b"\n", n - 1) {
+                        Ok(_) => match seeker.seek_back(b"\n") {
+                            Ok(idx) => pos = idx + 1,
+                            Err(e) => match e.kind() {
+                                byteseeker::ErrorKind::ByteNotFound => pos = 0,
+                                _ => return Err(e.into()),
+                            },
+                        },
+                        Err(e) => match e.kind() {
+                            byteseeker::ErrorKind::ByteNotFound => {
+                                return Err(ErrorKind::InvalidSkip)
                             }
+                            _ => return Err(e.into()),
                         },
-                        Skip::LinesOnce(n) => match pos {
-                            PartPos::End => stream_len,
-                            _ => match n {
-                                0 => stream_len,
-                                _ => {
-                                    let pos;
-
-                                    // Ignore any ending newline.
-                                    if endn {
-                                        seeker.seek_back(b"\n")?;
-                                    }
+                    },
+                }
 
-                                    match n {
-                                        1 => match seeker.seek_back(b"\n") {
-                                            Ok(idx) => {
-                                                pos = idx + 1;
-                                            }
-                                            Err(e) => match e.kind() {
-                                                byteseeker::ErrorKind::ByteNotFound => pos = 0,
-                                                _ => return Err(e.into()),
-                                            },
-                                        },
-                                        _ => match seeker.seek_nth_back(b"\n", n - 1) {
-                                            Ok(_) => match seeker.seek_back(b"\n") {
-                                                Ok(idx) => pos = idx + 1,
-                                                Err(e) => match e.kind() {
-                                                    byteseeker::ErrorKind::ByteNotFound => pos = 0,
-                                                    _ => return Err(e.into()),
-                                                },
-                                            },
-                                            Err(e) => match e.kind() {
-                                                byteseeker::ErrorKind::ByteNotFound => {
-                                                    return Err(ErrorKind::InvalidSkip)
-                                                }
-                                                _ => return Err(e.into()),
-                                            },
-                                        },
-                                    }
+                pos
+            }
+        },
+        Skip::LinesOnce(n) => match pos {
+            PartPos::End => stream_len,
+            _ => match n {
+                0 => stream_len,
+                _ => {
+                    let pos;
 
-                                    pos
-                                }
-                            },
-                        },
-                        Skip::Until(bytes) => match seeker.seek_back(bytes) {
-                            Ok(pos) => pos,
+                    // Ignore any ending newline.
+                    if endn {
+                        seeker.seek_back(b"\n")?;
+                    }
+
+                    match n {
+                        1 => match seeker.seek_back(b"\n") {
+                            Ok(idx) => {
+                                pos = idx + 1;
+                            }
                             Err(e) => match e.kind() {
-                                byteseeker::ErrorKind::ByteNotFound => 0,
+                                byteseeker::ErrorKind::ByteNotFound => pos = 0,
                                 _ => return Err(e.into()),
                             },
                         },
-                        Skip::Before(bytes) => match seeker.seek(bytes) {
-                            Ok(pos) => pos + bytes.len(),
+                        _ => match seeker.seek_nth_back(b"\n", n - 1) {
+                            Ok(_) => match seeker.seek_back(b"\n") {
+                                Ok(idx) => pos = idx + 1,
+                                Err(e) => match e.kind() {
+                                    byteseeker::ErrorKind::ByteNotFound => pos = 0,
+                                    _ => return Err(e.into()),
+                                },
+                            },
                             Err(e) => match e.kind() {
-                                byteseeker::ErrorKind::ByteNotFound => 0,
+                                byteseeker::ErrorKind::ByteNotFound => {
+                                    return Err(ErrorKind::InvalidSkip)
+                                }
                                 _ => return Err(e.into()),
                             },
                         },
-                        Skip::Repeats(bytes) => {
-                            let width = bytes.len();
-                            match width {
-                                0 => stream_len,
-                                _ => {
-                                    let mut buf = vec![0; width];
-
-                                    let mut reader = seeker.get_mut();
-                                    util::seek_to_end(&mut reader)?;
-                                    let mut bytes_match = 0;
-                                    loop {
-                                        // Avoid seek negative.
-                                        if bytes_match + width > stream_len {
-                                            break;
-                                        }
-                                        util::seek_end(
-                                            -((bytes_match + width) as i64),
-                                            reader,
-                                        )?;
-                                        reader.read_exact(&mut buf)?;
-                                        if buf == bytes {
-                                            bytes_match += width;
-                                            if bytes_match == stream_len {
-                                                break;
-                                            }
-                                        } else {
-                                            break;
-                                        }
-                                    }
-
-                                    stream_len - bytes_match
-                                }
-                            }
-                        }
-                    },
-                };
-
-                match (start, end) {
-                    _ if end < start => return Err(ErrorKind::InvalidSkip),
-                    _ if start == end => (),
-                    _ => {
-                        // Reads the desired contents.
-                        let bytes_count = end - start;
-                        match bytes_count {
-                            0 => (),
-                            _ => {
-                                let mut buf_reader = BufReader::new(reader);
-                                let mut read = 0;
-                                util::seek_start(
-                                    start as u64,
-                                    &mut buf_reader,
-                                )?;
-
-                                loop {
-                                    let buf = buf_reader.fill_buf()?;
-                                    let length = buf.len();
-                                    if length == 0 {
-                                        break;
-                                    }
-                                    if read + length > bytes_count {
-                                        let mut buffer = buf.to_owned();
-                                        buffer.truncate(bytes_count - read);
-                                        writer.write_all(&buffer)?;
-                                    } else {
-                                        read += length;
-                                        writer.write_all(buf)?;
-                                    }
-                                    buf_reader.consume(length);
-                                }
-                            }
-                        }
                     }
-                }
-            } else {
-                // Just copy the entire contents of the given reader into the given writer.
-                io::copy(reader, writer)?;
-            }
-        }
 
-        // Should we writer ending newline?
-        if self.opts.newline.is_some() && !endn {
-            match self.opts.newline.unwrap() {
-                Newline::Lf => {
-                    writer.write_all(b"\n")?;
-                }
-                Newline::Crlf => {
-                    writer.write_all(b"\r\n")?;
+                    pos
                 }
+            },
+        },
+        Skip::Until(bytes) | Skip::Before(bytes) if bytes.is_empty() => stream_len,
+        Skip::Until(bytes) => util::rfind(bytes, seeker.get_mut())?.unwrap_or(0),
+        Skip::Before(bytes) => match util::find(bytes, seeker.get_mut())? {
+            Some(pos) => pos + bytes.len(),
+            None => 0,
+        },
+        #[cfg(feature = "regex")]
+        Skip::UntilMatch(_) | Skip::BeforeMatch(_) => {
+            let reader = seeker.get_mut();
+            util::seek_to_start(reader)?;
+            let tail = TailScan::new(Some(skip), pos, terminator, limit);
+            stream::scan_tail(tail, reader, stream_len as u64, endn)? as usize
+        }
+        Skip::Custom(rule) => {
+            let reader = seeker.get_mut();
+            match rule.skip(&mut SkipSource::new(reader, Side::Tail, pos, terminator)?)? {
+                n if n > stream_len => return Err(ErrorKind::InvalidSkip),
+                n => stream_len - n,
             }
         }
-
-        // Writes padding after this source.
-        self.write_padding_after(writer, pos)?;
-
-        Ok(())
-    }
-
-    fn write_padding_before<W: Write>(
-        &self,
-        writer: &mut W,
-        pos: PartPos,
-    ) -> Result<()> {
-        if let Some(pad) = &self.opts.padding {
-            // Check if padding should be filled before this source.
-            match (pad, pos) {
-                (Pad::Before(padding), PartPos::Start) => {
-                    writer.write_all(padding)?;
-                }
-                (Pad::Custom(Some(padding), _, _), PartPos::Start) => {
-                    writer.write_all(padding)?;
-                }
-                _ => (),
+        Skip::Chain(skips) => {
+            // Each skip applies to what the previous ones left.
+            let reader = seeker.get_mut();
+            let mut end = stream_len;
+            for skip in skips {
+                let mut window = Window::new(&mut *reader, 0, end as u64);
+                end = tail_end(skip, &mut window, pos, terminator, limit)?;
             }
+            end
         }
+        Skip::Repeats(bytes) => {
+            let width = bytes.len();
+            match width {
+                0 => stream_len,
+                _ => {
+                    let mut buf = vec![0; width];
 
-        Ok(())
-    }
+                    let mut reader = seeker.get_mut();
+                    util::seek_to_end(&mut reader)?;
+                    let mut bytes_match = 0;
+                    loop {
+                        // Avoid seek negative.
+                        if bytes_match + width > stream_len {
+                            break;
+                        }
+                        util::seek_end(-((bytes_match + width) as i64), reader)?;
+                        reader.read_exact(&mut buf)?;
+                        if buf == bytes {
+                            bytes_match += width;
+                            if bytes_match == stream_len {
+                                break;
+                            }
+                        } else {
+                            break;
+                        }
+                    }
 
-    fn write_padding_after<W: Write>(
-        &self,
-        writer: &mut W,
-        pos: PartPos,
-    ) -> Result<()> {
-        if let Some(pad) = &self.opts.padding {
-            // Check if padding should be filled before this source.
-            match (pad, pos) {
-                (Pad::After(padding), PartPos::End) => {
-                    writer.write_all(padding)?;
-                }
-                (Pad::Between(padding), PartPos::Start) => {
-                    writer.write_all(padding)?;
+                    stream_len - bytes_match
                 }
-                (Pad::Between(padding), PartPos::Inside) => {
-                    writer.write_all(padding)?;
-                }
-                (Pad::Custom(_, Some(padding), _), PartPos::Start) => {
-                    writer.write_all(padding)?;
-                }
-                (Pad::Custom(_, Some(padding), _), PartPos::Inside) => {
-                    writer.write_all(padding)?;
-                }
-                (Pad::Custom(_, _, Some(padding)), PartPos::End) => {
-                    writer.write_all(padding)?;
-                }
-                _ => (),
             }
         }
+    };
 
-        Ok(())
-    }
-
-    // Returns `true` if viewing into the contents of each part is required.
-    fn should_view_contents(&self) -> bool {
-        self.opts.newline.is_some()
-            || self.opts.skip_head.is_some()
-            || self.opts.skip_tail.is_some()
-    }
+    Ok(end)
 }
 
 /// Simliar to [`RsMerger`] but provides dedicated methods to work with [`Path`]s and [`File`]s.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct FileMerger<'a>(RsMerger<'a>);
 
-impl<'a> Default for FileMerger<'a> {
-    fn default() -> Self {
-        let opts = RsMergerOptions {
-            skip_head: None,
-            skip_tail: None,
-            padding: None,
-            newline: None,
-        };
-        FileMerger(RsMerger { opts })
-    }
-}
-
 impl<'a> FileMerger<'a> {
     /// Creates a new `FileMerger` builder.
     pub fn new() -> Self {
@@ -902,8 +2761,145 @@ impl<'a> FileMerger<'a> {
     }
 
     /// Configures this merger to force the presence of ending newline after each file.
-    pub fn force_ending_newline(&mut self, newline: Newline) -> &mut Self {
-        self.0.opts.newline = Some(newline);
+    pub fn force_ending_newline(&mut self, newline: impl Into<NewlineStyle>) -> &mut Self {
+        self.0.opts.newline = Some(newline.into());
+        self
+    }
+
+    /// Configures the line terminators that end lines of each file, see
+    /// [`RsMerger::line_terminator`].
+    pub fn line_terminator(&mut self, terminator: Terminator<'a>) -> &mut Self {
+        self.0.opts.terminator = terminator;
+        self
+    }
+
+    /// Configures this merger to check that the contents kept from each file are made of whole
+    /// records of the given size, see [`RsMerger::check_records`].
+    pub fn check_records(&mut self, size: usize) -> &mut Self {
+        self.0.opts.record_size = Some(size);
+        self
+    }
+
+    /// Configures this merger to treat files as CSV in the given format, keeping the header of the
+    /// first file only, see [`RsMerger::csv_headers`].
+    pub fn csv_headers(&mut self, csv: Csv) -> &mut Self {
+        self.0.opts.csv = Some(csv);
+        self
+    }
+
+    /// Configures this merger to rewrite the records of CSV files so that they hold the given
+    /// columns, see [`RsMerger::csv_columns`].
+    pub fn csv_columns(&mut self, columns: Columns<'a>) -> &mut Self {
+        self.0.opts.columns = Some(columns);
+        self
+    }
+
+    /// Sets the field that fills columns a CSV file lacks, see [`RsMerger::missing_fields`].
+    pub fn missing_fields(&mut self, field: &'a [u8]) -> &mut Self {
+        self.0.opts.missing = field;
+        self
+    }
+
+    /// Configures this merger to start each file at a multiple of the given alignment, see
+    /// [`RsMerger::align_parts`].
+    pub fn align_parts(&mut self, alignment: u64) -> &mut Self {
+        self.0.opts.align_parts = Some(alignment);
+        self
+    }
+
+    /// Configures this merger to end the merged output at a multiple of the given alignment,
+    /// see [`RsMerger::align_output`].
+    pub fn align_output(&mut self, alignment: u64) -> &mut Self {
+        self.0.opts.align_output = Some(alignment);
+        self
+    }
+
+    /// Configures this merger to fill the merged output up to the given size, see
+    /// [`RsMerger::image_size`].
+    pub fn image_size(&mut self, size: u64) -> &mut Self {
+        self.0.opts.image_size = Some(size);
+        self
+    }
+
+    /// Configures the byte written to align files and to fill images, see
+    /// [`RsMerger::fill_byte`].
+    pub fn fill_byte(&mut self, fill: u8) -> &mut Self {
+        self.0.opts.fill = fill;
+        self
+    }
+
+    /// Configures this merger to work out an index of the merged output, see
+    /// [`RsMerger::emit_index`].
+    pub fn emit_index(&mut self, placement: IndexPlacement) -> &mut Self {
+        self.0.opts.index = Some(placement);
+        self
+    }
+
+    /// Configures this merger to rewrite every line ending into the given newline, see
+    /// [`RsMerger::normalize_newlines`].
+    pub fn normalize_newlines(&mut self, newline: impl Into<NewlineStyle>) -> &mut Self {
+        self.0.opts.normalize = Some(newline.into());
+        self
+    }
+
+    /// Configures this merger to write the given byte order mark at the top of the output, see
+    /// [`RsMerger::emit_bom`].
+    pub fn emit_bom(&mut self, bom: Bom) -> &mut Self {
+        self.0.opts.bom = Some(bom);
+        self
+    }
+
+    /// Configures this merger to decode files into UTF-8, see [`RsMerger::decode_sources`].
+    ///
+    /// This method is only available if the `encoding_rs` feature is enabled.
+    #[cfg(feature = "encoding_rs")]
+    pub fn decode_sources(&mut self, decode: Decode<'a>) -> &mut Self {
+        self.0.opts.decode = Some(decode);
+        self
+    }
+
+    /// Configures this merger to encode the merged output, see [`RsMerger::encode_output`].
+    ///
+    /// This method is only available if the `encoding_rs` feature is enabled.
+    #[cfg(feature = "encoding_rs")]
+    pub fn encode_output(&mut self, encoding: &'static Encoding) -> &mut Self {
+        self.0.opts.encode = Some(encoding);
+        self
+    }
+
+    /// Configures this merger to keep or drop lines of each file, see [`RsMerger::filter_lines`].
+    pub fn filter_lines(&mut self, filter: LineFilter<'a>) -> &mut Self {
+        self.0.opts.line_filter = Some(filter);
+        self
+    }
+
+    /// Configures the maximum number of bytes this merger may hold in memory while buffering
+    /// parts of a file, see [`RsMerger::buffer_limit`].
+    pub fn buffer_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.opts.buffer_limit = limit;
+        self
+    }
+
+    /// Configures this merger to spill buffered bytes into an anonymous temporary file once they
+    /// exceed the buffer limit, see [`RsMerger::spill_to_disk`].
+    ///
+    /// This method is only available if the `tempfile` feature is enabled.
+    #[cfg(feature = "tempfile")]
+    pub fn spill_to_disk(&mut self, spill: bool) -> &mut Self {
+        self.0.opts.spill = spill;
+        self
+    }
+
+    /// Configures the maximum number of files that [`with_paths`] and its variants may keep open
+    /// at the same time. Defaults to 1.
+    ///
+    /// Files are opened right before their contents are written and closed right after. With a
+    /// limit greater than 1, up to `limit - 1` upcoming files are opened ahead of time. A limit of
+    /// 0 is treated as 1.
+    ///
+    /// [`with_paths`]: FileMerger::with_paths
+    pub fn max_open_files(&mut self, limit: usize) -> &mut Self {
+        self.0.opts.max_open_files = limit.max(1);
         self
     }
 
@@ -913,6 +2909,9 @@ impl<'a> FileMerger<'a> {
     /// Noting that this method will return an error if any path given is not point to a regular
     /// file. For an variant that ignores invalid paths, see [`with_paths_lossy`].
     ///
+    /// Every path is checked before anything is written, but files are only opened when they are
+    /// about to be written, see [`max_open_files`].
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -949,23 +2948,221 @@ impl<'a> FileMerger<'a> {
     /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
     ///
     /// [`with_paths_lossy`]: FileMerger::with_paths_lossy
+    /// [`max_open_files`]: FileMerger::max_open_files
     pub fn with_paths<P, W>(&self, paths: Vec<P>, writer: &mut W) -> Result<()>
     where
         P: AsRef<Path>,
         W: Write,
     {
-        let sources: Result<Vec<_>> = paths
+        self.with_paths_with_report(paths, writer).map(|_| ())
+    }
+
+    /// Does the same as [`with_paths`], returning a [`MergeReport`] telling where each file
+    /// landed in the output.
+    ///
+    /// [`with_paths`]: FileMerger::with_paths
+    pub fn with_paths_with_report<P, W>(&self, paths: Vec<P>, writer: &mut W) -> Result<MergeReport>
+    where
+        P: AsRef<Path>,
+        W: Write,
+    {
+        if let Some(i) = paths.iter().position(|p| !p.as_ref().is_file()) {
+            return Err(ErrorKind::InvalidPath(i));
+        }
+
+        self.with_paths_iter_with_report(paths, writer)
+    }
+
+    /// Works out what merging the files at the given paths would write, without writing anything.
+    ///
+    /// Files are opened one at a time and closed once their ranges are worked out. See
+    /// [`RsMerger::plan`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given path slice is
+    /// empty;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidPath`] if the given paths contain invalid
+    /// path.
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given sources;
+    ///
+    /// Returns an error variant of [`ErrorKind::Unsupported`] if files are transcoded or their
+    /// line endings normalised;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    pub fn plan<P: AsRef<Path>>(&self, paths: &[P]) -> Result<MergePlan<'a>> {
+        self.0.check_offsets("plan")?;
+        let count = paths.len();
+        if count == 0 {
+            return Err(ErrorKind::NothingPassed);
+        }
+        if let Some(i) = paths.iter().position(|p| !p.as_ref().is_file()) {
+            return Err(ErrorKind::InvalidPath(i));
+        }
+
+        let mut merger = Cow::Borrowed(&self.0);
+        let mut plan = MergePlan::default();
+        for (i, path) in paths.iter().enumerate() {
+            let pos = PartPos::of(i, count);
+            let mut file = File::open(path)?;
+            if i == 0 {
+                merger = self.0.resolved(&mut file)?;
+            }
+            let mut range = merger.part_range(&mut file, pos)?;
+            let runs = merger.part_runs(&mut file, &mut range)?;
+            plan.push(&merger, range, runs, pos)?;
+        }
+        plan.finish(&merger)?;
+
+        Ok(plan)
+    }
+
+    /// Opens the file paths yielded by the given iterator one by one, and merges file contents
+    /// into the given writer according to the given configrations.
+    ///
+    /// Unlike [`with_paths`], the paths are not collected up front: each path is only checked and
+    /// opened when its contents are about to be written, and the file is closed right after.
+    /// Therefore, if an invalid path is encountered, the contents of the preceding files have
+    /// already been written.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use admerge::{FileMerger, Newline, Result};
+    /// use std::fs::OpenOptions;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut file = OpenOptions::new().append(true).create(true).open("merged.txt")?;
+    ///
+    ///     // Configures merger.
+    ///     let mut merger = FileMerger::new();
+    ///     merger.force_ending_newline(Newline::Lf);
+    ///
+    ///     // Merges 50k shards without keeping them open.
+    ///     let paths = (0..50_000).map(|i| format!("shards/{}.log", i));
+    ///     merger.with_paths_iter(paths, &mut file)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::NothingPassed`] if the given iterator yields
+    /// nothing;
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidPath`] if the given iterator yields an
+    /// invalid path.
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given sources;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    ///
+    /// [`with_paths`]: FileMerger::with_paths
+    pub fn with_paths_iter<I, P, W>(&self, paths: I, writer: &mut W) -> Result<()>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+        W: Write,
+    {
+        self.with_paths_iter_with_report(paths, writer).map(|_| ())
+    }
+
+    /// Does the same as [`with_paths_iter`], returning a [`MergeReport`] telling where each file
+    /// landed in the output.
+    ///
+    /// [`with_paths_iter`]: FileMerger::with_paths_iter
+    pub fn with_paths_iter_with_report<I, P, W>(
+        &self,
+        paths: I,
+        writer: &mut W,
+    ) -> Result<MergeReport>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+        W: Write,
+    {
+        let open = |(i, p): (usize, P)| {
+            if !p.as_ref().is_file() {
+                return Err(ErrorKind::InvalidPath(i));
+            }
+            let path = p.as_ref().to_path_buf();
+            Ok((File::open(p)?, path))
+        };
+
+        // Every header is read first if the union of columns is merged.
+        let mut paths = paths.into_iter();
+        let collected: Option<Vec<P>> = match self.0.opts.columns {
+            Some(Columns::Union) => Some(paths.by_ref().collect()),
+            _ => None,
+        };
+        let mut union = match &collected {
+            Some(collected) => {
+                let files = collected.iter().enumerate().map(|(i, p)| {
+                    if !p.as_ref().is_file() {
+                        return Err(ErrorKind::InvalidPath(i));
+                    }
+                    Ok(File::open(p)?)
+                });
+                self.0.union(files)?
+            }
+            None => None,
+        };
+
+        let mut paths = collected
             .into_iter()
+            .flatten()
+            .chain(paths)
             .enumerate()
-            .map(|(i, p)| {
-                if !p.as_ref().is_file() {
-                    return Err(ErrorKind::InvalidPath(i));
+            .peekable();
+        let mut files = VecDeque::new();
+        let mut writer = Tracker::new(writer, &self.0);
+        let mut merger = Cow::Borrowed(&self.0);
+        let mut report = MergeReport::default();
+        loop {
+            // Opens the next file, and as many upcoming files as allowed.
+            while files.len() < self.0.opts.max_open_files {
+                match paths.next() {
+                    Some(path) => files.push_back(open(path)?),
+                    None => break,
+                }
+            }
+
+            let (mut file, path) = match files.pop_front() {
+                Some(opened) => opened,
+                None => break,
+            };
+            let last = files.is_empty() && paths.peek().is_none();
+            let pos = match (report.parts().len(), last) {
+                (0, _) => PartPos::Start,
+                (_, true) => PartPos::End,
+                _ => PartPos::Inside,
+            };
+            let index = report.parts().len();
+            if index == 0 {
+                merger = self.0.resolved(&mut file)?;
+                if let Some(union) = union.take() {
+                    merger.to_mut().opts.union = Some(union);
                 }
-                File::open(p).map_err(ErrorKind::Io)
-            })
-            .collect();
+            }
+            merger.write_fill(&mut writer, merger.fill_before(report.len()))?;
+            writer.begin(&merger, pos)?;
+            let mut range = merger.write_contents(&mut file, &mut writer, pos, index)?;
+            writer.end(&mut range);
+            report.push(&merger, range, pos, Some(path))?;
+        }
 
-        self.with_files(sources?, writer)
+        if report.parts().is_empty() {
+            return Err(ErrorKind::NothingPassed);
+        }
+        merger.write_fill(&mut writer, report.finish(&merger)?)?;
+        merger.write_footer(&mut writer, &mut report)?;
+
+        Ok(report)
     }
 
     /// Opens every file path given if path points to a regular file, and then merges file contents
@@ -1011,6 +3208,22 @@ impl<'a> FileMerger<'a> {
         paths: Vec<P>,
         writer: &mut W,
     ) -> Result<()>
+    where
+        P: AsRef<Path>,
+        W: Write,
+    {
+        self.with_paths_lossy_with_report(paths, writer).map(|_| ())
+    }
+
+    /// Does the same as [`with_paths_lossy`], returning a [`MergeReport`] telling where each file
+    /// landed in the output.
+    ///
+    /// [`with_paths_lossy`]: FileMerger::with_paths_lossy
+    pub fn with_paths_lossy_with_report<P, W>(
+        &self,
+        paths: Vec<P>,
+        writer: &mut W,
+    ) -> Result<MergeReport>
     where
         P: AsRef<Path>,
         W: Write,
@@ -1019,7 +3232,7 @@ impl<'a> FileMerger<'a> {
         let sources: Vec<_> =
             paths.into_iter().filter(|p| p.as_ref().is_file()).collect();
 
-        self.with_paths(sources, writer)
+        self.with_paths_with_report(sources, writer)
     }
 
     /// Reads sequentially from the given files and merges their contents into the given writer
@@ -1063,6 +3276,17 @@ impl<'a> FileMerger<'a> {
     where
         W: Write,
     {
-        self.0.merge_sources_into(files, writer)
+        self.with_files_with_report(files, writer).map(|_| ())
+    }
+
+    /// Does the same as [`with_files`], returning a [`MergeReport`] telling where each file
+    /// landed in the output.
+    ///
+    /// [`with_files`]: FileMerger::with_files
+    pub fn with_files_with_report<W>(&self, files: Vec<File>, writer: &mut W) -> Result<MergeReport>
+    where
+        W: Write,
+    {
+        self.0.merge_sources_into_with_report(files, writer)
     }
 }
@@ -0,0 +1,125 @@
+//! Line-ending normalisation of merged contents.
+use crate::error::Result;
+use crate::merge::Newline;
+use crate::rewrite::Rewrite;
+
+use std::borrow::Cow;
+use std::io::{self, Read};
+
+/// Rewrites every `\n`, `\r\n` and lone `\r` into the given newline, chunk by chunk.
+pub(crate) struct Normalizer {
+    newline: &'static [u8],
+    // Whether the last chunk ended with a `\r` that may be followed by a `\n`.
+    cr: bool,
+}
+
+impl Normalizer {
+    pub(crate) fn new(newline: &'static [u8]) -> Self {
+        Normalizer { newline, cr: false }
+    }
+}
+
+impl Rewrite for Normalizer {
+    // Normalises the next chunk into `out`, holding back a trailing `\r`.
+    fn push(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        for &b in chunk {
+            if self.cr {
+                self.cr = false;
+                out.extend_from_slice(self.newline);
+                if b == b'\n' {
+                    continue;
+                }
+            }
+            match b {
+                b'\r' => self.cr = true,
+                b'\n' => out.extend_from_slice(self.newline),
+                _ => out.push(b),
+            }
+        }
+        Ok(())
+    }
+
+    // Writes out a `\r` held back at the very end.
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        if self.cr {
+            self.cr = false;
+            out.extend_from_slice(self.newline);
+        }
+        Ok(())
+    }
+}
+
+// Normalises the line endings of the given bytes at once.
+pub(crate) fn normalize<'b>(bytes: &'b [u8], newline: &'static [u8]) -> Cow<'b, [u8]> {
+    let is_normal = match newline {
+        b"\n" => !bytes.contains(&b'\r'),
+        _ => is_crlf_only(bytes),
+    };
+    if is_normal {
+        return Cow::Borrowed(bytes);
+    }
+
+    let mut normalizer = Normalizer::new(newline);
+    let mut out = Vec::with_capacity(bytes.len());
+    // Normalising never fails.
+    normalizer.push(bytes, &mut out).unwrap();
+    normalizer.finish(&mut out).unwrap();
+    Cow::Owned(out)
+}
+
+// Returns `true` if every line ending of the given bytes is a `\r\n`.
+fn is_crlf_only(bytes: &[u8]) -> bool {
+    bytes.iter().enumerate().all(|(i, &b)| match b {
+        b'\r' => bytes.get(i + 1) == Some(&b'\n'),
+        b'\n' => i > 0 && bytes[i - 1] == b'\r',
+        _ => true,
+    })
+}
+
+/// Counts the line endings of a source to tell its dominant newline style.
+///
+/// NUL bytes are ignored, so that line endings of UTF-16 sources are counted as well.
+#[derive(Default)]
+pub(crate) struct Detector {
+    crlf: u64,
+    lf: u64,
+    // Whether the last byte counted was a `\r`.
+    cr: bool,
+}
+
+impl Detector {
+    pub(crate) fn push(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            match b {
+                0 => continue,
+                b'\n' if self.cr => self.crlf += 1,
+                b'\n' => self.lf += 1,
+                _ => (),
+            }
+            self.cr = b == b'\r';
+        }
+    }
+
+    // Returns `Newline::Crlf` if most line endings were `\r\n`, and `Newline::Lf` otherwise.
+    pub(crate) fn newline(&self) -> Newline {
+        match self.crlf > self.lf {
+            true => Newline::Crlf,
+            false => Newline::Lf,
+        }
+    }
+}
+
+// Reads the given reader to the end and returns its dominant newline style.
+pub(crate) fn detect<R: Read>(reader: &mut R) -> Result<Newline> {
+    let mut detector = Detector::default();
+    let mut buf = [0; 8 * 1024];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => detector.push(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(detector.newline())
+}
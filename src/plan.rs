@@ -0,0 +1,154 @@
+//! Dry-run descriptions of merges.
+use crate::error::Result;
+use crate::merge::{PartPos, PartRange, RsMerger};
+
+use std::ops::Range;
+
+/// A description of what a merge would write, worked out without writing anything.
+///
+/// This struct is created by [`RsMerger::plan`] and [`FileMerger::plan`].
+///
+/// # Examples
+///
+/// ```
+/// use admerge::{RsMerger, Skip, Pad, Newline, Result};
+/// use std::io::Cursor;
+///
+/// fn main() -> Result<()> {
+///     let mut sources = vec![Cursor::new("header\n 1\n"), Cursor::new("header\n 2")];
+///
+///     let mut merger = RsMerger::new();
+///     merger.skip_head(Skip::LinesOnce(1));
+///     merger.pad_with(Pad::Before(b"#\n"));
+///     merger.force_ending_newline(Newline::Lf);
+///
+///     let plan = merger.plan(&mut sources)?;
+///     assert_eq!(plan.len(), 15);
+///
+///     let part = &plan.parts()[1];
+///     assert_eq!(part.head_skipped, 0..7);
+///     assert_eq!(part.kept, 7..9);
+///     assert_eq!(part.tail_skipped, 9..9);
+///     assert_eq!(part.newline, Some(&b"\n"[..]));
+///     assert_eq!(part.output, 12..14);
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`FileMerger::plan`]: crate::FileMerger::plan
+#[derive(Clone, Debug, Default)]
+pub struct MergePlan<'a> {
+    parts: Vec<PartPlan<'a>>,
+    fill: u64,
+    len: u64,
+}
+
+/// The plan for one source of a merge, see [`MergePlan`].
+///
+/// Ranges in `head_skipped`, `kept` and `tail_skipped` are byte offsets into the source, while
+/// `output` is a byte range of the merged output.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartPlan<'a> {
+    /// Index of this source among the given sources.
+    pub index: usize,
+    /// Length of this source.
+    pub source_len: u64,
+    /// Range skipped from the head of this source.
+    pub head_skipped: Range<u64>,
+    /// Range of this source that will be written.
+    pub kept: Range<u64>,
+    /// Range skipped from the tail of this source.
+    pub tail_skipped: Range<u64>,
+    /// Ranges of this source that will be written, in order.
+    ///
+    /// This is `kept` itself unless lines are filtered out by [`RsMerger::filter_lines`], in
+    /// which case each range covers a run of adjacent kept lines. Empty ranges are left out.
+    pub runs: Vec<Range<u64>>,
+    /// Number of fill bytes written before this source to align it, see
+    /// [`RsMerger::align_parts`].
+    pub fill: u64,
+    /// Byte order mark written before this source and its padding, if any.
+    pub bom: Option<&'static [u8]>,
+    /// Padding written before this source, if any.
+    pub padding_before: Option<&'a [u8]>,
+    /// Padding written after this source, if any.
+    pub padding_after: Option<&'a [u8]>,
+    /// Newline, or record delimiter, appended after the kept contents, if any.
+    pub newline: Option<&'a [u8]>,
+    /// Range of the merged output the kept contents will occupy.
+    pub output: Range<u64>,
+}
+
+impl<'a> MergePlan<'a> {
+    /// Returns the plans of all sources, in merge order.
+    pub fn parts(&self) -> &[PartPlan<'a>] {
+        &self.parts
+    }
+
+    /// Returns the number of fill bytes written after the last source, see
+    /// [`RsMerger::align_output`] and [`RsMerger::image_size`].
+    pub fn fill(&self) -> u64 {
+        self.fill
+    }
+
+    /// Returns the total length of the merged output.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the merged output would be empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Appends the plan of the next part.
+    pub(crate) fn push(
+        &mut self,
+        merger: &RsMerger<'a>,
+        range: PartRange,
+        runs: Vec<Range<u64>>,
+        pos: PartPos,
+    ) -> Result<()> {
+        merger.check_part(self.parts.len(), &range)?;
+        let bom = merger.bom_before(pos);
+        let padding_before = merger.padding_before(pos);
+        let padding_after = merger.padding_after(pos);
+        let newline = merger.ending_newline(range.endn);
+        let fill = merger.fill_before(self.len);
+
+        let start = self.len
+            + fill
+            + bom.map_or(0, |b| b.len() as u64)
+            + padding_before.map_or(0, |p| p.len() as u64);
+        let end = start + range.written;
+        self.len = end
+            + newline.map_or(0, |n| n.len() as u64)
+            + padding_after.map_or(0, |p| p.len() as u64);
+        merger.check_image_size(self.len)?;
+
+        self.parts.push(PartPlan {
+            index: self.parts.len(),
+            source_len: range.len,
+            head_skipped: 0..range.start,
+            kept: range.start..range.end,
+            tail_skipped: range.end..range.len,
+            runs,
+            fill,
+            bom,
+            padding_before,
+            padding_after,
+            newline,
+            output: start..end,
+        });
+        Ok(())
+    }
+
+    // Records the fill bytes after the last part.
+    pub(crate) fn finish(&mut self, merger: &RsMerger<'a>) -> Result<()> {
+        self.fill = merger.fill_end(self.len)?;
+        self.len += self.fill;
+        Ok(())
+    }
+}
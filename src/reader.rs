@@ -0,0 +1,198 @@
+//! A reader that exposes merged sources without writing them anywhere.
+use crate::error::Result;
+use crate::merge::RsMerger;
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// A reader over the logical concatenation of several sources, as produced by an [`RsMerger`].
+///
+/// `MergedReader` yields exactly the bytes [`RsMerger::merge_sources_into`] would write, including
+/// paddings and forced newlines, but reads them lazily from the underlying sources on demand.
+/// The kept range of each source is computed up front, so `MergedReader` also implements
+/// [`Seek`] and allows random access into the merged stream without materialising it.
+///
+/// This struct is created by [`RsMerger::merged_reader`].
+///
+/// # Examples
+///
+/// ```
+/// use admerge::{RsMerger, Skip, Pad, Result};
+/// use std::io::{Cursor, Read, Seek, SeekFrom};
+///
+/// fn main() -> Result<()> {
+///     let c1 = Cursor::new("header\nfirst\n");
+///     let c2 = Cursor::new("header\nsecond\n");
+///
+///     let mut merger = RsMerger::new();
+///     merger.skip_head(Skip::LinesOnce(1));
+///     merger.pad_with(Pad::Between(b"--\n"));
+///
+///     let mut reader = merger.merged_reader(vec![c1, c2])?;
+///     assert_eq!(reader.len(), 23);
+///
+///     let mut buf = String::new();
+///     reader.read_to_string(&mut buf)?;
+///     assert_eq!(buf, "header\nfirst\n--\nsecond\n");
+///
+///     let mut buf = String::new();
+///     reader.seek(SeekFrom::End(-7))?;
+///     reader.read_to_string(&mut buf)?;
+///     assert_eq!(buf, "second\n");
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`Seek`]: std::io::Seek
+#[derive(Debug)]
+pub struct MergedReader<'a, RS> {
+    sources: Vec<RS>,
+    segments: Vec<Segment<'a>>,
+    len: u64,
+    pos: u64,
+    // The source last read from and the position of its cursor, saves seeking on sequential reads.
+    cursor: Option<(usize, u64)>,
+}
+
+// A contiguous piece of the merged stream.
+#[derive(Clone, Copy, Debug)]
+struct Segment<'a> {
+    // Offset of this segment in the merged stream.
+    offset: u64,
+    kind: SegmentKind<'a>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SegmentKind<'a> {
+    // Bytes that do not come from any source, i.e. BOMs, paddings and newlines.
+    Bytes(&'a [u8]),
+    // A number of fill bytes.
+    Fill(u8, u64),
+    // The kept range of the source at the given index.
+    Source { index: usize, start: u64, end: u64 },
+}
+
+impl<'a> SegmentKind<'a> {
+    fn len(&self) -> u64 {
+        match *self {
+            SegmentKind::Bytes(bytes) => bytes.len() as u64,
+            SegmentKind::Fill(_, len) => len,
+            SegmentKind::Source { start, end, .. } => end - start,
+        }
+    }
+}
+
+impl<'a, RS: Read + Seek> MergedReader<'a, RS> {
+    pub(crate) fn new(merger: &RsMerger<'a>, mut sources: Vec<RS>) -> Result<Self> {
+        let plan = merger.plan(&mut sources)?;
+
+        let mut kinds = Vec::new();
+        for part in plan.parts() {
+            kinds.push(SegmentKind::Fill(merger.fill(), part.fill));
+            kinds.extend(part.bom.map(SegmentKind::Bytes));
+            kinds.extend(part.padding_before.map(SegmentKind::Bytes));
+            kinds.extend(part.runs.iter().map(|run| SegmentKind::Source {
+                index: part.index,
+                start: run.start,
+                end: run.end,
+            }));
+            kinds.extend(part.newline.map(SegmentKind::Bytes));
+            kinds.extend(part.padding_after.map(SegmentKind::Bytes));
+        }
+        kinds.push(SegmentKind::Fill(merger.fill(), plan.fill()));
+
+        // Empty segments are dropped so every remaining segment holds at least one byte.
+        let mut len = 0;
+        let mut segments = Vec::with_capacity(kinds.len());
+        for kind in kinds.into_iter().filter(|kind| kind.len() > 0) {
+            segments.push(Segment { offset: len, kind });
+            len += kind.len();
+        }
+
+        Ok(MergedReader { sources, segments, len, pos: 0, cursor: None })
+    }
+}
+
+impl<'a, RS> MergedReader<'a, RS> {
+    /// Returns the total length of the merged stream.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the merged stream contains no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Consumes this `MergedReader`, returning the underlying sources.
+    pub fn into_inner(self) -> Vec<RS> {
+        self.sources
+    }
+}
+
+impl<'a, RS: Read + Seek> Read for MergedReader<'a, RS> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+
+        // Finds the segment that contains the current position.
+        let i = self.segments.partition_point(|seg| seg.offset <= self.pos) - 1;
+        let segment = self.segments[i];
+        let skip = self.pos - segment.offset;
+        let want = (segment.kind.len() - skip).min(buf.len() as u64) as usize;
+
+        let n = match segment.kind {
+            SegmentKind::Bytes(bytes) => {
+                let skip = skip as usize;
+                buf[..want].copy_from_slice(&bytes[skip..skip + want]);
+                want
+            }
+            SegmentKind::Fill(fill, _) => {
+                buf[..want].fill(fill);
+                want
+            }
+            SegmentKind::Source { index, start, .. } => {
+                let at = start + skip;
+                let source = &mut self.sources[index];
+                if self.cursor != Some((index, at)) {
+                    source.seek(SeekFrom::Start(at))?;
+                }
+                let n = source.read(&mut buf[..want])?;
+                if n == 0 {
+                    // The source shrank since the ranges were computed.
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                self.cursor = Some((index, at + n as u64));
+                n
+            }
+        };
+
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<'a, RS: Read + Seek> Seek for MergedReader<'a, RS> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(n) => {
+                self.pos = n;
+                return Ok(n);
+            }
+            SeekFrom::End(n) => (self.len, n),
+            SeekFrom::Current(n) => (self.pos, n),
+        };
+
+        match base.checked_add_signed(offset) {
+            Some(n) => {
+                self.pos = n;
+                Ok(n)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Summaries of finished merges.
+use crate::error::Result;
+use crate::index::{Index, IndexEntry};
+use crate::merge::{Newline, PartPos, PartRange, RsMerger};
+
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// A summary of a finished merge, telling where each source landed in the output.
+///
+/// This struct is returned by the `_with_report` variant of every merging method, such as
+/// [`RsMerger::merge_sources_into_with_report`] and [`FileMerger::with_paths_with_report`].
+///
+/// # Examples
+///
+/// ```
+/// use admerge::{RsMerger, Skip, Pad, Newline, Result};
+/// use std::io::Cursor;
+///
+/// fn main() -> Result<()> {
+///     let c1 = Cursor::new("header\n 1\n");
+///     let c2 = Cursor::new("header\n 2");
+///     let mut buf = Vec::new();
+///
+///     let mut merger = RsMerger::new();
+///     merger.skip_head(Skip::LinesOnce(1));
+///     merger.pad_with(Pad::Between(b"--\n"));
+///     merger.force_ending_newline(Newline::Lf);
+///
+///     let report = merger.merge_sources_into_with_report(vec![c1, c2], &mut buf)?;
+///     assert_eq!(buf, b"header\n 1\n--\n 2\n");
+///     assert_eq!(report.len(), 16);
+///     assert_eq!(report.padding_len(), 3);
+///
+///     let part = &report.parts()[1];
+///     assert_eq!(part.source_len, 9);
+///     assert_eq!(part.head_skipped, 7);
+///     assert_eq!(part.output, 13..15);
+///     assert!(part.newline);
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`FileMerger::with_paths_with_report`]: crate::FileMerger::with_paths_with_report
+#[derive(Clone, Debug, Default)]
+pub struct MergeReport {
+    parts: Vec<PartReport>,
+    padding_len: u64,
+    len: u64,
+    newline: Option<Newline>,
+}
+
+/// The summary of one merged source, see [`MergeReport`].
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartReport {
+    /// Index of this source among the given sources.
+    pub index: usize,
+    /// Path of this source, if it was opened from a path.
+    pub path: Option<PathBuf>,
+    /// Length of this source.
+    pub source_len: u64,
+    /// Number of bytes skipped from the head of this source.
+    pub head_skipped: u64,
+    /// Number of bytes skipped from the tail of this source.
+    pub tail_skipped: u64,
+    /// Number of fill bytes written before this source to align it, see
+    /// [`RsMerger::align_parts`].
+    pub fill: u64,
+    /// Range of the output the kept contents of this source were written to.
+    pub output: Range<u64>,
+    /// Number of lines filtered out by [`RsMerger::filter_lines`].
+    pub lines_dropped: u64,
+    /// Whether a newline was appended after the kept contents.
+    pub newline: bool,
+    /// CRC-32 of the kept contents as written, if worked out, see [`RsMerger::emit_index`].
+    pub checksum: Option<u32>,
+}
+
+impl MergeReport {
+    /// Returns the summaries of all sources, in merge order.
+    pub fn parts(&self) -> &[PartReport] {
+        &self.parts
+    }
+
+    /// Returns the total number of bytes written.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if nothing was written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the total number of padding bytes written, fill bytes included.
+    pub fn padding_len(&self) -> u64 {
+        self.padding_len
+    }
+
+    /// Returns the index of the merged output, if [`RsMerger::emit_index`] was configured.
+    ///
+    /// Sources opened from paths are named after their paths, other sources after their
+    /// indices.
+    pub fn index(&self) -> Option<Index> {
+        let entries = self.parts.iter().map(|part| {
+            let name = match &part.path {
+                Some(path) => path.to_string_lossy().into_owned(),
+                None => part.index.to_string(),
+            };
+            Some(IndexEntry {
+                name,
+                offset: part.output.start,
+                len: part.output.end - part.output.start,
+                crc32: part.checksum?,
+            })
+        });
+        entries.collect::<Option<_>>().map(Index::new)
+    }
+
+    /// Returns the style of the newlines forced or normalised by the merge, if any.
+    ///
+    /// If [`NewlineStyle::Auto`] was configured, the style it was resolved to is returned.
+    ///
+    /// [`NewlineStyle::Auto`]: crate::NewlineStyle::Auto
+    pub fn newline(&self) -> Option<Newline> {
+        self.newline
+    }
+
+    // Records the part that was just written.
+    pub(crate) fn push(
+        &mut self,
+        merger: &RsMerger<'_>,
+        range: PartRange,
+        pos: PartPos,
+        path: Option<PathBuf>,
+    ) -> Result<()> {
+        merger.check_part(self.parts.len(), &range)?;
+        let bom = merger.bom_before(pos).map_or(0, |b| b.len() as u64);
+        let before = merger.output_len(merger.padding_before(pos).unwrap_or_default())?;
+        let after = merger.output_len(merger.padding_after(pos).unwrap_or_default())?;
+        let newline = merger.ending_newline(range.endn);
+        let fill = merger.fill_before(self.len);
+
+        let start = self.len + fill + bom + before;
+        let end = start + range.written;
+        self.len = end + merger.output_len(newline.unwrap_or_default())? + after;
+        self.padding_len += fill + before + after;
+        merger.check_image_size(self.len)?;
+        self.newline = merger.newline_style();
+
+        self.parts.push(PartReport {
+            index: self.parts.len(),
+            path,
+            source_len: range.len,
+            head_skipped: range.start,
+            tail_skipped: range.len - range.end,
+            fill,
+            output: start..end,
+            lines_dropped: range.dropped,
+            newline: newline.is_some(),
+            checksum: range.checksum,
+        });
+        Ok(())
+    }
+
+    // Records the index appended to the output.
+    pub(crate) fn push_footer(&mut self, len: u64) {
+        self.len += len;
+    }
+
+    // Records the fill bytes after the last part, returning how many must be written.
+    pub(crate) fn finish(&mut self, merger: &RsMerger<'_>) -> Result<u64> {
+        let fill = merger.fill_end(self.len)?;
+        self.len += fill;
+        self.padding_len += fill;
+        Ok(fill)
+    }
+}
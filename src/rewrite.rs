@@ -0,0 +1,106 @@
+//! Chunk by chunk rewriting of sources, such as transcoding or line-ending normalisation.
+use crate::error::Result;
+use crate::stream;
+
+use std::io::{self, Read};
+
+/// A transform that rewrites the contents of a source chunk by chunk.
+pub(crate) trait Rewrite {
+    // Rewrites the next chunk into `out`, possibly holding back bytes until the next one.
+    fn push(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()>;
+
+    // Writes out whatever was held back once the source ends.
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()>;
+}
+
+/// A reader that rewrites the contents of the underlying reader with the given [`Rewrite`].
+///
+/// Errors other than I/O ones are passed through `io::Error`, see `stream::recover`.
+pub(crate) struct RewriteReader<R, T> {
+    inner: R,
+    rewrite: T,
+    input: Vec<u8>,
+    // Rewritten bytes not handed out yet, starting at `pos`.
+    output: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read, T: Rewrite> RewriteReader<R, T> {
+    pub(crate) fn new(inner: R, rewrite: T) -> Self {
+        RewriteReader {
+            inner,
+            rewrite,
+            input: vec![0; 8 * 1024],
+            output: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: Read, T: Rewrite> Read for RewriteReader<R, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.output.len() {
+            if self.done {
+                return Ok(0);
+            }
+
+            let n = self.inner.read(&mut self.input)?;
+            self.output.clear();
+            self.pos = 0;
+            let result = match n {
+                0 => {
+                    self.done = true;
+                    self.rewrite.finish(&mut self.output)
+                }
+                n => self.rewrite.push(&self.input[..n], &mut self.output),
+            };
+            result.map_err(stream::into_io)?;
+        }
+
+        let n = buf.len().min(self.output.len() - self.pos);
+        buf[..n].copy_from_slice(&self.output[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Several transforms applied one after the other.
+#[derive(Default)]
+pub(crate) struct Pipeline<'r> {
+    stages: Vec<Box<dyn Rewrite + 'r>>,
+}
+
+impl<'r> Pipeline<'r> {
+    // Appends the given transform, which rewrites the output of the previous ones.
+    pub(crate) fn push_stage<T: Rewrite + 'r>(&mut self, rewrite: T) {
+        self.stages.push(Box::new(rewrite));
+    }
+}
+
+impl<'r> Rewrite for Pipeline<'r> {
+    fn push(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let mut input = chunk.to_vec();
+        for stage in &mut self.stages {
+            let mut output = Vec::with_capacity(input.len());
+            stage.push(&input, &mut output)?;
+            input = output;
+        }
+        out.extend_from_slice(&input);
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        // What a stage holds back is rewritten by the stages after it.
+        let mut input = Vec::new();
+        for stage in &mut self.stages {
+            let mut output = Vec::new();
+            stage.push(&input, &mut output)?;
+            stage.finish(&mut output)?;
+            input = output;
+        }
+        out.extend_from_slice(&input);
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+//! Splitting merged outputs back into their parts.
+use crate::error::{ErrorKind, Result};
+use crate::index::{Crc32, Index, IndexEntry};
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Reconstructs the kept contents of each source of a merged output, given its [`Index`].
+///
+/// Each part is read from the range its [`IndexEntry`] gives, and its length and CRC-32 are
+/// checked against the entry. As parts are written as they are read, a part found not to match
+/// its entry has already been written by the time the error is returned.
+///
+/// # Examples
+///
+/// ```
+/// use admerge::{RsMerger, IndexPlacement, Pad, Splitter, Result};
+/// use std::io::Cursor;
+///
+/// fn main() -> Result<()> {
+///     let mut bundle = Vec::new();
+///
+///     let mut merger = RsMerger::new();
+///     merger.pad_with(Pad::Between(b"--\n"));
+///     merger.emit_index(IndexPlacement::Footer);
+///     let sources = vec![Cursor::new("first\n"), Cursor::new("second\n")];
+///     merger.merge_sources_into(sources, &mut bundle)?;
+///
+///     let mut bundle = Cursor::new(bundle);
+///     let splitter = Splitter::from_footer(&mut bundle)?;
+///     let parts = splitter.split_with(&mut bundle, |_| Ok(Vec::new()))?;
+///     assert_eq!(parts, [b"first\n".to_vec(), b"second\n".to_vec()]);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Splitter {
+    index: Index,
+}
+
+impl Splitter {
+    /// Creates a new `Splitter` from the index of a merged output.
+    pub fn new(index: Index) -> Self {
+        Splitter { index }
+    }
+
+    /// Creates a new `Splitter` from the index appended to the given merged output, see
+    /// [`IndexPlacement::Footer`].
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidIndex`] if the given output does not end
+    /// with an index.
+    ///
+    /// [`IndexPlacement::Footer`]: crate::IndexPlacement::Footer
+    pub fn from_footer<RS: Read + Seek>(bundle: &mut RS) -> Result<Self> {
+        Index::read_footer(bundle).map(Splitter::new)
+    }
+
+    /// Returns the index this splitter follows.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Writes the part at the given index of the given merged output into the given writer,
+    /// returning the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::Truncated`] if the merged output ends before the
+    /// part does;
+    ///
+    /// Returns an error variant of [`ErrorKind::ChecksumMismatch`] if the part does not match its
+    /// CRC-32;
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given index is out of the bounds of the entries of the index.
+    pub fn split_part<RS, W>(&self, i: usize, bundle: &mut RS, writer: &mut W) -> Result<u64>
+    where
+        RS: Read + Seek,
+        W: Write,
+    {
+        let entry = &self.index.entries()[i];
+        bundle.seek(SeekFrom::Start(entry.offset))?;
+
+        let mut writer = Hasher {
+            inner: writer,
+            crc: Crc32::new(),
+        };
+        let copied = io::copy(&mut bundle.take(entry.len), &mut writer)?;
+        if copied != entry.len {
+            return Err(ErrorKind::Truncated(i));
+        }
+        if writer.crc.finish() != entry.crc32 {
+            return Err(ErrorKind::ChecksumMismatch(i));
+        }
+
+        Ok(copied)
+    }
+
+    /// Writes every part of the given merged output into the writer `open` returns for its
+    /// entry, returning the writers in order.
+    ///
+    /// See [`split_part`] for the errors returned.
+    ///
+    /// [`split_part`]: Splitter::split_part
+    pub fn split_with<RS, W, F>(&self, bundle: &mut RS, mut open: F) -> Result<Vec<W>>
+    where
+        RS: Read + Seek,
+        W: Write,
+        F: FnMut(&IndexEntry) -> io::Result<W>,
+    {
+        let mut writers = Vec::with_capacity(self.index.entries().len());
+        for (i, entry) in self.index.entries().iter().enumerate() {
+            let mut writer = open(entry)?;
+            self.split_part(i, bundle, &mut writer)?;
+            writer.flush()?;
+            writers.push(writer);
+        }
+
+        Ok(writers)
+    }
+
+    /// Writes every part of the given merged output into a new file in the given directory,
+    /// returning the paths of the files written.
+    ///
+    /// Each file is named after the last component of the name of its entry, or after the
+    /// index of its entry if the name has none. Entries whose names end alike, such as
+    /// `a/data.csv` and `b/data.csv`, have the index of their entry inserted before the
+    /// extension instead, as in `data.0.csv` and `data.1.csv`. Existing files are never
+    /// overwritten: an error variant of [`ErrorKind::Io`] is returned instead.
+    ///
+    /// Parts are written one after the other, so the files of the parts before the one that
+    /// failed are left in the directory when an error is returned.
+    ///
+    /// See [`split_part`] for the other errors returned.
+    ///
+    /// [`split_part`]: Splitter::split_part
+    pub fn split_to_dir<RS, P>(&self, bundle: &mut RS, dir: P) -> Result<Vec<PathBuf>>
+    where
+        RS: Read + Seek,
+        P: AsRef<Path>,
+    {
+        let names = self.file_names();
+        let mut paths = Vec::with_capacity(names.len());
+        for (i, name) in names.into_iter().enumerate() {
+            let path = dir.as_ref().join(name);
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)?;
+            self.split_part(i, bundle, &mut file)?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    /// Checks the length and CRC-32 of every part of the given merged output, without writing
+    /// them anywhere.
+    ///
+    /// See [`split_part`] for the errors returned.
+    ///
+    /// [`split_part`]: Splitter::split_part
+    pub fn verify<RS: Read + Seek>(&self, bundle: &mut RS) -> Result<()> {
+        for i in 0..self.index.entries().len() {
+            self.split_part(i, bundle, &mut io::sink())?;
+        }
+
+        Ok(())
+    }
+}
+
+// Private methods
+impl Splitter {
+    // Returns the names of the files the parts are written into, see `split_to_dir`.
+    fn file_names(&self) -> Vec<OsString> {
+        let names: Vec<OsString> = self
+            .index
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| match Path::new(&entry.name).file_name() {
+                Some(name) => name.to_owned(),
+                None => i.to_string().into(),
+            })
+            .collect();
+
+        let mut counts = HashMap::new();
+        for name in &names {
+            *counts.entry(name.clone()).or_insert(0) += 1;
+        }
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| match counts[name] {
+                1 => name.clone(),
+                _ => {
+                    let path = Path::new(name);
+                    let mut name = path.file_stem().unwrap_or_default().to_owned();
+                    name.push(format!(".{}", i));
+                    if let Some(extension) = path.extension() {
+                        name.push(".");
+                        name.push(extension);
+                    }
+                    name
+                }
+            })
+            .collect()
+    }
+}
+
+// A writer that works out the CRC-32 of the bytes written through it.
+struct Hasher<'w, W> {
+    inner: &'w mut W,
+    crc: Crc32,
+}
+
+impl<'w, W: Write> Write for Hasher<'w, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.crc.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
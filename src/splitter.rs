@@ -0,0 +1,582 @@
+//! Splitting one source into many parts, the other way around of merging.
+use crate::error::{ErrorKind, Result};
+use crate::merge::{self, NewlineStyle, Pad, PartPos, Skip, Terminator};
+use crate::normalize;
+use crate::stream::{Ends, DEFAULT_BUFFER_LIMIT};
+use crate::util::{self, Window};
+
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "regex")]
+use regex::bytes::Regex;
+
+/// Controls where a source is cut into parts, see [`RsSplitter::split_by`].
+///
+/// Only the body of the source is cut: the head and tail given to [`RsSplitter::repeat_head`]
+/// and [`RsSplitter::repeat_tail`] are repeated in every part instead.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy)]
+pub enum SplitBy<'a> {
+    /// Cut after every given number of lines, ended by the configured
+    /// [`line_terminator`](RsSplitter::line_terminator)s. Zero lines never cut.
+    Lines(usize),
+    /// Cut after every given number of bytes. Zero bytes never cut.
+    Bytes(u64),
+    /// Cut after every occurrence of the given byte sequence, which ends the part before the cut.
+    /// An empty delimiter never cuts.
+    Delimiter(&'a [u8]),
+    /// Cut before every line that matches the given regex, as `csplit` does.
+    ///
+    /// Lines are tested without their line terminators. This variant is only available if the
+    /// `regex` feature is enabled.
+    #[cfg(feature = "regex")]
+    BeforeMatch(&'a Regex),
+}
+
+/// A splitter that cuts a source that implements [`Read`] and [`Seek`] into many parts.
+///
+/// `RsSplitter` is the other way around of [`RsMerger`]: the bytes a head skip would strip from
+/// the source can be repeated at the top of each part, and the bytes a tail skip would strip at
+/// the bottom, so that each part stands on its own.
+///
+/// # Behaviours
+///
+/// Each part is written as following:
+///
+/// 1. If any leading padding is given by [`pad_with`], writes it into the writer.
+/// 2. If a head is given by [`repeat_head`], writes the head of the source into the writer.
+/// 3. Writes the contents of the part into the writer.
+/// 4. If forcing ending newline option is set by [`force_ending_newline`], writes a ending
+///    newline into the writer if the part does not end with a newline.
+/// 5. If a tail is given by [`repeat_tail`], writes the tail of the source into the writer.
+/// 6. If any inner padding is given by [`pad_with`], writes it into the writer, unless this is
+///    the last part.
+/// 7. If any ending padding is given by [`pad_with`], writes it into the writer.
+///
+/// Parts are never empty: a source with an empty body is split into no parts.
+///
+/// # Examples
+///
+/// ```
+/// use admerge::{RsSplitter, SplitBy, Skip, Result};
+/// use std::io::Cursor;
+///
+/// fn main() -> Result<()> {
+///     let mut source = Cursor::new("id,name\n1,foo\n2,bar\n3,baz\n");
+///
+///     // Configures splitter.
+///     let mut splitter = RsSplitter::new();
+///     splitter.split_by(SplitBy::Lines(2));
+///     splitter.repeat_head(Skip::LinesOnce(1));
+///
+///     // Splits the source into parts.
+///     let parts = splitter.split_source_with(&mut source, |_| Ok(Vec::new()))?;
+///     assert_eq!(parts, [&b"id,name\n1,foo\n2,bar\n"[..], b"id,name\n3,baz\n"]);
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`Read`]: std::io::Read
+/// [`Seek`]: std::io::Seek
+/// [`RsMerger`]: crate::RsMerger
+/// [`pad_with`]: RsSplitter::pad_with
+/// [`repeat_head`]: RsSplitter::repeat_head
+/// [`repeat_tail`]: RsSplitter::repeat_tail
+/// [`force_ending_newline`]: RsSplitter::force_ending_newline
+#[derive(Debug, Clone)]
+pub struct RsSplitter<'a> {
+    opts: RsSplitterOptions<'a>,
+}
+
+#[derive(Clone, Debug)]
+struct RsSplitterOptions<'a> {
+    split_by: SplitBy<'a>,
+    head: Option<Skip<'a>>,
+    tail: Option<Skip<'a>>,
+    padding: Option<Pad<'a>>,
+    newline: Option<NewlineStyle>,
+    terminator: Terminator<'a>,
+    buffer_limit: usize,
+}
+
+impl<'a> Default for RsSplitter<'a> {
+    fn default() -> Self {
+        let opts = RsSplitterOptions {
+            split_by: SplitBy::Lines(1000),
+            head: None,
+            tail: None,
+            padding: None,
+            newline: None,
+            terminator: Terminator::Lf,
+            buffer_limit: DEFAULT_BUFFER_LIMIT,
+        };
+        RsSplitter { opts }
+    }
+}
+
+// Public APIs
+impl<'a> RsSplitter<'a> {
+    /// Creates a new `RsSplitter` builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Configures where this splitter cuts the source. Defaults to `SplitBy::Lines(1000)`, as
+    /// `split` does.
+    pub fn split_by(&mut self, split_by: SplitBy<'a>) -> &mut Self {
+        self.opts.split_by = split_by;
+        self
+    }
+
+    /// Configures this splitter to repeat the head of the source at the top of each part.
+    ///
+    /// The head is made of the bytes the given skip would strip from the head of a source that is
+    /// neither the first nor the last, see [`RsMerger::skip_head`]. So `Skip::LinesOnce(1)`
+    /// repeats the first line, the very header merging with the same skip keeps only once.
+    ///
+    /// [`RsMerger::skip_head`]: crate::RsMerger::skip_head
+    pub fn repeat_head(&mut self, skip: Skip<'a>) -> &mut Self {
+        self.opts.head = Some(skip);
+        self
+    }
+
+    /// Configures this splitter to repeat the tail of the source at the bottom of each part.
+    ///
+    /// The tail is made of the bytes the given skip would strip from the tail of a source that is
+    /// neither the first nor the last, see [`RsMerger::skip_tail`].
+    ///
+    /// [`RsMerger::skip_tail`]: crate::RsMerger::skip_tail
+    pub fn repeat_tail(&mut self, skip: Skip<'a>) -> &mut Self {
+        self.opts.tail = Some(skip);
+        self
+    }
+
+    /// Configures this splitter to fill paddings into each part.
+    ///
+    /// `Pad::Before` is written at the top of each part and `Pad::After` at the bottom of each
+    /// part, while `Pad::Between` is written at the bottom of every part but the last.
+    pub fn pad_with(&mut self, padding: Pad<'a>) -> &mut Self {
+        self.opts.padding = Some(padding);
+        self
+    }
+
+    /// Configures this splitter to force the presence of ending newline after the contents of
+    /// each part, before its repeated tail.
+    ///
+    /// With [`NewlineStyle::Auto`], the dominant style among the line endings of the source is used.
+    /// With a [`Terminator::Delimiter`] configured, the delimiter itself is appended instead.
+    pub fn force_ending_newline(&mut self, newline: impl Into<NewlineStyle>) -> &mut Self {
+        self.opts.newline = Some(newline.into());
+        self
+    }
+
+    /// Configures the line terminators that end lines, for line-based skips and splits and ending
+    /// newlines. Defaults to [`Terminator::Lf`].
+    pub fn line_terminator(&mut self, terminator: Terminator<'a>) -> &mut Self {
+        self.opts.terminator = terminator;
+        self
+    }
+
+    /// Configures the maximum number of bytes this splitter may hold in memory. Defaults to
+    /// 8 MiB.
+    ///
+    /// The repeated head and tail are held while the parts are written, and each line is held
+    /// while it is tested by `SplitBy::BeforeMatch`, so either one growing beyond the limit
+    /// makes splitting fail with [`ErrorKind::BufferExceeded`]. Lines searched by regex skips
+    /// must fit within this limit as well.
+    pub fn buffer_limit(&mut self, limit: usize) -> &mut Self {
+        self.opts.buffer_limit = limit;
+        self
+    }
+
+    /// Splits the given source into parts, writing each one into the writer `open` returns for
+    /// its index, and returns the writers in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidSkip`] if the given [`Skip`]s cannot
+    /// applied to the given source, or if the head and tail overlap;
+    ///
+    /// Returns an error variant of [`ErrorKind::BufferExceeded`] if the bytes held in memory
+    /// grow beyond the [`buffer_limit`];
+    ///
+    /// Returns an error variant of [`ErrorKind::Io`] if any I/O errors were encountered.
+    ///
+    /// [`buffer_limit`]: RsSplitter::buffer_limit
+    pub fn split_source_with<RS, W, F>(&self, source: &mut RS, mut open: F) -> Result<Vec<W>>
+    where
+        RS: Read + Seek,
+        W: Write,
+        F: FnMut(usize) -> io::Result<W>,
+    {
+        let terminator = self.opts.terminator;
+        let len = util::seek_to_end(source)?;
+        let newline = match self.opts.newline {
+            Some(NewlineStyle::Auto) => {
+                util::seek_to_start(source)?;
+                Some(normalize::detect(source)?)
+            }
+            newline => newline.and_then(NewlineStyle::fixed),
+        };
+
+        let limit = self.opts.buffer_limit;
+        let head_end = match &self.opts.head {
+            Some(skip) => {
+                merge::head_start(skip, source, PartPos::Inside, terminator, limit)? as u64
+            }
+            None => 0,
+        };
+        let tail_start = match &self.opts.tail {
+            Some(skip) => merge::tail_end(skip, source, PartPos::Inside, terminator, limit)? as u64,
+            None => len,
+        };
+        if head_end > tail_start || tail_start > len {
+            return Err(ErrorKind::InvalidSkip);
+        }
+        let head = read_range(source, 0..head_end, limit)?;
+        let tail = read_range(source, tail_start..len, limit)?;
+
+        let parts = self.parts(source, head_end..tail_start)?;
+        let count = parts.len();
+        let [before, between, after] = self.paddings();
+        let mut writers = Vec::with_capacity(count);
+        for (i, part) in parts.into_iter().enumerate() {
+            let mut writer = open(i)?;
+            if let Some(padding) = before {
+                writer.write_all(padding)?;
+            }
+            writer.write_all(&head)?;
+
+            let mut window = Window::new(&mut *source, part.start, part.end - part.start);
+            let endn = util::endswith_newline(&mut window, terminator)?;
+            io::copy(&mut window, &mut writer)?;
+            match (newline, terminator) {
+                (Some(_), Terminator::Delimiter(delimiter)) if !endn && !delimiter.is_empty() => {
+                    writer.write_all(delimiter)?
+                }
+                (Some(newline), _) if !endn => writer.write_all(newline.as_bytes())?,
+                _ => {}
+            }
+
+            writer.write_all(&tail)?;
+            if let Some(padding) = between.filter(|_| i + 1 < count) {
+                writer.write_all(padding)?;
+            }
+            if let Some(padding) = after {
+                writer.write_all(padding)?;
+            }
+            writer.flush()?;
+            writers.push(writer);
+        }
+
+        Ok(writers)
+    }
+}
+
+// Private methods
+impl<'a> RsSplitter<'a> {
+    // Returns the paddings before, between and after parts.
+    fn paddings(&self) -> [Option<&'a [u8]>; 3] {
+        match self.opts.padding {
+            Some(Pad::Before(padding)) => [Some(padding), None, None],
+            Some(Pad::Between(padding)) => [None, Some(padding), None],
+            Some(Pad::After(padding)) => [None, None, Some(padding)],
+            Some(Pad::Custom(before, between, after)) => [before, between, after],
+            None => [None, None, None],
+        }
+    }
+
+    // Works out the source ranges of the parts the given body is cut into.
+    fn parts<RS: Read + Seek>(&self, source: &mut RS, body: Range<u64>) -> Result<Vec<Range<u64>>> {
+        let mut cuts = Vec::new();
+        match self.opts.split_by {
+            SplitBy::Lines(n) if n > 0 => {
+                let mut seen = 0;
+                let terminator = self.opts.terminator;
+                lines(source, body.clone(), terminator, None, |line, _| {
+                    seen += 1;
+                    if seen % n == 0 {
+                        cuts.push(line.end);
+                    }
+                })?;
+            }
+            SplitBy::Bytes(n) if n > 0 => {
+                let mut at = body.start.saturating_add(n);
+                while at < body.end {
+                    cuts.push(at);
+                    at = at.saturating_add(n);
+                }
+            }
+            SplitBy::Delimiter(delimiter) if !delimiter.is_empty() => {
+                let terminator = Terminator::Delimiter(delimiter);
+                lines(source, body.clone(), terminator, None, |record, _| {
+                    cuts.push(record.end)
+                })?;
+            }
+            #[cfg(feature = "regex")]
+            SplitBy::BeforeMatch(regex) => {
+                let terminator = self.opts.terminator;
+                let limit = Some(self.opts.buffer_limit);
+                lines(source, body.clone(), terminator, limit, |line, bytes| {
+                    if regex.is_match(strip(bytes, terminator)) {
+                        cuts.push(line.start);
+                    }
+                })?;
+            }
+            _ => {}
+        }
+
+        let mut parts = Vec::with_capacity(cuts.len() + 1);
+        let mut start = body.start;
+        for cut in cuts {
+            if cut > start && cut < body.end {
+                parts.push(start..cut);
+                start = cut;
+            }
+        }
+        if start < body.end {
+            parts.push(start..body.end);
+        }
+        Ok(parts)
+    }
+}
+
+// Calls `f` with the range of every line of the given range of the source, the last one included
+// even if it lacks a terminator, along with the bytes of the line if they are kept, up to the
+// given limit.
+fn lines<RS, F>(
+    source: &mut RS,
+    range: Range<u64>,
+    terminator: Terminator<'_>,
+    keep: Option<usize>,
+    mut f: F,
+) -> Result<()>
+where
+    RS: Read + Seek,
+    F: FnMut(Range<u64>, &[u8]),
+{
+    let mut ends = Ends::new(terminator);
+    let mut window = Window::new(source, range.start, range.end - range.start);
+    let mut buf = vec![0; 8 * 1024];
+    let mut line = Vec::new();
+    let mut start = range.start;
+    let mut offset = range.start;
+    loop {
+        let n = match window.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let chunk = &buf[..n];
+
+        let mut found = Vec::new();
+        ends.feed(offset, chunk, |end| {
+            found.push(end);
+            true
+        });
+        // A pending `\r` is reported at the start of the next chunk, so `end >= offset`.
+        let mut from = 0;
+        for end in found {
+            let to = (end - offset) as usize;
+            keep_bytes(&mut line, &chunk[from..to], keep)?;
+            f(start..end, &line);
+            line.clear();
+            start = end;
+            from = to;
+        }
+        keep_bytes(&mut line, &chunk[from..], keep)?;
+        offset += n as u64;
+    }
+
+    if let Some(end) = ends.pending() {
+        f(start..end, &line);
+        line.clear();
+        start = end;
+    }
+    if start < range.end {
+        f(start..range.end, &line);
+    }
+    Ok(())
+}
+
+// Appends the given bytes to the line being read if lines are kept, failing once the line grows
+// beyond the given limit.
+fn keep_bytes(line: &mut Vec<u8>, bytes: &[u8], keep: Option<usize>) -> Result<()> {
+    if let Some(limit) = keep {
+        if line.len() + bytes.len() > limit {
+            return Err(ErrorKind::BufferExceeded(limit));
+        }
+        line.extend_from_slice(bytes);
+    }
+
+    Ok(())
+}
+
+// Returns the given line without its terminator.
+#[cfg(feature = "regex")]
+fn strip<'l>(line: &'l [u8], terminator: Terminator<'_>) -> &'l [u8] {
+    let lf = || {
+        line.strip_suffix(b"\r\n")
+            .or_else(|| line.strip_suffix(b"\n"))
+    };
+    let stripped = match terminator {
+        Terminator::Lf => lf(),
+        Terminator::Crlf => line.strip_suffix(b"\r\n"),
+        Terminator::Cr => line.strip_suffix(b"\r"),
+        Terminator::Any => lf().or_else(|| line.strip_suffix(b"\r")),
+        Terminator::Delimiter(delimiter) if !delimiter.is_empty() => line.strip_suffix(delimiter),
+        Terminator::Delimiter(_) => None,
+    };
+    stripped.unwrap_or(line)
+}
+
+// Reads the given range of the source, failing if it is longer than the given limit.
+fn read_range<RS: Read + Seek>(
+    source: &mut RS,
+    range: Range<u64>,
+    limit: usize,
+) -> Result<Vec<u8>> {
+    if range.end - range.start > limit as u64 {
+        return Err(ErrorKind::BufferExceeded(limit));
+    }
+    let mut bytes = Vec::with_capacity((range.end - range.start) as usize);
+    Window::new(source, range.start, range.end - range.start).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// A splitter that cuts a file into many files, see [`RsSplitter`].
+#[derive(Clone, Debug, Default)]
+pub struct FileSplitter<'a>(RsSplitter<'a>);
+
+impl<'a> FileSplitter<'a> {
+    /// Creates a new `FileSplitter` builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Configures where this splitter cuts the file.
+    ///
+    /// See [`RsSplitter::split_by`].
+    pub fn split_by(&mut self, split_by: SplitBy<'a>) -> &mut Self {
+        self.0.opts.split_by = split_by;
+        self
+    }
+
+    /// Configures this splitter to repeat the head of the file at the top of each part.
+    ///
+    /// See [`RsSplitter::repeat_head`].
+    pub fn repeat_head(&mut self, skip: Skip<'a>) -> &mut Self {
+        self.0.opts.head = Some(skip);
+        self
+    }
+
+    /// Configures this splitter to repeat the tail of the file at the bottom of each part.
+    ///
+    /// See [`RsSplitter::repeat_tail`].
+    pub fn repeat_tail(&mut self, skip: Skip<'a>) -> &mut Self {
+        self.0.opts.tail = Some(skip);
+        self
+    }
+
+    /// Configures this splitter to fill paddings into each part.
+    ///
+    /// See [`RsSplitter::pad_with`].
+    pub fn pad_with(&mut self, padding: Pad<'a>) -> &mut Self {
+        self.0.opts.padding = Some(padding);
+        self
+    }
+
+    /// Configures this splitter to force the presence of ending newline after the contents of
+    /// each part.
+    ///
+    /// See [`RsSplitter::force_ending_newline`].
+    pub fn force_ending_newline(&mut self, newline: impl Into<NewlineStyle>) -> &mut Self {
+        self.0.opts.newline = Some(newline.into());
+        self
+    }
+
+    /// Configures the line terminators that end lines.
+    ///
+    /// See [`RsSplitter::line_terminator`].
+    pub fn line_terminator(&mut self, terminator: Terminator<'a>) -> &mut Self {
+        self.0.opts.terminator = terminator;
+        self
+    }
+
+    /// Configures the maximum number of bytes this splitter may hold in memory.
+    ///
+    /// See [`RsSplitter::buffer_limit`].
+    pub fn buffer_limit(&mut self, limit: usize) -> &mut Self {
+        self.0.opts.buffer_limit = limit;
+        self
+    }
+
+    /// Splits the file at the given path into new files in the given directory, returning the
+    /// paths of the files written.
+    ///
+    /// The part at index `i` of `data.csv` is written into `data.i.csv`. Existing files are never
+    /// overwritten: an error variant of [`ErrorKind::Io`] is returned instead.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use admerge::{FileSplitter, SplitBy, Skip, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     // Configures splitter.
+    ///     let mut splitter = FileSplitter::new();
+    ///     splitter.split_by(SplitBy::Bytes(64 * 1024 * 1024));
+    ///     splitter.repeat_head(Skip::LinesOnce(1));
+    ///
+    ///     // Splits `data.csv` into `parts/data.0.csv`, `parts/data.1.csv` and so on.
+    ///     let paths = splitter.with_path("data.csv", "parts")?;
+    ///     println!("{} parts written", paths.len());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant of [`ErrorKind::InvalidPath`] if the given path does not point to
+    /// a regular file;
+    ///
+    /// See [`RsSplitter::split_source_with`] for the other errors returned.
+    pub fn with_path<P, Q>(&self, path: P, dir: Q) -> Result<Vec<PathBuf>>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let stem = match path.file_stem() {
+            Some(stem) if path.is_file() => stem,
+            _ => return Err(ErrorKind::InvalidPath(0)),
+        };
+
+        let mut file = File::open(path)?;
+        let mut paths = Vec::new();
+        self.0.split_source_with(&mut file, |i| {
+            let mut name = OsString::from(stem);
+            name.push(format!(".{}", i));
+            if let Some(extension) = path.extension() {
+                name.push(".");
+                name.push(extension);
+            }
+            let part = dir.as_ref().join(name);
+            let file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&part)?;
+            paths.push(part);
+            Ok(file)
+        })?;
+
+        Ok(paths)
+    }
+}
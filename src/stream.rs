@@ -0,0 +1,1392 @@
+//! Incremental skip machinery used to merge sources that only implement [`Read`].
+//!
+//! A source is fed chunk by chunk into a [`Filter`]. The head scanner works out where the kept
+//! contents start, and the tail scanner tracks a lower bound of where they will end. Bytes that
+//! may still be cut by the tail skip are held in a look-behind [`Spool`] until the end of the
+//! source is reached.
+//!
+//! [`Read`]: std::io::Read
+use crate::error::{ErrorKind, Result};
+use crate::merge::{Bom, PartPos, PartRange, Skip, Terminator};
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+#[cfg(feature = "regex")]
+use regex::bytes::Regex;
+
+/// The default number of bytes a look-behind buffer may hold in memory.
+pub(crate) const DEFAULT_BUFFER_LIMIT: usize = 8 * 1024 * 1024;
+
+/// A seekable copy of a whole source, held in memory or spilled to disk once it grows beyond a
+/// limit.
+pub(crate) enum Buffered {
+    Mem(io::Cursor<Vec<u8>>, usize, bool),
+    File(File),
+}
+
+impl Buffered {
+    pub(crate) fn new(limit: usize, spill: bool) -> Self {
+        Buffered::Mem(io::Cursor::new(Vec::new()), limit, spill)
+    }
+
+    // Copies all bytes of the given reader into this buffer, rewinding it afterwards.
+    pub(crate) fn fill_from<R: Read>(&mut self, reader: &mut R) -> Result<()> {
+        let mut buf = [0; 8 * 1024];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            self.push(&buf[..n])?;
+        }
+        self.seek(SeekFrom::Start(0))?;
+        Ok(())
+    }
+
+    // Appends the given bytes to this buffer.
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Result<()> {
+        match self {
+            Buffered::File(file) => file.write_all(bytes)?,
+            Buffered::Mem(cursor, limit, spill) => {
+                cursor.get_mut().extend_from_slice(bytes);
+                if cursor.get_ref().len() > *limit {
+                    let mut file = spill_file(*spill, *limit)?;
+                    file.write_all(cursor.get_ref())?;
+                    *self = Buffered::File(file);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Read for Buffered {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Buffered::Mem(cursor, ..) => cursor.read(buf),
+            Buffered::File(file) => file.read(buf),
+        }
+    }
+}
+
+impl Seek for Buffered {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Buffered::Mem(cursor, ..) => cursor.seek(pos),
+            Buffered::File(file) => file.seek(pos),
+        }
+    }
+}
+
+// Appends everything written, see `recover` for getting back errors other than I/O ones.
+#[cfg(feature = "tokio")]
+impl Write for Buffered {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.push(buf).map_err(into_io)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Buffered::Mem(..) => Ok(()),
+            Buffered::File(file) => file.flush(),
+        }
+    }
+}
+
+// Passes the given error through `io::Error`, see `recover` for getting it back.
+pub(crate) fn into_io(e: ErrorKind) -> io::Error {
+    match e {
+        ErrorKind::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+// Recovers an error that was passed through `io::Error`, such as while writing to a `Buffered`.
+pub(crate) fn recover(e: ErrorKind) -> ErrorKind {
+    match e {
+        ErrorKind::Io(e) if e.get_ref().is_some_and(|inner| inner.is::<ErrorKind>()) => {
+            // The inner error was just checked to be an `ErrorKind`.
+            *e.into_inner().unwrap().downcast::<ErrorKind>().unwrap()
+        }
+        e => e,
+    }
+}
+
+// Creates the anonymous temporary file a buffer spills into once it grows beyond its limit, or
+// fails if spilling is disabled.
+fn spill_file(spill: bool, limit: usize) -> Result<File> {
+    #[cfg(feature = "tempfile")]
+    if spill {
+        return Ok(tempfile::tempfile()?);
+    }
+    #[cfg(not(feature = "tempfile"))]
+    let _ = spill;
+
+    Err(ErrorKind::BufferExceeded(limit))
+}
+
+/// A first-in first-out byte buffer that optionally spills to disk once it grows beyond a limit.
+pub(crate) struct Spool {
+    mem: Vec<u8>,
+    head: usize,
+    file: Option<SpillFile>,
+    limit: usize,
+    spill: bool,
+}
+
+struct SpillFile {
+    file: File,
+    read: u64,
+    write: u64,
+}
+
+impl Spool {
+    pub(crate) fn new(limit: usize, spill: bool) -> Self {
+        Spool { mem: Vec::new(), head: 0, file: None, limit, spill }
+    }
+
+    // Returns the number of bytes held.
+    pub(crate) fn len(&self) -> u64 {
+        match &self.file {
+            Some(f) => f.write - f.read,
+            None => (self.mem.len() - self.head) as u64,
+        }
+    }
+
+    // Appends the given bytes to the back of this spool.
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(f) = &mut self.file {
+            f.file.seek(SeekFrom::Start(f.write))?;
+            f.file.write_all(bytes)?;
+            f.write += bytes.len() as u64;
+            return Ok(());
+        }
+
+        self.mem.extend_from_slice(bytes);
+        if self.mem.len() - self.head > self.limit {
+            let mut file = spill_file(self.spill, self.limit)?;
+            file.write_all(&self.mem[self.head..])?;
+            let write = (self.mem.len() - self.head) as u64;
+            self.file = Some(SpillFile { file, read: 0, write });
+            self.mem = Vec::new();
+            self.head = 0;
+        }
+
+        Ok(())
+    }
+
+    // Drops `n` bytes from the front of this spool.
+    pub(crate) fn discard(&mut self, n: u64) {
+        match &mut self.file {
+            Some(f) => {
+                f.read += n;
+                if f.read == f.write {
+                    self.file = None;
+                }
+            }
+            None => {
+                self.head += n as usize;
+                self.compact();
+            }
+        }
+    }
+
+    // Writes `n` bytes from the front of this spool into the given writer.
+    pub(crate) fn drain_into<W: Write>(&mut self, n: u64, writer: &mut W) -> Result<()> {
+        match &mut self.file {
+            Some(f) => {
+                f.file.seek(SeekFrom::Start(f.read))?;
+                let copied = io::copy(&mut (&f.file).take(n), writer)?;
+                if copied != n {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+                f.read += n;
+                if f.read == f.write {
+                    self.file = None;
+                }
+            }
+            None => {
+                let end = self.head + n as usize;
+                writer.write_all(&self.mem[self.head..end])?;
+                self.head = end;
+                self.compact();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compact(&mut self) {
+        if self.head == self.mem.len() {
+            self.mem.clear();
+            self.head = 0;
+        } else if self.head > self.mem.len() / 2 {
+            self.mem.drain(..self.head);
+            self.head = 0;
+        }
+    }
+}
+
+/// Finds every (possibly overlapping) occurrence of a byte pattern in a chunked stream.
+pub(crate) struct Finder<'s> {
+    needle: &'s [u8],
+    carry: Vec<u8>,
+}
+
+impl<'s> Finder<'s> {
+    pub(crate) fn new(needle: &'s [u8]) -> Self {
+        Finder { needle, carry: Vec::new() }
+    }
+
+    // Feeds the chunk that starts at `offset` and calls `f` with the offset of every occurrence
+    // found, stopping early if `f` returns `false`.
+    pub(crate) fn feed<F>(&mut self, offset: u64, chunk: &[u8], mut f: F)
+    where
+        F: FnMut(u64) -> bool,
+    {
+        let w = self.needle.len();
+
+        // Occurrences that straddle the previous chunk and this one.
+        if !self.carry.is_empty() {
+            let carried = self.carry.len();
+            let mut joined = self.carry.clone();
+            joined.extend_from_slice(&chunk[..chunk.len().min(w - 1)]);
+            let base = offset - carried as u64;
+            for i in 0..carried {
+                if joined[i..].starts_with(self.needle) && !f(base + i as u64) {
+                    return;
+                }
+            }
+        }
+
+        // Occurrences that lie entirely in this chunk.
+        if chunk.len() >= w {
+            for (i, window) in chunk.windows(w).enumerate() {
+                if window == self.needle && !f(offset + i as u64) {
+                    return;
+                }
+            }
+        }
+
+        // Keeps the last `w - 1` bytes seen for the next chunk.
+        self.carry.extend_from_slice(chunk);
+        let excess = self.carry.len().saturating_sub(w - 1);
+        self.carry.drain(..excess);
+    }
+}
+
+/// Finds where the line terminators of a chunked stream end.
+pub(crate) struct Ends<'s> {
+    terminator: Terminator<'s>,
+    // The end of the last byte fed, if it is a `\r`.
+    cr: Option<u64>,
+    // Finds the occurrences of a `Terminator::Delimiter`, which may overlap.
+    finder: Option<Finder<'s>>,
+    // The offset before which no delimiter may start, as it would overlap the last one found.
+    next: u64,
+}
+
+impl<'s> Ends<'s> {
+    pub(crate) fn new(terminator: Terminator<'s>) -> Self {
+        let finder = match terminator {
+            Terminator::Delimiter(bytes) if !bytes.is_empty() => Some(Finder::new(bytes)),
+            _ => None,
+        };
+        Ends {
+            terminator,
+            cr: None,
+            finder,
+            next: 0,
+        }
+    }
+
+    // Feeds the chunk that starts at `offset` and calls `f` with the end of every terminator
+    // found, stopping early if `f` returns `false`.
+    //
+    // With `Terminator::Any`, a `\r` is only reported once the next byte tells that it is not
+    // the start of a `\r\n`, see `pending`.
+    pub(crate) fn feed<F>(&mut self, offset: u64, chunk: &[u8], mut f: F)
+    where
+        F: FnMut(u64) -> bool,
+    {
+        if let Terminator::Delimiter(bytes) = self.terminator {
+            let width = bytes.len() as u64;
+            let next = &mut self.next;
+            if let Some(finder) = &mut self.finder {
+                finder.feed(offset, chunk, |pos| {
+                    if pos < *next {
+                        return true;
+                    }
+                    *next = pos + width;
+                    f(pos + width)
+                });
+            }
+            return;
+        }
+
+        for (i, &b) in chunk.iter().enumerate() {
+            let at = offset + i as u64;
+            let cr = self.cr.take();
+            let end = match (self.terminator, b) {
+                (Terminator::Lf, b'\n') | (Terminator::Cr, b'\r') => Some(at + 1),
+                (Terminator::Crlf, b'\n') => cr.map(|_| at + 1),
+                (Terminator::Any, b'\n') => Some(at + 1),
+                (Terminator::Any, _) => cr,
+                _ => None,
+            };
+            if b == b'\r' {
+                self.cr = Some(at + 1);
+            }
+            if let Some(end) = end {
+                if !f(end) {
+                    return;
+                }
+            }
+        }
+    }
+
+    // Returns the end of a `\r` that was fed last but not reported yet, if any.
+    pub(crate) fn pending(&self) -> Option<u64> {
+        match self.terminator {
+            Terminator::Any => self.cr,
+            _ => None,
+        }
+    }
+}
+
+/// Remembers the last few bytes fed, to tell whether they end with a line terminator.
+pub(crate) struct Suffix<'s> {
+    terminator: Terminator<'s>,
+    last: Vec<u8>,
+}
+
+impl<'s> Suffix<'s> {
+    pub(crate) fn new(terminator: Terminator<'s>) -> Self {
+        Suffix {
+            terminator,
+            last: Vec::with_capacity(terminator.width()),
+        }
+    }
+
+    pub(crate) fn push(&mut self, chunk: &[u8]) {
+        let width = self.terminator.width();
+        self.last
+            .extend_from_slice(&chunk[chunk.len().saturating_sub(width)..]);
+        let excess = self.last.len().saturating_sub(width);
+        self.last.drain(..excess);
+    }
+
+    // Returns `true` if the bytes fed end with a terminator.
+    pub(crate) fn ends(&self) -> bool {
+        self.terminator.ends(&self.last)
+    }
+
+    // Returns `true` if the last `len` bytes fed, taken on their own, end with a terminator.
+    fn ends_within(&self, len: u64) -> bool {
+        let n = len.min(self.last.len() as u64) as usize;
+        self.terminator.ends(&self.last[self.last.len() - n..])
+    }
+}
+
+/// Finds every match of a regular expression in a chunked stream, searching line by line.
+///
+/// Lines are split on `\n` and searched without it, so that a match never spans a newline.
+#[cfg(feature = "regex")]
+pub(crate) struct LineMatcher<'s> {
+    regex: &'s Regex,
+    limit: usize,
+    // The line being fed, which is yet to be terminated.
+    line: Vec<u8>,
+    // Offset of the first byte of `line`.
+    start: u64,
+}
+
+#[cfg(feature = "regex")]
+impl<'s> LineMatcher<'s> {
+    pub(crate) fn new(regex: &'s Regex, limit: usize) -> Self {
+        LineMatcher {
+            regex,
+            limit,
+            line: Vec::new(),
+            start: 0,
+        }
+    }
+
+    // Feeds the next chunk and calls `f` with the range of every match in each completed line,
+    // failing if a line is longer than the limit.
+    fn feed<F>(&mut self, chunk: &[u8], mut f: F) -> Result<()>
+    where
+        F: FnMut(u64, u64),
+    {
+        let mut rest = chunk;
+        while let Some(i) = rest.iter().position(|&b| b == b'\n') {
+            self.push(&rest[..i])?;
+            self.search(&mut f);
+            self.start += self.line.len() as u64 + 1;
+            self.line.clear();
+            rest = &rest[i + 1..];
+        }
+        self.push(rest)
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.line.len() + bytes.len() > self.limit {
+            return Err(ErrorKind::BufferExceeded(self.limit));
+        }
+        self.line.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    // Calls `f` with the range of every match in the line being fed.
+    fn search<F>(&self, f: &mut F)
+    where
+        F: FnMut(u64, u64),
+    {
+        for m in self.regex.find_iter(&self.line) {
+            f(self.start + m.start() as u64, self.start + m.end() as u64);
+        }
+    }
+}
+
+// Returns the length of the byte order mark the given bytes start with, if any.
+fn bom_len(head: &[u8]) -> u64 {
+    Bom::detect(head).map_or(0, |bom| bom.as_bytes().len() as u64)
+}
+
+/// Works out where the kept contents of a source start.
+pub(crate) enum HeadScan<'s> {
+    // The start position is known up front.
+    Fixed(u64),
+    // The start position is decided by the first few bytes.
+    Bom {
+        head: Vec<u8>,
+        start: Option<u64>,
+    },
+    Lines {
+        n: usize,
+        seen: usize,
+        ends: Ends<'s>,
+        start: Option<u64>,
+    },
+    Search {
+        finder: Finder<'s>,
+        width: u64,
+        skip_match: bool,
+        start: Option<u64>,
+    },
+    Repeats {
+        pattern: &'s [u8],
+        matched: u64,
+        idx: usize,
+        done: bool,
+    },
+    #[cfg(feature = "regex")]
+    Regex {
+        matcher: LineMatcher<'s>,
+        skip_match: bool,
+        start: Option<u64>,
+    },
+    Chain(Box<HeadChain<'s>>),
+}
+
+impl<'s> HeadScan<'s> {
+    pub(crate) fn new(
+        skip: Option<&Skip<'s>>,
+        pos: PartPos,
+        terminator: Terminator<'s>,
+        limit: usize,
+    ) -> Self {
+        let skip = match skip {
+            None => return HeadScan::Fixed(0),
+            Some(skip) => skip,
+        };
+
+        match *skip {
+            Skip::Bom => HeadScan::Bom {
+                head: Vec::with_capacity(3),
+                start: None,
+            },
+            Skip::BomOnce => match pos {
+                PartPos::Start => HeadScan::Fixed(0),
+                _ => HeadScan::Bom {
+                    head: Vec::with_capacity(3),
+                    start: None,
+                },
+            },
+            Skip::Bytes(n) => HeadScan::Fixed(n as u64),
+            Skip::BytesOnce(n) => match pos {
+                PartPos::Start => HeadScan::Fixed(0),
+                _ => HeadScan::Fixed(n as u64),
+            },
+            Skip::Records(n, size) => HeadScan::Fixed(n.saturating_mul(size) as u64),
+            Skip::RecordsOnce(n, size) => match pos {
+                PartPos::Start => HeadScan::Fixed(0),
+                _ => HeadScan::Fixed(n.saturating_mul(size) as u64),
+            },
+            Skip::Lines(0) => HeadScan::Fixed(0),
+            Skip::Lines(n) => HeadScan::Lines {
+                n,
+                seen: 0,
+                ends: Ends::new(terminator),
+                start: None,
+            },
+            Skip::LinesOnce(n) => match (pos, n) {
+                (PartPos::Start, _) | (_, 0) => HeadScan::Fixed(0),
+                _ => HeadScan::Lines {
+                    n,
+                    seen: 0,
+                    ends: Ends::new(terminator),
+                    start: None,
+                },
+            },
+            Skip::Until(bytes) | Skip::Before(bytes) if bytes.is_empty() => HeadScan::Fixed(0),
+            Skip::Until(bytes) => HeadScan::Search {
+                finder: Finder::new(bytes),
+                width: bytes.len() as u64,
+                skip_match: true,
+                start: None,
+            },
+            Skip::Before(bytes) => HeadScan::Search {
+                finder: Finder::new(bytes),
+                width: bytes.len() as u64,
+                skip_match: false,
+                start: None,
+            },
+            Skip::Repeats(&[]) => HeadScan::Fixed(0),
+            Skip::Repeats(bytes) => HeadScan::Repeats {
+                pattern: bytes,
+                matched: 0,
+                idx: 0,
+                done: false,
+            },
+            #[cfg(feature = "regex")]
+            Skip::UntilMatch(regex) => HeadScan::Regex {
+                matcher: LineMatcher::new(regex, limit),
+                skip_match: true,
+                start: None,
+            },
+            #[cfg(feature = "regex")]
+            Skip::BeforeMatch(regex) => HeadScan::Regex {
+                matcher: LineMatcher::new(regex, limit),
+                skip_match: false,
+                start: None,
+            },
+            Skip::Chain(&[]) => HeadScan::Fixed(0),
+            Skip::Chain(skips) => {
+                HeadScan::Chain(Box::new(HeadChain::new(skips, pos, terminator, limit)))
+            }
+            // Custom skips are applied to buffered sources instead.
+            Skip::Custom(_) => unreachable!(),
+        }
+    }
+
+    // Feeds the chunk that starts at `offset`.
+    fn feed(&mut self, offset: u64, chunk: &[u8]) -> Result<()> {
+        match self {
+            HeadScan::Fixed(_) => (),
+            HeadScan::Bom { head, start } => {
+                if start.is_some() {
+                    return Ok(());
+                }
+                let n = chunk.len().min(3 - head.len());
+                head.extend_from_slice(&chunk[..n]);
+                if head.len() == 3 {
+                    *start = Some(bom_len(head));
+                }
+            }
+            HeadScan::Lines {
+                n,
+                seen,
+                ends,
+                start,
+            } => {
+                if start.is_some() {
+                    return Ok(());
+                }
+                ends.feed(offset, chunk, |end| {
+                    *seen += 1;
+                    if *seen == *n {
+                        *start = Some(end);
+                    }
+                    start.is_none()
+                });
+            }
+            HeadScan::Search { finder, width, skip_match, start } => {
+                if start.is_some() {
+                    return Ok(());
+                }
+                let advance = if *skip_match { *width } else { 0 };
+                finder.feed(offset, chunk, |pos| {
+                    *start = Some(pos + advance);
+                    false
+                });
+            }
+            HeadScan::Repeats { pattern, matched, idx, done } => {
+                for &b in chunk {
+                    if *done {
+                        return Ok(());
+                    }
+                    if b == pattern[*idx] {
+                        *idx += 1;
+                        if *idx == pattern.len() {
+                            *matched += pattern.len() as u64;
+                            *idx = 0;
+                        }
+                    } else {
+                        *done = true;
+                    }
+                }
+            }
+            #[cfg(feature = "regex")]
+            HeadScan::Regex {
+                matcher,
+                skip_match,
+                start,
+            } => {
+                if start.is_some() {
+                    return Ok(());
+                }
+                let skip_match = *skip_match;
+                matcher.feed(chunk, |s, e| {
+                    start.get_or_insert(if skip_match { e } else { s });
+                })?;
+            }
+            HeadScan::Chain(chain) => chain.feed(chunk)?,
+        }
+
+        Ok(())
+    }
+
+    // Returns the start position if it is already known.
+    fn start(&self) -> Option<u64> {
+        match self {
+            HeadScan::Fixed(n) => Some(*n),
+            HeadScan::Bom { start, .. } => *start,
+            HeadScan::Lines { start, .. } => *start,
+            HeadScan::Search { start, .. } => *start,
+            HeadScan::Repeats { matched, done, .. } => match done {
+                true => Some(*matched),
+                false => None,
+            },
+            #[cfg(feature = "regex")]
+            HeadScan::Regex { start, .. } => *start,
+            HeadScan::Chain(chain) => chain.start(),
+        }
+    }
+
+    // Returns a position that the start position will never precede, given that `pos` bytes
+    // have been fed.
+    fn floor(&self, pos: u64) -> u64 {
+        if let Some(start) = self.start() {
+            return start;
+        }
+        match self {
+            HeadScan::Search { width, skip_match: false, .. } => pos.saturating_sub(width - 1),
+            HeadScan::Bom { .. } => 0,
+            HeadScan::Repeats { matched, .. } => *matched,
+            #[cfg(feature = "regex")]
+            HeadScan::Regex { matcher, .. } => matcher.start,
+            HeadScan::Chain(chain) => chain.floor(),
+            _ => pos,
+        }
+    }
+
+    // Returns the start position once the whole source has been fed.
+    fn finish(&mut self, len: u64, endn: bool) -> Result<u64> {
+        if let Some(start) = self.start() {
+            return Ok(start);
+        }
+        match self {
+            HeadScan::Bom { head, .. } => Ok(bom_len(head)),
+            HeadScan::Lines { n, seen, ends, .. } => {
+                // A `\r` at the very end is only counted now.
+                let seen = *seen + ends.pending().is_some() as usize;
+                match seen == *n || !endn && seen + 1 == *n {
+                    true => Ok(len),
+                    false => Err(ErrorKind::InvalidSkip),
+                }
+            }
+            HeadScan::Repeats { matched, .. } => Ok(*matched),
+            #[cfg(feature = "regex")]
+            HeadScan::Regex {
+                matcher,
+                skip_match,
+                ..
+            } => {
+                // The last line may be unterminated.
+                let mut start = None;
+                matcher.search(&mut |s, e| {
+                    start.get_or_insert(if *skip_match { e } else { s });
+                });
+                Ok(start.unwrap_or(len))
+            }
+            HeadScan::Chain(chain) => chain.finish(len),
+            _ => Ok(len),
+        }
+    }
+}
+
+/// Works out where the kept contents of a source end.
+pub(crate) enum TailScan<'s> {
+    Bytes(u64),
+    // Remembers the ends of the last `n + 1` line terminators.
+    Lines {
+        n: usize,
+        count: usize,
+        recent: VecDeque<u64>,
+        ends: Ends<'s>,
+    },
+    Until {
+        finder: Finder<'s>,
+        last: Option<u64>,
+    },
+    Before {
+        finder: Finder<'s>,
+        width: u64,
+        end: Option<u64>,
+    },
+    // Remembers, for each residue modulo the pattern width, the smallest position from which
+    // the fed bytes are a run of the pattern.
+    Repeats { pattern: &'s [u8], runs: Vec<Option<u64>> },
+    #[cfg(feature = "regex")]
+    Regex {
+        matcher: LineMatcher<'s>,
+        skip_match: bool,
+        last: Option<u64>,
+    },
+    Chain(Box<TailChain<'s>>),
+}
+
+impl<'s> TailScan<'s> {
+    pub(crate) fn new(
+        skip: Option<&Skip<'s>>,
+        pos: PartPos,
+        terminator: Terminator<'s>,
+        limit: usize,
+    ) -> Self {
+        let skip = match skip {
+            None => return TailScan::Bytes(0),
+            Some(skip) => skip,
+        };
+
+        match *skip {
+            Skip::Bom | Skip::BomOnce => TailScan::Bytes(0),
+            Skip::Bytes(n) => TailScan::Bytes(n as u64),
+            Skip::BytesOnce(n) => match pos {
+                PartPos::End => TailScan::Bytes(0),
+                _ => TailScan::Bytes(n as u64),
+            },
+            Skip::Records(n, size) => TailScan::Bytes(n.saturating_mul(size) as u64),
+            Skip::RecordsOnce(n, size) => match pos {
+                PartPos::End => TailScan::Bytes(0),
+                _ => TailScan::Bytes(n.saturating_mul(size) as u64),
+            },
+            Skip::Lines(0) => TailScan::Bytes(0),
+            Skip::Lines(n) => TailScan::Lines {
+                n,
+                count: 0,
+                recent: VecDeque::new(),
+                ends: Ends::new(terminator),
+            },
+            Skip::LinesOnce(n) => match (pos, n) {
+                (PartPos::End, _) | (_, 0) => TailScan::Bytes(0),
+                _ => TailScan::Lines {
+                    n,
+                    count: 0,
+                    recent: VecDeque::new(),
+                    ends: Ends::new(terminator),
+                },
+            },
+            Skip::Until(bytes) | Skip::Before(bytes) if bytes.is_empty() => TailScan::Bytes(0),
+            Skip::Until(bytes) => TailScan::Until { finder: Finder::new(bytes), last: None },
+            Skip::Before(bytes) => TailScan::Before {
+                finder: Finder::new(bytes),
+                width: bytes.len() as u64,
+                end: None,
+            },
+            Skip::Repeats(&[]) => TailScan::Bytes(0),
+            Skip::Repeats(bytes) => TailScan::Repeats {
+                pattern: bytes,
+                runs: vec![None; bytes.len()],
+            },
+            #[cfg(feature = "regex")]
+            Skip::UntilMatch(regex) => TailScan::Regex {
+                matcher: LineMatcher::new(regex, limit),
+                skip_match: true,
+                last: None,
+            },
+            #[cfg(feature = "regex")]
+            Skip::BeforeMatch(regex) => TailScan::Regex {
+                matcher: LineMatcher::new(regex, limit),
+                skip_match: false,
+                last: None,
+            },
+            Skip::Chain(&[]) => TailScan::Bytes(0),
+            Skip::Chain(skips) => {
+                TailScan::Chain(Box::new(TailChain::new(skips, pos, terminator, limit)))
+            }
+            // Custom skips are applied to buffered sources instead.
+            Skip::Custom(_) => unreachable!(),
+        }
+    }
+
+    // Feeds the chunk that starts at `offset`.
+    fn feed(&mut self, offset: u64, chunk: &[u8]) -> Result<()> {
+        match self {
+            TailScan::Bytes(_) => (),
+            TailScan::Lines {
+                n,
+                count,
+                recent,
+                ends,
+            } => {
+                ends.feed(offset, chunk, |end| {
+                    *count += 1;
+                    recent.push_back(end);
+                    if recent.len() > *n + 1 {
+                        recent.pop_front();
+                    }
+                    true
+                });
+            }
+            TailScan::Until { finder, last } => {
+                finder.feed(offset, chunk, |pos| {
+                    *last = Some(pos);
+                    true
+                });
+            }
+            TailScan::Before { finder, width, end } => {
+                if end.is_some() {
+                    return Ok(());
+                }
+                let width = *width;
+                finder.feed(offset, chunk, |pos| {
+                    *end = Some(pos + width);
+                    false
+                });
+            }
+            TailScan::Repeats { pattern, runs } => {
+                let w = pattern.len() as u64;
+                for (i, &b) in chunk.iter().enumerate() {
+                    let at = offset + i as u64;
+                    let slot = &mut runs[(at % w) as usize];
+                    if slot.is_none() {
+                        *slot = Some(at);
+                    }
+                    for run in runs.iter_mut() {
+                        if let Some(p) = *run {
+                            if pattern[((at - p) % w) as usize] != b {
+                                *run = None;
+                            }
+                        }
+                    }
+                }
+            }
+            #[cfg(feature = "regex")]
+            TailScan::Regex {
+                matcher,
+                skip_match,
+                last,
+            } => {
+                let skip_match = *skip_match;
+                matcher.feed(chunk, |s, e| {
+                    *last = Some(if skip_match { s } else { e });
+                })?;
+            }
+            TailScan::Chain(chain) => chain.feed(chunk)?,
+        }
+
+        Ok(())
+    }
+
+    // Returns the end position the fed bytes would give if the source ended here.
+    fn lines_end(
+        n: usize,
+        count: usize,
+        recent: &VecDeque<u64>,
+        ends: &Ends<'_>,
+        endn: bool,
+    ) -> Option<u64> {
+        // A `\r` fed last ends a line if the source ends here.
+        let pending = ends.pending();
+        let count = count + pending.is_some() as usize;
+        let m = count.saturating_sub(endn as usize);
+        if m >= n {
+            let k = n + endn as usize;
+            match pending {
+                Some(end) if k == 1 => Some(end),
+                Some(_) => Some(recent[recent.len() + 1 - k]),
+                None => Some(recent[recent.len() - k]),
+            }
+        } else if m + 1 == n {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    // Returns a position that the end position will never precede, given that `pos` bytes
+    // have been fed.
+    fn safe(&self, pos: u64, endn: bool) -> u64 {
+        match self {
+            TailScan::Bytes(n) => pos.saturating_sub(*n),
+            TailScan::Lines {
+                n,
+                count,
+                recent,
+                ends,
+            } => Self::lines_end(*n, *count, recent, ends, endn).unwrap_or(0),
+            TailScan::Until { last, .. } => last.unwrap_or(0),
+            TailScan::Before { end, .. } => end.unwrap_or(0),
+            TailScan::Repeats { runs, .. } => runs.iter().flatten().copied().min().unwrap_or(pos),
+            #[cfg(feature = "regex")]
+            TailScan::Regex { last, .. } => last.unwrap_or(0),
+            TailScan::Chain(chain) => chain.safe(),
+        }
+    }
+
+    // Returns the end position once the whole source has been fed.
+    fn finish(&mut self, len: u64, endn: bool) -> Result<u64> {
+        match self {
+            TailScan::Bytes(n) => match *n > len {
+                true => Err(ErrorKind::InvalidSkip),
+                false => Ok(len - *n),
+            },
+            TailScan::Lines {
+                n,
+                count,
+                recent,
+                ends,
+            } => Self::lines_end(*n, *count, recent, ends, endn).ok_or(ErrorKind::InvalidSkip),
+            TailScan::Until { last, .. } => Ok(last.unwrap_or(0)),
+            TailScan::Before { end, .. } => Ok(end.unwrap_or(0)),
+            TailScan::Repeats { pattern, runs } => {
+                Ok(runs[(len % pattern.len() as u64) as usize].unwrap_or(len))
+            }
+            #[cfg(feature = "regex")]
+            TailScan::Regex {
+                matcher,
+                skip_match,
+                last,
+            } => {
+                // The last line may be unterminated.
+                let mut end = *last;
+                matcher.search(&mut |s, e| {
+                    end = Some(if *skip_match { s } else { e });
+                });
+                Ok(end.unwrap_or(0))
+            }
+            TailScan::Chain(chain) => chain.finish(len, endn),
+        }
+    }
+}
+
+/// Applies a sequence of head skips, each one to what the previous ones left.
+///
+/// Bytes that the rule being scanned may not skip are kept, so that they can be fed again to the
+/// next rule once the start of the current one is known. The kept bytes are bounded by the
+/// buffer limit, and never spilled to disk.
+pub(crate) struct HeadChain<'s> {
+    rules: &'s [Skip<'s>],
+    pos: PartPos,
+    terminator: Terminator<'s>,
+    limit: usize,
+    // Index of the rule being scanned.
+    idx: usize,
+    current: HeadScan<'s>,
+    // Offset from which the current rule applies.
+    base: u64,
+    // Number of bytes fed to the current rule.
+    fed: u64,
+    // Bytes kept for the upcoming rules, starting at `kept`.
+    buf: Vec<u8>,
+    kept: u64,
+    // Number of bytes fed to this chain.
+    total: u64,
+    // The last bytes fed to this chain.
+    last: Suffix<'s>,
+}
+
+impl<'s> HeadChain<'s> {
+    fn new(rules: &'s [Skip<'s>], pos: PartPos, terminator: Terminator<'s>, limit: usize) -> Self {
+        HeadChain {
+            rules,
+            pos,
+            terminator,
+            limit,
+            idx: 0,
+            current: HeadScan::new(Some(&rules[0]), pos, terminator, limit),
+            base: 0,
+            fed: 0,
+            buf: Vec::new(),
+            kept: 0,
+            total: 0,
+            last: Suffix::new(terminator),
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8]) -> Result<()> {
+        self.buf.extend_from_slice(chunk);
+        self.total += chunk.len() as u64;
+        self.last.push(chunk);
+
+        // Feeds the current rule, moving on to the next one as soon as its start is known.
+        loop {
+            let from = self.base + self.fed;
+            if from < self.total {
+                self.current
+                    .feed(self.fed, &self.buf[(from - self.kept) as usize..])?;
+                self.fed = self.total - self.base;
+            }
+            match self.current.start() {
+                Some(start) if self.idx + 1 < self.rules.len() => self.next(start),
+                _ => break,
+            }
+        }
+
+        // Drops the bytes no upcoming rule will see, which is all of them once the last rule is
+        // being scanned.
+        let floor = match self.idx + 1 == self.rules.len() {
+            true => self.total,
+            false => self.floor().min(self.total),
+        };
+        if floor > self.kept {
+            self.buf.drain(..(floor - self.kept) as usize);
+            self.kept = floor;
+        }
+        if self.buf.len() > self.limit {
+            return Err(ErrorKind::BufferExceeded(self.limit));
+        }
+
+        Ok(())
+    }
+
+    // Moves on to the next rule, which applies from the given start of the current one.
+    fn next(&mut self, start: u64) {
+        self.base += start;
+        self.fed = 0;
+        self.idx += 1;
+        let rule = &self.rules[self.idx];
+        self.current = HeadScan::new(Some(rule), self.pos, self.terminator, self.limit);
+    }
+
+    fn start(&self) -> Option<u64> {
+        match self.idx + 1 == self.rules.len() {
+            true => self.current.start().map(|start| self.base + start),
+            false => None,
+        }
+    }
+
+    fn floor(&self) -> u64 {
+        self.base + self.current.floor(self.fed)
+    }
+
+    fn finish(&mut self, len: u64) -> Result<u64> {
+        loop {
+            if self.base > len {
+                return Err(ErrorKind::InvalidSkip);
+            }
+            // Whether what the current rule applies to ends with a terminator.
+            let endn = self.last.ends_within(len - self.base);
+            let start = self.current.finish(len - self.base, endn)?;
+            if self.base + start > len {
+                return Err(ErrorKind::InvalidSkip);
+            }
+            if self.idx + 1 == self.rules.len() {
+                return Ok(self.base + start);
+            }
+
+            self.next(start);
+            if self.base < len {
+                let from = (self.base - self.kept) as usize;
+                self.current.feed(0, &self.buf[from..])?;
+                self.fed = len - self.base;
+            }
+        }
+    }
+}
+
+/// Applies a sequence of tail skips, each one to what the previous ones left.
+///
+/// Each rule is only fed the bytes that the previous rule can no longer cut, and the rest are
+/// kept until the end of the source is reached, within the buffer limit.
+pub(crate) struct TailChain<'s> {
+    scans: Vec<TailScan<'s>>,
+    // Number of bytes fed to each rule.
+    fed: Vec<u64>,
+    // The last bytes fed to each rule.
+    last: Vec<Suffix<'s>>,
+    // Bytes kept for the rules that lag behind, starting at `kept`.
+    buf: Vec<u8>,
+    kept: u64,
+    limit: usize,
+}
+
+impl<'s> TailChain<'s> {
+    fn new(rules: &'s [Skip<'s>], pos: PartPos, terminator: Terminator<'s>, limit: usize) -> Self {
+        TailChain {
+            scans: rules
+                .iter()
+                .map(|rule| TailScan::new(Some(rule), pos, terminator, limit))
+                .collect(),
+            fed: vec![0; rules.len()],
+            last: rules.iter().map(|_| Suffix::new(terminator)).collect(),
+            buf: Vec::new(),
+            kept: 0,
+            limit,
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8]) -> Result<()> {
+        self.buf.extend_from_slice(chunk);
+        let total = self.kept + self.buf.len() as u64;
+
+        self.feed_upto(0, total)?;
+        for i in 1..self.scans.len() {
+            let safe = self.scans[i - 1].safe(self.fed[i - 1], self.last[i - 1].ends());
+            self.feed_upto(i, safe.min(self.fed[i - 1]))?;
+        }
+
+        // Drops the bytes every rule has been fed.
+        let fed = self.fed.iter().copied().min().unwrap_or(total);
+        if fed > self.kept {
+            self.buf.drain(..(fed - self.kept) as usize);
+            self.kept = fed;
+        }
+        if self.buf.len() > self.limit {
+            return Err(ErrorKind::BufferExceeded(self.limit));
+        }
+
+        Ok(())
+    }
+
+    // Feeds the rule at the given index with the kept bytes up to `end`.
+    fn feed_upto(&mut self, i: usize, end: u64) -> Result<()> {
+        if end > self.fed[i] {
+            let from = (self.fed[i] - self.kept) as usize;
+            let to = (end - self.kept) as usize;
+            self.scans[i].feed(self.fed[i], &self.buf[from..to])?;
+            self.fed[i] = end;
+            self.last[i].push(&self.buf[from..to]);
+        }
+
+        Ok(())
+    }
+
+    fn safe(&self) -> u64 {
+        let last = self.scans.len() - 1;
+        self.scans[last].safe(self.fed[last], self.last[last].ends())
+    }
+
+    fn finish(&mut self, len: u64, endn: bool) -> Result<u64> {
+        let mut end = self.scans[0].finish(len, endn)?;
+        for i in 1..self.scans.len() {
+            self.feed_upto(i, end)?;
+            end = self.scans[i].finish(end, self.last[i].ends())?;
+        }
+
+        Ok(end)
+    }
+}
+
+/// Tracks where the kept contents of a source start and end as it is fed chunk by chunk.
+pub(crate) struct Scanner<'s> {
+    head: HeadScan<'s>,
+    tail: TailScan<'s>,
+    // Number of bytes fed so far.
+    pos: u64,
+    last: Suffix<'s>,
+    endn: bool,
+}
+
+impl<'s> Scanner<'s> {
+    pub(crate) fn new(head: HeadScan<'s>, tail: TailScan<'s>, terminator: Terminator<'s>) -> Self {
+        Scanner {
+            head,
+            tail,
+            pos: 0,
+            last: Suffix::new(terminator),
+            endn: false,
+        }
+    }
+
+    /// Returns `true` if the kept range depends on the contents of the source, rather than on
+    /// its length alone.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn needs_contents(&self) -> bool {
+        !matches!((&self.head, &self.tail), (HeadScan::Fixed(_), TailScan::Bytes(_)))
+    }
+
+    /// Feeds the next chunk of the source.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Result<()> {
+        if chunk.is_empty() {
+            return Ok(());
+        }
+
+        self.head.feed(self.pos, chunk)?;
+        self.tail.feed(self.pos, chunk)?;
+        self.pos += chunk.len() as u64;
+        self.last.push(chunk);
+        self.endn = self.last.ends();
+
+        Ok(())
+    }
+
+    /// Returns the kept range of the source fed, and whether it ends with a line terminator.
+    pub(crate) fn finish(&mut self) -> Result<PartRange> {
+        let start = self.head.finish(self.pos, self.endn)?;
+        let end = self.tail.finish(self.pos, self.endn)?;
+        if end < start {
+            return Err(ErrorKind::InvalidSkip);
+        }
+
+        Ok(PartRange {
+            start,
+            end,
+            endn: self.endn,
+            len: self.pos,
+            written: end - start,
+            dropped: 0,
+            checksum: None,
+            mismatch: false,
+        })
+    }
+
+    /// Returns the kept range of a source of the given length, and whether it ends with a
+    /// newline.
+    ///
+    /// This only makes sense if the contents are not needed, see [`needs_contents`].
+    ///
+    /// [`needs_contents`]: Scanner::needs_contents
+    #[cfg(feature = "tokio")]
+    pub(crate) fn finish_with(mut self, len: u64, endn: bool) -> Result<PartRange> {
+        self.pos = len;
+        self.endn = endn;
+        self.finish()
+    }
+}
+
+/// Applies a head and a tail skip to a source that is fed chunk by chunk.
+pub(crate) struct Filter<'s> {
+    scan: Scanner<'s>,
+    spool: Spool,
+    // Offset of the first byte held by the spool.
+    base: u64,
+}
+
+impl<'s> Filter<'s> {
+    pub(crate) fn new(scan: Scanner<'s>, spool: Spool) -> Self {
+        Filter { scan, spool, base: 0 }
+    }
+
+    /// Feeds the next chunk of the source, writing out every byte known to be kept.
+    pub(crate) fn feed<W: Write>(&mut self, chunk: &[u8], writer: &mut W) -> Result<()> {
+        if chunk.is_empty() {
+            return Ok(());
+        }
+
+        let offset = self.scan.pos;
+        self.scan.feed(chunk)?;
+        let end = self.scan.pos;
+
+        // Never holds bytes that are known to be skipped by the head skip.
+        let floor = self.scan.head.floor(end);
+        if floor > self.base {
+            self.spool.discard(floor.min(offset).saturating_sub(self.base));
+            self.base = floor.min(end);
+        }
+
+        // Writes out what the tail skip can no longer cut, first from the spool and then
+        // straight from the chunk.
+        if self.scan.head.start().is_some() {
+            let safe = self.scan.tail.safe(end, self.scan.endn);
+            if safe > self.base {
+                let held = safe.min(offset).saturating_sub(self.base);
+                self.spool.drain_into(held, writer)?;
+                let from = self.base.max(offset);
+                if safe > from {
+                    writer.write_all(&chunk[(from - offset) as usize..(safe - offset) as usize])?;
+                }
+                self.base = safe;
+            }
+        }
+
+        // Holds back the rest.
+        let from = (self.base.max(offset) - offset) as usize;
+        self.spool.push(&chunk[from..])?;
+
+        Ok(())
+    }
+
+    /// Writes out the remaining kept bytes, and returns the kept range of the source.
+    pub(crate) fn finish<W: Write>(mut self, writer: &mut W) -> Result<PartRange> {
+        let range = self.scan.finish()?;
+        let (start, end) = (range.start, range.end);
+
+        if start > self.base {
+            let n = (start - self.base).min(self.spool.len());
+            self.spool.discard(n);
+            self.base += n;
+        }
+        if end > self.base {
+            self.spool.drain_into(end - self.base, writer)?;
+        }
+
+        Ok(range)
+    }
+}
+
+/// Works out the start position of a source of the given length by feeding it into the given
+/// head scanner, reading no further than needed.
+pub(crate) fn scan_head<R: Read>(
+    mut head: HeadScan<'_>,
+    reader: &mut R,
+    len: u64,
+    endn: bool,
+) -> Result<u64> {
+    let mut buf = vec![0; 8 * 1024];
+    let mut pos = 0;
+    while head.start().is_none() {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        head.feed(pos, &buf[..n])?;
+        pos += n as u64;
+    }
+    head.finish(len, endn)
+}
+
+/// Works out the end position of a source of the given length by feeding it into the given tail
+/// scanner.
+pub(crate) fn scan_tail<R: Read>(
+    mut tail: TailScan<'_>,
+    reader: &mut R,
+    len: u64,
+    endn: bool,
+) -> Result<u64> {
+    let mut buf = vec![0; 8 * 1024];
+    let mut pos = 0;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        tail.feed(pos, &buf[..n])?;
+        pos += n as u64;
+    }
+    tail.finish(len, endn)
+}
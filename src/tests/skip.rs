@@ -801,3 +801,71 @@ fn test_skip_tail_before() {
         Err(_) => assert!(false),
     }
 }
+
+#[test]
+fn test_skip_until_and_before_after_partial_matches() {
+    // The first byte of each pattern appears right before its match.
+    let mut c1 = Cursor::new("aab rest");
+    let mut c2 = Cursor::new("xaab rest");
+    let mut buf = Vec::new();
+    let mut merger = RsMerger::new();
+
+    merger.skip_head(Skip::Until("ab".as_bytes()));
+    buf.clear();
+    match merger.merge_sources_into(vec![&mut c1, &mut c2], &mut buf) {
+        Ok(_) => assert_eq!(str::from_utf8(&buf).unwrap(), " rest rest"),
+        Err(_) => assert!(false),
+    }
+
+    merger.skip_head(Skip::Before("ab".as_bytes()));
+    buf.clear();
+    match merger.merge_sources_into(vec![&mut c1, &mut c2], &mut buf) {
+        Ok(_) => assert_eq!(str::from_utf8(&buf).unwrap(), "ab restab rest"),
+        Err(_) => assert!(false),
+    }
+
+    let mut c1 = Cursor::new("rest aab x");
+    let mut merger = RsMerger::new();
+
+    merger.skip_tail(Skip::Before("ab".as_bytes()));
+    buf.clear();
+    match merger.merge_sources_into(vec![&mut c1], &mut buf) {
+        Ok(_) => assert_eq!(str::from_utf8(&buf).unwrap(), "rest aab"),
+        Err(_) => assert!(false),
+    }
+}
+
+#[test]
+fn test_skip_head_repeats_stops_at_end() {
+    // The source ends with a partial repeat, which must not be read past.
+    let mut c1 = Cursor::new("ababa");
+    let mut c2 = Cursor::new("abab");
+    let mut buf = Vec::new();
+    let mut merger = RsMerger::new();
+
+    merger.skip_head(Skip::Repeats("ab".as_bytes()));
+    buf.clear();
+    match merger.merge_sources_into(vec![&mut c1, &mut c2], &mut buf) {
+        Ok(_) => assert_eq!(str::from_utf8(&buf).unwrap(), "a"),
+        Err(_) => assert!(false),
+    }
+}
+
+#[test]
+fn test_skip_lines_past_the_end_fail() {
+    // The head skip seeks past every line, which used to hang the tail skip that followed.
+    let mut c1 = Cursor::new("a\nb\nc");
+    let mut buf = Vec::new();
+    let mut merger = RsMerger::new();
+
+    merger.skip_head(Skip::Lines(3));
+    merger.skip_tail(Skip::Lines(1));
+    buf.clear();
+    match merger.merge_sources_into(vec![&mut c1], &mut buf) {
+        Ok(_) => assert!(false),
+        Err(e) => match e {
+            ErrorKind::InvalidSkip => assert!(true),
+            _ => assert!(false),
+        },
+    }
+}
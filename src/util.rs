@@ -1,6 +1,7 @@
 //! Utility functions.
 use crate::error::Result;
-use std::io::{Read, Seek, SeekFrom};
+use crate::merge::Terminator;
+use std::io::{self, Read, Seek, SeekFrom};
 
 /// Move the internal cursor of the given stream to the start position.
 pub fn seek_to_start<S: Seek>(stream: &mut S) -> Result<()> {
@@ -13,7 +14,7 @@ pub fn seek_to_end<S: Seek>(stream: &mut S) -> Result<u64> {
     stream.seek(SeekFrom::End(0)).map_err(|e| e.into())
 }
 
-/// Offset the internal cursor of the given stream relativing to the current position.
+// Offset the internal cursor of the given stream relativing to the current position.
 // pub fn seek_relative<S: Seek>(offset: i64, stream: &mut S) -> Result<u64> {
 //     stream.seek(SeekFrom::Current(offset)).map_err(|e| e.into())
 // }
@@ -28,27 +29,101 @@ pub fn seek_end<S: Seek>(offset: i64, stream: &mut S) -> Result<u64> {
     stream.seek(SeekFrom::End(offset)).map_err(|e| e.into())
 }
 
-/// Returns `true` if the given stream ends with a newline.
+/// Returns `true` if the given stream ends with one of the given line terminators.
 ///
 /// If this function succeed, this cursor position of the given stream will restore to its original
 /// position (the cursor position before calling this function).
-pub fn endswith_newline<RS: Seek + Read>(stream: &mut RS) -> Result<bool> {
-    let pos = stream.seek(SeekFrom::Current(0))?;
+pub fn endswith_newline<RS: Seek + Read>(
+    stream: &mut RS,
+    terminator: Terminator<'_>,
+) -> Result<bool> {
+    let pos = stream.stream_position()?;
     let len = stream.seek(SeekFrom::End(0))?;
-    match len {
+    let width = len.min(terminator.width() as u64);
+    match width {
         0 => {
             stream.seek(SeekFrom::Start(pos))?;
             Ok(false)
         }
         _ => {
-            stream.seek(SeekFrom::End(-1)).unwrap();
-            let mut buf = [0; 1];
+            stream.seek(SeekFrom::End(-(width as i64)))?;
+            let mut buf = vec![0; width as usize];
             stream.read_exact(&mut buf)?;
             stream.seek(SeekFrom::Start(pos))?;
-            if &buf == b"\n" {
-                return Ok(true);
+            Ok(terminator.ends(&buf))
+        }
+    }
+}
+
+/// Returns the offset of the first occurrence of the given bytes in the given stream.
+///
+/// The internal cursor of the given stream will be moved to an unspecified position.
+pub fn find<RS: Seek + Read>(bytes: &[u8], stream: &mut RS) -> Result<Option<usize>> {
+    seek_to_start(stream)?;
+    xfind::find(bytes, stream).transpose().map_err(|e| e.into())
+}
+
+/// Returns the offset of the last occurrence of the given bytes in the given stream.
+///
+/// The internal cursor of the given stream will be moved to an unspecified position.
+pub fn rfind<RS: Seek + Read>(bytes: &[u8], stream: &mut RS) -> Result<Option<usize>> {
+    xfind::rfind(bytes, stream).transpose().map_err(|e| e.into())
+}
+
+/// A stream that implements both [`Read`] and [`Seek`], usable as a trait object.
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek + ?Sized> ReadSeek for T {}
+
+/// A view of a range of bytes of the given stream, which behaves as a stream on its own.
+pub struct Window<'s, S: ?Sized> {
+    inner: &'s mut S,
+    start: u64,
+    len: u64,
+    pos: u64,
+}
+
+impl<'s, S: Seek + ?Sized> Window<'s, S> {
+    /// Creates a view of the `len` bytes that start at `start` of the given stream.
+    pub fn new(inner: &'s mut S, start: u64, len: u64) -> Self {
+        Window {
+            inner,
+            start,
+            len,
+            pos: 0,
+        }
+    }
+}
+
+impl<'s, S: Read + Seek + ?Sized> Read for Window<'s, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len {
+            return Ok(0);
+        }
+        self.inner.seek(SeekFrom::Start(self.start + self.pos))?;
+        let max = (self.len - self.pos).min(buf.len() as u64) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<'s, S: Seek + ?Sized> Seek for Window<'s, S> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => self.len.checked_add_signed(n),
+            SeekFrom::Current(n) => self.pos.checked_add_signed(n),
+        };
+        match pos {
+            Some(n) => {
+                self.pos = n;
+                Ok(n)
             }
-            Ok(false)
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
         }
     }
 }
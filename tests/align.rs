@@ -0,0 +1,109 @@
+use std::io::{Cursor, Read};
+
+use admerge::*;
+
+fn merge(merger: &RsMerger, sources: &[&'static [u8]]) -> Result<(MergeReport, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let expected = merger.merge_sources_into_with_report(cursors, &mut buf)?;
+
+    // Streaming merges, plans and merged readers must agree with seekable merges.
+    let mut stream = Vec::new();
+    let report = merger.merge_readers_into_with_report(sources.to_vec(), &mut stream)?;
+    assert_eq!(buf, stream);
+    assert_eq!(expected.parts(), report.parts());
+    assert_eq!(expected.len(), report.len());
+
+    let mut cursors: Vec<_> = sources.iter().map(Cursor::new).collect();
+    let plan = merger.plan(&mut cursors)?;
+    assert_eq!(plan.len(), expected.len());
+    for (part, planned) in expected.parts().iter().zip(plan.parts()) {
+        assert_eq!(part.fill, planned.fill);
+        assert_eq!(part.output, planned.output);
+    }
+
+    let mut reader = merger.merged_reader(sources.iter().map(Cursor::new).collect())?;
+    let mut read = Vec::new();
+    reader.read_to_end(&mut read)?;
+    assert_eq!(buf, read);
+
+    Ok((expected, buf))
+}
+
+#[test]
+fn align_parts() {
+    let mut merger = RsMerger::new();
+    merger.align_parts(4);
+
+    let sources: &[&[u8]] = &[b"a", b"bcde", b"", b"f"];
+    let (report, buf) = merge(&merger, sources).unwrap();
+    assert_eq!(buf, b"a\0\0\0bcdef");
+    let fills: Vec<_> = report.parts().iter().map(|p| p.fill).collect();
+    assert_eq!(fills, [0, 3, 0, 0]);
+    let outputs: Vec<_> = report.parts().iter().map(|p| p.output.clone()).collect();
+    assert_eq!(outputs, [0..1, 4..8, 8..8, 8..9]);
+    assert_eq!(report.padding_len(), 3);
+
+    // Newlines and paddings after a part are written before the fill.
+    merger.pad_with(Pad::Between(b"#"));
+    merger.force_ending_newline(Newline::Lf);
+    merger.fill_byte(b'.');
+    let (report, buf) = merge(&merger, &[b"ab", b"cdef"]).unwrap();
+    assert_eq!(buf, b"ab\n#cdef\n");
+    assert_eq!(report.parts()[1].output, 4..8);
+
+    let (_, buf) = merge(&merger, &[b"abc", b"d"]).unwrap();
+    assert_eq!(buf, b"abc\n#...d\n");
+
+    // Alignments of zero or one are ignored.
+    for alignment in [0, 1] {
+        merger.align_parts(alignment);
+        let (_, buf) = merge(&merger, &[b"abc", b"d"]).unwrap();
+        assert_eq!(buf, b"abc\n#d\n");
+    }
+}
+
+#[test]
+fn align_output() {
+    let mut merger = RsMerger::new();
+    merger.align_parts(512).align_output(512).fill_byte(0xff);
+
+    let sources: &[&[u8]] = &[&[1; 100], &[2; 600]];
+    let (report, buf) = merge(&merger, sources).unwrap();
+    assert_eq!(buf.len(), 1536);
+    assert_eq!(report.len(), 1536);
+    assert_eq!(report.parts()[1].output, 512..1112);
+    assert!(buf[100..512].iter().all(|&b| b == 0xff));
+    assert!(buf[1112..].iter().all(|&b| b == 0xff));
+
+    // Outputs that are already aligned are left as they are.
+    let (_, buf) = merge(&merger, &[&[1; 512]]).unwrap();
+    assert_eq!(buf.len(), 512);
+}
+
+#[test]
+fn fill_image() {
+    let mut merger = RsMerger::new();
+    merger.align_parts(4).align_output(4).image_size(16);
+
+    let (report, buf) = merge(&merger, &[b"abc", b"def"]).unwrap();
+    assert_eq!(buf, b"abc\0def\0\0\0\0\0\0\0\0\0");
+    assert_eq!(report.len(), 16);
+    assert_eq!(report.padding_len(), 10);
+
+    let (_, buf) = merge(&merger, &[b"abcdefgh", b"ijklmnop"]).unwrap();
+    assert_eq!(buf, b"abcdefghijklmnop");
+
+    // Alignment may push the output past the image size.
+    for sources in [&[&b"abcdefghi"[..], b"jklmnop"], &[b"abcdefghijklm", b"n"]] {
+        let mut cursors: Vec<_> = sources.iter().map(Cursor::new).collect();
+        let result = merger.plan(&mut cursors);
+        assert!(
+            matches!(result, Err(ErrorKind::ImageOverflow(16))),
+            "{:?}",
+            result
+        );
+        let result = merger.merge_readers_into(sources.to_vec(), &mut Vec::new());
+        assert!(matches!(result, Err(ErrorKind::ImageOverflow(16))));
+    }
+}
@@ -0,0 +1,502 @@
+#![cfg(feature = "tokio")]
+
+use std::io::prelude::*;
+use std::io::Cursor;
+use std::path::Path;
+
+use admerge::*;
+use tempfile::{tempdir, NamedTempFile};
+
+macro_rules! tempfiles {
+    () => {{
+        let mut file1 = NamedTempFile::new().unwrap();
+        let mut file2 = NamedTempFile::new().unwrap();
+        let mut file3 = NamedTempFile::new().unwrap();
+        write!(&mut file1, "header\n 11\n 12\n 13").unwrap();
+        write!(&mut file2, "header\n 21\n 22\n 23").unwrap();
+        write!(&mut file3, "header\n 31\n 32\n 33").unwrap();
+        vec![file1, file2, file3]
+    }};
+}
+
+fn sources() -> Vec<&'static [u8]> {
+    vec![
+        b"",
+        b"\n",
+        b"foo foo bar\n",
+        b"header\n 1\n 2\n",
+        b"header\r\n 1\r\n 2",
+        b"abab\nba\nab",
+        b" skip until untouched before skip ",
+        b"\xef\xbb\xbfabab\n",
+    ]
+}
+
+fn skips() -> Vec<Skip<'static>> {
+    vec![
+        Skip::Bytes(2),
+        Skip::BytesOnce(3),
+        Skip::Lines(1),
+        Skip::LinesOnce(2),
+        Skip::Repeats(b"foo "),
+        Skip::Repeats(b"ab"),
+        Skip::Until(b"until"),
+        Skip::Before(b"before"),
+        Skip::Before(b"\n"),
+        Skip::Chain(&[Skip::Repeats(b"foo "), Skip::Lines(1), Skip::Bytes(1)]),
+        Skip::Custom(&half),
+        Skip::Chain(&[Skip::Bom, Skip::Repeats(b"ab")]),
+    ]
+}
+
+// Skips half of each source.
+fn half(source: &mut SkipSource<'_>) -> Result<usize> {
+    Ok(source.len() as usize / 2)
+}
+
+fn paddings() -> Vec<Option<Pad<'static>>> {
+    vec![
+        None,
+        Some(Pad::Before(b"<")),
+        Some(Pad::Between(b"|")),
+        Some(Pad::After(b">")),
+        Some(Pad::Custom(Some(b"<"), Some(b"|"), Some(b">"))),
+    ]
+}
+
+#[tokio::test]
+async fn async_merge_matches_sync_merge() {
+    let sources = sources();
+    for (i, padding) in paddings().into_iter().enumerate() {
+        for newline in [None, Some(Newline::Lf), Some(Newline::Crlf)] {
+            for head in skips().into_iter().map(Some).chain(Some(None)) {
+                for tail in skips().into_iter().map(Some).chain(Some(None)) {
+                    let mut merger = RsMerger::new();
+                    let mut async_merger = AsyncRsMerger::new();
+                    if let Some(pad) = padding.clone() {
+                        merger.pad_with(pad.clone());
+                        async_merger.pad_with(pad);
+                    }
+                    if let Some(newline) = newline {
+                        merger.force_ending_newline(newline);
+                        async_merger.force_ending_newline(newline);
+                    }
+                    if let Some(skip) = head.clone() {
+                        merger.skip_head(skip.clone());
+                        async_merger.skip_head(skip);
+                    }
+                    if let Some(skip) = tail.clone() {
+                        merger.skip_tail(skip.clone());
+                        async_merger.skip_tail(skip);
+                    }
+
+                    // Rotates sources so that each one takes every position.
+                    for n in 0..sources.len() {
+                        let mut parts = sources.clone();
+                        parts.rotate_left(n);
+                        parts.truncate(1 + (n + i) % 3);
+
+                        let mut expected = Vec::new();
+                        let cursors = parts.iter().map(Cursor::new).collect();
+                        let expected = merger
+                            .merge_sources_into_with_report(cursors, &mut expected)
+                            .map(|report| (report.parts().to_vec(), expected));
+
+                        let mut buf = Vec::new();
+                        let cursors = parts.iter().map(Cursor::new).collect();
+                        let actual = async_merger
+                            .merge_sources_into_with_report(cursors, &mut buf)
+                            .await
+                            .map(|report| (report.parts().to_vec(), buf));
+
+                        match (expected, actual) {
+                            (Ok(expected), Ok(actual)) => assert_eq!(expected, actual),
+                            (Err(ErrorKind::InvalidSkip), Err(ErrorKind::InvalidSkip)) => (),
+                            (expected, actual) => panic!("{:?} != {:?}", expected, actual),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn async_with_paths_basics() {
+    let mut merger = AsyncFileMerger::new();
+    merger.skip_head(Skip::LinesOnce(1));
+    merger.force_ending_newline(Newline::Lf);
+    let tempfiles = tempfiles!();
+    let paths: Vec<&Path> = tempfiles.iter().map(|f| f.path()).collect();
+
+    let mut buf = Vec::new();
+    assert!(merger.with_paths(paths.clone(), &mut buf).await.is_ok());
+    assert_eq!(
+        &buf,
+        b"header\n 11\n 12\n 13\n 21\n 22\n 23\n 31\n 32\n 33\n"
+    );
+
+    let tempdir = tempdir().unwrap();
+    let mut lossy = paths.clone();
+    lossy.insert(1, tempdir.path());
+    let mut buf = Vec::new();
+    assert!(merger.with_paths_lossy(lossy.clone(), &mut buf).await.is_ok());
+    assert_eq!(&buf, b"header\n 11\n 12\n 13\n 21\n 22\n 23\n 31\n 32\n 33\n");
+
+    match merger.with_paths(lossy, &mut buf).await {
+        Err(e) => assert!(matches!(e, ErrorKind::InvalidPath(1))),
+        Ok(_) => panic!(),
+    }
+}
+
+#[tokio::test]
+async fn async_with_files_basics() {
+    let merger = AsyncFileMerger::new();
+    let files = tempfiles!()
+        .into_iter()
+        .map(|f| tokio::fs::File::from_std(f.into_file()))
+        .collect();
+
+    let mut buf = Vec::new();
+    assert!(merger.with_files(files, &mut buf).await.is_ok());
+    assert_eq!(&buf, b"header\n 11\n 12\n 13header\n 21\n 22\n 23header\n 31\n 32\n 33");
+
+    match merger.with_files(Vec::new(), &mut buf).await {
+        Err(e) => assert!(matches!(e, ErrorKind::NothingPassed)),
+        Ok(_) => panic!(),
+    }
+}
+
+#[tokio::test]
+async fn async_filter_lines_matches_sync() {
+    let mut merger = RsMerger::new();
+    let mut async_merger = AsyncRsMerger::new();
+    for filter in [
+        LineFilter::DropContaining(b"ab"),
+        LineFilter::KeepContaining(b" "),
+    ] {
+        merger
+            .filter_lines(filter)
+            .force_ending_newline(Newline::Lf);
+        async_merger
+            .filter_lines(filter)
+            .force_ending_newline(Newline::Lf);
+
+        let sources = sources();
+        let mut expected = Vec::new();
+        let cursors = sources.iter().map(Cursor::new).collect();
+        let report = merger
+            .merge_sources_into_with_report(cursors, &mut expected)
+            .unwrap();
+
+        let mut buf = Vec::new();
+        let cursors = sources.iter().map(Cursor::new).collect();
+        let async_report = async_merger
+            .merge_sources_into_with_report(cursors, &mut buf)
+            .await
+            .unwrap();
+        assert_eq!(expected, buf);
+        assert_eq!(report.parts(), async_report.parts());
+    }
+}
+
+#[tokio::test]
+async fn async_normalize_newlines_matches_sync() {
+    let mut merger = RsMerger::new();
+    let mut async_merger = AsyncRsMerger::new();
+    merger
+        .normalize_newlines(Newline::Crlf)
+        .skip_head(Skip::Lines(1))
+        .pad_with(Pad::Between(b"\n"));
+    async_merger
+        .normalize_newlines(Newline::Crlf)
+        .skip_head(Skip::Lines(1))
+        .pad_with(Pad::Between(b"\n"));
+
+    let sources = sources();
+    let mut expected = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let report = merger
+        .merge_sources_into_with_report(cursors, &mut expected)
+        .unwrap();
+
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let async_report = async_merger
+        .merge_sources_into_with_report(cursors, &mut buf)
+        .await
+        .unwrap();
+    assert_eq!(expected, buf);
+    assert_eq!(report.parts(), async_report.parts());
+}
+
+#[cfg(feature = "encoding_rs")]
+#[tokio::test]
+async fn async_transcoding_matches_sync() {
+    use encoding_rs::{UTF_16LE, WINDOWS_1252};
+
+    let mut merger = RsMerger::new();
+    let mut async_merger = AsyncRsMerger::new();
+    merger
+        .decode_sources(Decode::All(WINDOWS_1252))
+        .encode_output(UTF_16LE)
+        .skip_head(Skip::Lines(1))
+        .pad_with(Pad::Between(b"|"));
+    async_merger
+        .decode_sources(Decode::All(WINDOWS_1252))
+        .encode_output(UTF_16LE)
+        .skip_head(Skip::Lines(1))
+        .pad_with(Pad::Between(b"|"));
+
+    let sources = sources();
+    let mut expected = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let report = merger
+        .merge_sources_into_with_report(cursors, &mut expected)
+        .unwrap();
+
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let async_report = async_merger
+        .merge_sources_into_with_report(cursors, &mut buf)
+        .await
+        .unwrap();
+    assert_eq!(expected, buf);
+    assert_eq!(report.parts(), async_report.parts());
+}
+
+#[tokio::test]
+async fn async_auto_newline_matches_sync() {
+    let mut file1 = NamedTempFile::new().unwrap();
+    let mut file2 = NamedTempFile::new().unwrap();
+    write!(&mut file1, " 11\r\n 12").unwrap();
+    write!(&mut file2, " 21\n 22").unwrap();
+    let paths = vec![file1.path(), file2.path()];
+
+    let mut merger = FileMerger::new();
+    let mut async_merger = AsyncFileMerger::new();
+    merger.normalize_newlines(NewlineStyle::Auto);
+    async_merger.normalize_newlines(NewlineStyle::Auto);
+
+    let mut expected = Vec::new();
+    let report = merger
+        .with_paths_with_report(paths.clone(), &mut expected)
+        .unwrap();
+    assert_eq!(&expected, b" 11\r\n 12 21\r\n 22");
+
+    let mut buf = Vec::new();
+    let async_report = async_merger
+        .with_paths_with_report(paths, &mut buf)
+        .await
+        .unwrap();
+    assert_eq!(expected, buf);
+    assert_eq!(report.parts(), async_report.parts());
+    assert_eq!(async_report.newline(), Some(Newline::Crlf));
+}
+
+#[tokio::test]
+async fn async_line_terminator_matches_sync() {
+    let sources: Vec<&[u8]> = vec![b"h\r 11\r 12\r", b"h\r\n 21\r\n 22", b"h\r 31"];
+    for &terminator in [Terminator::Cr, Terminator::Crlf, Terminator::Any].iter() {
+        let mut merger = RsMerger::new();
+        let mut async_merger = AsyncRsMerger::new();
+        merger
+            .line_terminator(terminator)
+            .skip_head(Skip::LinesOnce(1))
+            .force_ending_newline(Newline::Lf);
+        async_merger
+            .line_terminator(terminator)
+            .skip_head(Skip::LinesOnce(1))
+            .force_ending_newline(Newline::Lf);
+
+        let mut expected = Vec::new();
+        let cursors = sources.iter().map(Cursor::new).collect();
+        let report = merger
+            .merge_sources_into_with_report(cursors, &mut expected)
+            .unwrap();
+
+        let mut buf = Vec::new();
+        let cursors = sources.iter().map(Cursor::new).collect();
+        let async_report = async_merger
+            .merge_sources_into_with_report(cursors, &mut buf)
+            .await
+            .unwrap();
+        assert_eq!(expected, buf);
+        assert_eq!(report.parts(), async_report.parts());
+    }
+}
+
+#[tokio::test]
+async fn async_check_records_matches_sync() {
+    let sources: Vec<&[u8]> = vec![b"hd1122", b"hd33", b"hd445"];
+    let mut merger = RsMerger::new();
+    let mut async_merger = AsyncRsMerger::new();
+    merger.skip_head(Skip::Records(1, 2)).check_records(2);
+    async_merger.skip_head(Skip::Records(1, 2)).check_records(2);
+
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let result = merger.merge_sources_into(cursors, &mut Vec::new());
+    assert!(matches!(result, Err(ErrorKind::PartialRecord(2))));
+
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let result = async_merger
+        .merge_sources_into(cursors, &mut Vec::new())
+        .await;
+    assert!(matches!(result, Err(ErrorKind::PartialRecord(2))));
+}
+
+#[tokio::test]
+async fn async_alignment_matches_sync() {
+    let sources: Vec<&[u8]> = vec![b"boot", b"kernel", b"rootfs"];
+    let mut merger = RsMerger::new();
+    let mut async_merger = AsyncRsMerger::new();
+    merger.align_parts(8).align_output(16).fill_byte(0xff);
+    async_merger.align_parts(8).align_output(16).fill_byte(0xff);
+
+    let mut expected = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let report = merger
+        .merge_sources_into_with_report(cursors, &mut expected)
+        .unwrap();
+
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let async_report = async_merger
+        .merge_sources_into_with_report(cursors, &mut buf)
+        .await
+        .unwrap();
+    assert_eq!(expected, buf);
+    assert_eq!(buf.len(), 32);
+    assert_eq!(report.parts(), async_report.parts());
+}
+
+#[tokio::test]
+async fn async_csv_matches_sync() {
+    let sources: Vec<&[u8]> = vec![b"a,b\n1,2\n", b"b,c\r\n3,4\r\n", b"c,a,b\n5,6,7"];
+    for columns in [None, Some(Columns::Union), Some(Columns::Only(&["b", "x"]))] {
+        let mut merger = RsMerger::new();
+        let mut async_merger = AsyncRsMerger::new();
+        merger.csv_headers(Csv::new()).missing_fields(b"NA");
+        async_merger.csv_headers(Csv::new()).missing_fields(b"NA");
+        merger.emit_index(IndexPlacement::Footer);
+        async_merger.emit_index(IndexPlacement::Footer);
+        if let Some(columns) = columns {
+            merger.csv_columns(columns);
+            async_merger.csv_columns(columns);
+        }
+
+        let mut expected = Vec::new();
+        let cursors = sources.iter().map(Cursor::new).collect();
+        let result = merger.merge_sources_into_with_report(cursors, &mut expected);
+
+        let mut buf = Vec::new();
+        let cursors = sources.iter().map(Cursor::new).collect();
+        let async_result = async_merger
+            .merge_sources_into_with_report(cursors, &mut buf)
+            .await;
+        match (result, async_result) {
+            (Ok(report), Ok(async_report)) => {
+                assert_eq!(expected, buf);
+                assert_eq!(report.parts(), async_report.parts());
+            }
+            (Err(e), Err(async_e)) => assert_eq!(e.to_string(), async_e.to_string()),
+            (result, async_result) => panic!("{:?} {:?}", result, async_result),
+        }
+    }
+}
+
+#[tokio::test]
+async fn async_buffer_limit() {
+    let sources: Vec<&[u8]> = vec![b"12345678", b"abcdefgh"];
+    let mut merger = AsyncRsMerger::new();
+    merger.skip_head(Skip::Custom(&half)).buffer_limit(4);
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let result = merger.merge_sources_into(cursors, &mut Vec::new()).await;
+    assert!(matches!(result, Err(ErrorKind::BufferExceeded(4))));
+
+    // Rewritten records keep the error kind as well.
+    let mut merger = AsyncRsMerger::new();
+    merger.csv_columns(Columns::Only(&["a"])).buffer_limit(4);
+    let sources: Vec<&[u8]> = vec![b"a\n1\n", b"a\n12345\n"];
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let result = merger.merge_sources_into(cursors, &mut Vec::new()).await;
+    assert!(matches!(result, Err(ErrorKind::BufferExceeded(4))));
+}
+
+#[tokio::test]
+async fn async_rewrites_sources_beyond_buffer_limit() {
+    let text = "line\r\n".repeat(16 * 1024);
+    let csv = format!("a,b\n{}", "1,2\n".repeat(16 * 1024));
+    let sources: Vec<&[u8]> = vec![text.as_bytes(), csv.as_bytes()];
+    for case in 0..3 {
+        let mut merger = RsMerger::new();
+        let mut async_merger = AsyncRsMerger::new();
+        merger.buffer_limit(1024);
+        async_merger.buffer_limit(1024);
+        match case {
+            0 => {
+                merger.normalize_newlines(Newline::Lf);
+                async_merger.normalize_newlines(Newline::Lf);
+            }
+            1 => {
+                merger.csv_columns(Columns::Only(&["b", "a"]));
+                async_merger.csv_columns(Columns::Only(&["b", "a"]));
+            }
+            #[cfg(feature = "encoding_rs")]
+            _ => {
+                merger.decode_sources(Decode::Auto(encoding_rs::UTF_8));
+                async_merger.decode_sources(Decode::Auto(encoding_rs::UTF_8));
+            }
+            #[cfg(not(feature = "encoding_rs"))]
+            _ => continue,
+        }
+
+        let mut expected = Vec::new();
+        let cursors = sources.iter().map(Cursor::new).collect();
+        let report = merger
+            .merge_sources_into_with_report(cursors, &mut expected)
+            .unwrap();
+
+        let mut buf = Vec::new();
+        let cursors = sources.iter().map(Cursor::new).collect();
+        let async_report = async_merger
+            .merge_sources_into_with_report(cursors, &mut buf)
+            .await
+            .unwrap();
+        assert_eq!(expected, buf);
+        assert_eq!(report.parts(), async_report.parts());
+    }
+}
+
+#[cfg(feature = "tempfile")]
+#[tokio::test(flavor = "multi_thread")]
+async fn async_spill_to_disk() {
+    let sources: Vec<&[u8]> = vec![b"12345678", b"abcd\r\nefgh"];
+    let mut merger = AsyncRsMerger::new();
+    merger
+        .skip_head(Skip::Custom(&half))
+        .normalize_newlines(Newline::Lf)
+        .buffer_limit(4)
+        .spill_to_disk(true);
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    merger.merge_sources_into(cursors, &mut buf).await.unwrap();
+    assert_eq!(buf, b"5678\nefgh");
+}
+
+#[tokio::test]
+async fn async_with_paths_csv() {
+    let dir = tempdir().unwrap();
+    let mut paths = Vec::new();
+    for (i, contents) in ["a,b\n1,2\n", "c\n3\n", "b,a\n4,5\n"].iter().enumerate() {
+        let path = dir.path().join(format!("{}.csv", i));
+        std::fs::write(&path, contents).unwrap();
+        paths.push(path);
+    }
+
+    let mut merger = AsyncFileMerger::new();
+    merger.csv_columns(Columns::Union).max_open_files(2);
+    let mut buf = Vec::new();
+    merger.with_paths(paths, &mut buf).await.unwrap();
+    assert_eq!(buf, b"a,b,c\n1,2,\n,,3\n5,4,\n");
+}
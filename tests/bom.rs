@@ -0,0 +1,105 @@
+use std::io::{Cursor, Read};
+
+use admerge::*;
+
+mod common;
+
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<Vec<u8>> {
+    common::merge(merger, sources, &[ErrorKind::InvalidSkip])
+}
+
+#[test]
+fn detect_boms() {
+    assert_eq!(Bom::detect(b"\xef\xbb\xbf"), Some(Bom::Utf8));
+    assert_eq!(Bom::detect(b"\xff\xfe"), Some(Bom::Utf16Le));
+    assert_eq!(Bom::detect(b"\xfe\xff\x00a"), Some(Bom::Utf16Be));
+    assert_eq!(Bom::detect(b"\xef\xbb"), None);
+    assert_eq!(Bom::detect(b""), None);
+
+    for bom in [Bom::Utf8, Bom::Utf16Le, Bom::Utf16Be] {
+        assert_eq!(Bom::detect(bom.as_bytes()), Some(bom));
+    }
+}
+
+#[test]
+fn skip_boms() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Bom);
+
+    let sources: &[&[u8]] = &[
+        b"\xef\xbb\xbf 11\n",
+        b"\xff\xfe 21\n",
+        b"\xfe\xff 31\n",
+        b" 41\n",
+        b"\xef\xbb",
+        b"",
+    ];
+    assert_eq!(
+        merge(&merger, sources).unwrap(),
+        b" 11\n 21\n 31\n 41\n\xef\xbb"
+    );
+
+    merger.skip_head(Skip::BomOnce);
+    assert_eq!(
+        merge(&merger, &[b"\xef\xbb\xbf 11\n", b"\xef\xbb\xbf 21\n"]).unwrap(),
+        b"\xef\xbb\xbf 11\n 21\n"
+    );
+
+    // Boms are only skipped from the head.
+    merger.skip_tail(Skip::Bom);
+    assert_eq!(merge(&merger, &[b"\xef\xbb\xbf"]).unwrap(), b"\xef\xbb\xbf");
+}
+
+#[test]
+fn skip_lines_after_boms() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Chain(&[
+        Skip::Bom,
+        Skip::Repeats(b"\n"),
+        Skip::Lines(1),
+    ]));
+
+    let sources: &[&[u8]] = &[b"\xef\xbb\xbf\nheader\n 11\n", b"header\n 21\n"];
+    assert_eq!(merge(&merger, sources).unwrap(), b" 11\n 21\n");
+}
+
+#[test]
+fn emit_a_single_bom() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Chain(&[Skip::Bom, Skip::LinesOnce(1)]));
+    merger.pad_with(Pad::Custom(Some(b"<"), None, Some(b">")));
+    merger.emit_bom(Bom::Utf8);
+
+    let sources = || {
+        vec![
+            Cursor::new(&b"\xef\xbb\xbfheader\n 11\n"[..]),
+            Cursor::new(&b"\xef\xbb\xbfheader\n 21\n"[..]),
+        ]
+    };
+    let expected = b"\xef\xbb\xbf<header\n 11\n 21\n>";
+
+    let mut buf = Vec::new();
+    let report = merger
+        .merge_sources_into_with_report(sources(), &mut buf)
+        .unwrap();
+    assert_eq!(buf, expected);
+    assert_eq!(report.len(), expected.len() as u64);
+    assert_eq!(report.padding_len(), 2);
+    assert_eq!(report.parts()[0].output, 4..15);
+
+    let plan = merger.plan(&mut sources()).unwrap();
+    assert_eq!(plan.parts()[0].bom, Some(&b"\xef\xbb\xbf"[..]));
+    assert_eq!(plan.parts()[1].bom, None);
+    assert_eq!(plan.len(), expected.len() as u64);
+
+    let mut buf = Vec::new();
+    let mut reader = merger.merged_reader(sources()).unwrap();
+    reader.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, expected);
+
+    let mut buf = Vec::new();
+    let readers: Vec<&[u8]> = vec![b"\xff\xfeh\n 11\n", b"h\n 21\n"];
+    merger.emit_bom(Bom::Utf16Le);
+    merger.merge_readers_into(readers, &mut buf).unwrap();
+    assert_eq!(buf, b"\xff\xfe<h\n 11\n 21\n>");
+}
@@ -0,0 +1,75 @@
+use admerge::*;
+
+mod common;
+
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<Vec<u8>> {
+    common::merge(merger, sources, &[ErrorKind::InvalidSkip])
+}
+
+#[test]
+fn chained_head_skips() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Chain(&[
+        Skip::Repeats(b"\xef\xbb\xbf"),
+        Skip::Lines(2),
+        Skip::Repeats(b"\n"),
+    ]));
+
+    let sources: &[&[u8]] = &[
+        b"\xef\xbb\xbfname\nversion\n\n\n 11\n",
+        b"name\nversion\n 21\n",
+    ];
+    assert_eq!(merge(&merger, sources).unwrap(), b" 11\n 21\n");
+
+    // Fails if a skip cannot be applied to what is left.
+    merger.skip_head(Skip::Chain(&[Skip::Lines(1), Skip::Bytes(4)]));
+    assert!(matches!(
+        merge(&merger, &[b"header\nfoo"]),
+        Err(ErrorKind::InvalidSkip)
+    ));
+}
+
+#[test]
+fn chained_tail_skips() {
+    let mut merger = RsMerger::new();
+    merger.skip_tail(Skip::Chain(&[
+        Skip::Until(b"-- end"),
+        Skip::Repeats(b"\n"),
+        Skip::Bytes(1),
+    ]));
+
+    let sources: &[&[u8]] = &[b" 11\n 12;\n\n\n-- end\n", b" 21;\n-- end"];
+    assert_eq!(merge(&merger, sources).unwrap(), b" 11\n 12 21");
+
+    // The head and tail skips overlap.
+    merger.skip_head(Skip::Bytes(4));
+    assert!(matches!(
+        merge(&merger, &[b" 11-- end"]),
+        Err(ErrorKind::InvalidSkip)
+    ));
+}
+
+#[test]
+fn chained_skips_are_bounded() {
+    let mut data = vec![b'x'; 64 * 1024];
+    data.extend_from_slice(b"\nend\n");
+    let mut merger = RsMerger::new();
+    merger.buffer_limit(1024);
+
+    // Skips that only look at a bounded window fit regardless of the source length.
+    merger.skip_head(Skip::Chain(&[Skip::Bytes(1), Skip::Bytes(2)]));
+    merger.skip_tail(Skip::Chain(&[Skip::Bytes(1), Skip::Bytes(5)]));
+    let mut buf = Vec::new();
+    merger
+        .merge_readers_into(vec![&data[..]], &mut buf)
+        .unwrap();
+    assert_eq!(buf.len(), data.len() - 3 - 6);
+
+    // Other skips keep what the upcoming ones may see, even when spilling.
+    data.splice(..0, b"end".iter().copied());
+    merger.skip_tail(Skip::Chain(&[Skip::Until(b"end"), Skip::Bytes(1)]));
+    #[cfg(feature = "tempfile")]
+    merger.spill_to_disk(true);
+    let result = merger.merge_readers_into(vec![&data[..]], &mut Vec::new());
+    assert!(matches!(result, Err(ErrorKind::BufferExceeded(1024))));
+}
@@ -0,0 +1,103 @@
+use std::fs;
+use std::io::Cursor;
+
+use admerge::*;
+
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<String> {
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let report = merger.merge_sources_into_with_report(cursors, &mut buf)?;
+    assert_eq!(report.len(), buf.len() as u64);
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+#[test]
+fn columns_union() {
+    let mut merger = RsMerger::new();
+    merger.csv_columns(Columns::Union);
+
+    let sources: &[&[u8]] = &[b"a,b\n1,2\n", b"b,c\r\n3,4\r\n", b"c,a,b\n5,6,7"];
+    let buf = merge(&merger, sources).unwrap();
+    assert_eq!(buf, "a,b,c\n1,2,\n,3,4\r\n6,7,5");
+
+    // Missing fields are filled with the given default, and short records alike.
+    merger.missing_fields(b"NA");
+    let buf = merge(&merger, &[b"a,b\n1\n", b"b\n2\n"]).unwrap();
+    assert_eq!(buf, "a,b\n1,NA\nNA,2\n");
+
+    // Empty sources, blank lines and sources that only hold a header.
+    let buf = merge(&merger, &[b"a\n1\n\n", b"", b"b\n"]).unwrap();
+    assert_eq!(buf, "a,b\n1,NA\n\n");
+
+    // Other options apply to the rewritten records.
+    merger.pad_with(Pad::Between(b"#\n"));
+    merger.force_ending_newline(Newline::Lf);
+    let buf = merge(&merger, &[b"a\n1", b"b\n2"]).unwrap();
+    assert_eq!(buf, "a,b\n1,NA\n#\nNA,2\n");
+}
+
+#[test]
+fn columns_only() {
+    let mut merger = RsMerger::new();
+    merger.csv_columns(Columns::Only(&["id", "note"]));
+
+    let sources: &[&[u8]] = &[b"id,size\n1,10\n", b"note,id\n\"a,\"\"b\"\"\",2\n"];
+    let buf = merge(&merger, sources).unwrap();
+    assert_eq!(buf, "id,note\n1,\n2,\"a,\"\"b\"\"\"\n");
+
+    // Sources that cannot be seeked work as well.
+    let mut buf = Vec::new();
+    merger
+        .merge_readers_into(sources.to_vec(), &mut buf)
+        .unwrap();
+    assert_eq!(buf, b"id,note\n1,\n2,\"a,\"\"b\"\"\"\n");
+
+    // Fields are read and written in the given format.
+    merger.csv_headers(Csv::new().delimiter(b';').quote(b'\''));
+    let buf = merge(&merger, &[b"note;id\n'x;y';1\n", b"id\n2\n"]).unwrap();
+    assert_eq!(buf, "id;note\n1;'x;y'\n2;\n");
+}
+
+#[test]
+fn columns_unsupported() {
+    let mut merger = RsMerger::new();
+    merger.csv_columns(Columns::Union);
+    let sources: Vec<&[u8]> = vec![b"a\n1\n", b"b\n2\n"];
+    let result = merger.merge_readers_into(sources, &mut Vec::new());
+    assert!(matches!(
+        result,
+        Err(ErrorKind::Unsupported("merge_readers_into"))
+    ));
+
+    let mut cursors = vec![Cursor::new(b"a\n1\n")];
+    let result = merger.plan(&mut cursors);
+    assert!(matches!(result, Err(ErrorKind::Unsupported("plan"))));
+
+    // Records are bounded by the buffer limit.
+    merger.csv_columns(Columns::Only(&["a"]));
+    merger.buffer_limit(4);
+    let result = merge(&merger, &[b"a\n1\n", b"a\n12345\n"]);
+    assert!(matches!(result, Err(ErrorKind::BufferExceeded(4))));
+}
+
+#[test]
+fn columns_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut paths = Vec::new();
+    for (i, contents) in ["a,b\n1,2\n", "c\n3\n", "b,a\n4,5\n"].iter().enumerate() {
+        let path = dir.path().join(format!("{}.csv", i));
+        fs::write(&path, contents).unwrap();
+        paths.push(path);
+    }
+
+    let mut merger = FileMerger::new();
+    merger.csv_columns(Columns::Union);
+    merger.max_open_files(1);
+    let mut buf = Vec::new();
+    merger.with_paths(paths.clone(), &mut buf).unwrap();
+    assert_eq!(buf, b"a,b,c\n1,2,\n,,3\n5,4,\n");
+
+    paths.push(dir.path().to_path_buf());
+    let result = merger.with_paths(paths, &mut Vec::new());
+    assert!(matches!(result, Err(ErrorKind::InvalidPath(3))));
+}
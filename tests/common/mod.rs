@@ -0,0 +1,38 @@
+use std::io::Cursor;
+use std::mem;
+
+use admerge::*;
+
+// Merges the given sources with `merge_sources_into`, checking that `merge_readers_into` agrees
+// on the output and the report. Both may only fail with the same error, of a kind in `errors`.
+pub fn merge(merger: &RsMerger, sources: &[&[u8]], errors: &[ErrorKind]) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let expected = merger
+        .merge_sources_into_with_report(cursors, &mut buf)
+        .map(|report| (report, buf));
+
+    // Streaming merges must agree with seekable ones.
+    let mut buf = Vec::new();
+    let actual = merger
+        .merge_readers_into_with_report(sources.to_vec(), &mut buf)
+        .map(|report| (report, buf));
+
+    match (expected, actual) {
+        (Ok((expected_report, expected)), Ok((actual_report, actual))) => {
+            assert_eq!(expected, actual);
+            assert_eq!(expected_report.parts(), actual_report.parts());
+            assert_eq!(expected_report.len(), expected.len() as u64);
+            Ok(expected)
+        }
+        (Err(expected), Err(actual))
+            if errors
+                .iter()
+                .any(|e| mem::discriminant(e) == mem::discriminant(&expected))
+                && expected.to_string() == actual.to_string() =>
+        {
+            Err(expected)
+        }
+        (expected, actual) => panic!("{:?} != {:?}", expected, actual),
+    }
+}
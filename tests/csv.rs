@@ -0,0 +1,155 @@
+use std::fs;
+use std::io::{Cursor, Read};
+
+use admerge::*;
+
+// Merges the given sources with seekable and streaming merges, plans and merged readers, which
+// must all agree.
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let report = merger.merge_sources_into_with_report(cursors, &mut buf)?;
+    assert_eq!(report.len(), buf.len() as u64);
+
+    let mut stream = Vec::new();
+    merger.merge_readers_into(sources.to_vec(), &mut stream)?;
+    assert_eq!(buf, stream);
+
+    let mut cursors: Vec<_> = sources.iter().map(Cursor::new).collect();
+    assert_eq!(merger.plan(&mut cursors)?.len(), buf.len() as u64);
+
+    let mut read = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    merger.merged_reader(cursors)?.read_to_end(&mut read)?;
+    assert_eq!(buf, read);
+
+    Ok(buf)
+}
+
+fn mismatch(result: Result<Vec<u8>>) -> usize {
+    match result {
+        Err(ErrorKind::HeaderMismatch(i)) => i,
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn csv_headers() {
+    let mut merger = RsMerger::new();
+    merger.csv_headers(Csv::new());
+
+    let buf = merge(&merger, &[b"a,b\n1,2\n", b"a,b\n3,4\n", b"a,b\n5,6"]).unwrap();
+    assert_eq!(buf, b"a,b\n1,2\n3,4\n5,6");
+
+    // Quoted fields may hold delimiters, quotes and line terminators.
+    let header = b"id,\"name\nfull\",\"say \"\"hi\"\"\"\r\n";
+    let first = [&header[..], b"1,a,b\n"].concat();
+    let second = [&header[..], b"2,c,d\n"].concat();
+    let buf = merge(&merger, &[&first, &second]).unwrap();
+    assert_eq!(buf, [&first[..], b"2,c,d\n"].concat());
+
+    // Headers only need to hold the same fields.
+    let buf = merge(&merger, &[b"a,b\r\n1,2\n", b"\"a\",\"b\"\n3,4\n"]).unwrap();
+    assert_eq!(buf, b"a,b\r\n1,2\n3,4\n");
+
+    // Empty sources and sources that only hold a header.
+    let buf = merge(&merger, &[b"a,b\n1,2\n", b"", b"a,b", b"a,b\n"]).unwrap();
+    assert_eq!(buf, b"a,b\n1,2\n");
+
+    // Other options apply to the records.
+    merger.pad_with(Pad::Between(b"#\n"));
+    merger.force_ending_newline(Newline::Lf);
+    let buf = merge(&merger, &[b"a\n1", b"a\n2\n"]).unwrap();
+    assert_eq!(buf, b"a\n1\n#\n2\n");
+}
+
+#[test]
+fn csv_headers_reject_skips() {
+    let sources: &[&[u8]] = &[b"a\n\"1\n2\"\n", b"a\n3\n"];
+    let mut merger = RsMerger::new();
+    merger.csv_headers(Csv::new());
+
+    // The header takes the place of the head skip.
+    merger.skip_head(Skip::Lines(1));
+    assert!(matches!(
+        merge(&merger, sources),
+        Err(ErrorKind::InvalidSkip)
+    ));
+
+    // The tail skip could cut a quoted field in half.
+    let mut merger = RsMerger::new();
+    merger.csv_headers(Csv::new());
+    merger.skip_tail(Skip::Lines(1));
+    assert!(matches!(
+        merge(&merger, sources),
+        Err(ErrorKind::InvalidSkip)
+    ));
+    let result = merger.merge_readers_into(sources.to_vec(), &mut Vec::new());
+    assert!(matches!(result, Err(ErrorKind::InvalidSkip)));
+}
+
+#[test]
+fn csv_header_mismatches() {
+    let mut merger = RsMerger::new();
+    merger.csv_headers(Csv::new());
+
+    let sources: &[&[u8]] = &[b"a,b\n1,2\n", b"a,b\n3,4\n", b"a,c\n5,6\n"];
+    assert_eq!(mismatch(merge(&merger, sources)), 2);
+    assert_eq!(mismatch(merge(&merger, &[b"a,b\n", b"a\n"])), 1);
+    assert_eq!(mismatch(merge(&merger, &[b"a,b\n", b"\"a,b\"\n"])), 1);
+
+    // Nothing is written from the source that does not match.
+    let mut buf = Vec::new();
+    merger.pad_with(Pad::Between(b"#\n"));
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let result = merger.merge_sources_into(cursors, &mut buf);
+    assert!(matches!(result, Err(ErrorKind::HeaderMismatch(2))));
+    assert_eq!(buf, b"a,b\n1,2\n#\n3,4\n#\n");
+
+    // Even when the output is indexed.
+    merger.emit_index(IndexPlacement::Sidecar);
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let result = merger.merge_sources_into(cursors, &mut Vec::new());
+    assert!(matches!(result, Err(ErrorKind::HeaderMismatch(2))));
+}
+
+#[test]
+fn csv_formats() {
+    let mut merger = RsMerger::new();
+    merger.csv_headers(Csv::new().delimiter(b';').quote(b'\''));
+    let buf = merge(&merger, &[b"a;'b;\nc'\n1;2\n", b"'a';'b;\nc'\n3;4\n"]).unwrap();
+    assert_eq!(buf, b"a;'b;\nc'\n1;2\n3;4\n");
+
+    // Double quotes are no longer special.
+    assert_eq!(mismatch(merge(&merger, &[b"a;b\n", b"\"a\";b\n"])), 1);
+
+    // Headers are bounded by the buffer limit.
+    merger.buffer_limit(4);
+    let result = merge(&merger, &[b"a;b\n", b"'a;b;c;d'\n"]);
+    assert!(matches!(result, Err(ErrorKind::BufferExceeded(4))));
+}
+
+#[test]
+fn csv_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut paths = Vec::new();
+    for (i, contents) in ["a,b\n1,2\n", "a,b\n3,4\n", "a,b,c\n5,6,7\n"]
+        .iter()
+        .enumerate()
+    {
+        let path = dir.path().join(format!("{}.csv", i));
+        fs::write(&path, contents).unwrap();
+        paths.push(path);
+    }
+
+    let mut merger = FileMerger::new();
+    merger.csv_headers(Csv::new());
+    let mut buf = Vec::new();
+    merger.with_paths(paths[..2].to_vec(), &mut buf).unwrap();
+    assert_eq!(buf, b"a,b\n1,2\n3,4\n");
+
+    let result = merger.with_paths(paths.clone(), &mut Vec::new());
+    assert!(matches!(result, Err(ErrorKind::HeaderMismatch(2))));
+    let result = merger.plan(&paths);
+    assert!(matches!(result, Err(ErrorKind::HeaderMismatch(2))));
+}
@@ -0,0 +1,107 @@
+use admerge::*;
+
+mod common;
+
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<Vec<u8>> {
+    common::merge(merger, sources, &[ErrorKind::InvalidSkip])
+}
+
+// Skips a header whose length is given by its first byte.
+fn header(source: &mut SkipSource<'_>) -> Result<usize> {
+    let mut len = [0];
+    match source.read_at(0, &mut len)? {
+        0 => Ok(0),
+        _ => Ok(1 + len[0] as usize),
+    }
+}
+
+// Skips everything after the last `;`.
+fn trailer(source: &mut SkipSource<'_>) -> Result<usize> {
+    match source.rfind(b";")? {
+        Some(pos) => Ok((source.len() - pos - 1) as usize),
+        None => Ok(0),
+    }
+}
+
+#[test]
+fn custom_head_skips() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Custom(&header));
+
+    let sources: &[&[u8]] = &[b"\x02ab 11\n", b"\x00 21\n", b""];
+    assert_eq!(merge(&merger, sources).unwrap(), b" 11\n 21\n");
+
+    // Fails if the rule skips past the end.
+    assert!(matches!(
+        merge(&merger, &[b"\x09ab"]),
+        Err(ErrorKind::InvalidSkip)
+    ));
+}
+
+#[test]
+fn custom_tail_skips() {
+    let mut merger = RsMerger::new();
+    merger.skip_tail(Skip::Custom(&trailer));
+
+    let sources: &[&[u8]] = &[b" 11;\n-- end\n", b" 21;"];
+    assert_eq!(merge(&merger, sources).unwrap(), b" 11; 21;");
+
+    // Fails if the rule skips past the start.
+    let rule = |source: &mut SkipSource<'_>| Ok(source.len() as usize + 1);
+    merger.skip_tail(Skip::Custom(&rule));
+    assert!(matches!(
+        merge(&merger, &[b" 11\n"]),
+        Err(ErrorKind::InvalidSkip)
+    ));
+}
+
+#[test]
+fn custom_rules_see_their_side_and_position() {
+    let rule = |source: &mut SkipSource<'_>| -> Result<usize> {
+        match (source.side(), source.position()) {
+            (Side::Head, PartPos::Start) => Ok(0),
+            (Side::Head, _) => Ok(source.find(b"\n")?.map_or(0, |pos| pos as usize + 1)),
+            (Side::Tail, PartPos::End) => Ok(0),
+            (Side::Tail, _) => Ok(source.ends_with_newline() as usize),
+        }
+    };
+
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Custom(&rule));
+    merger.skip_tail(Skip::Custom(&rule));
+
+    let sources: &[&[u8]] = &[b"h\n 11\n", b"h\n 21\n", b"h\n 31\n"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"h\n 11 21 31\n");
+}
+
+#[test]
+fn custom_rules_in_chains() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Chain(&[Skip::Lines(1), Skip::Custom(&header)]));
+    merger.skip_tail(Skip::Chain(&[Skip::Custom(&trailer), Skip::Bytes(1)]));
+
+    let sources: &[&[u8]] = &[b"name\n\x01a 11;\n", b"name\n\x00 21;-- end"];
+    assert_eq!(merge(&merger, sources).unwrap(), b" 11 21");
+}
+
+#[test]
+fn custom_rules_respect_buffer_limit() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Custom(&header));
+    merger.buffer_limit(4);
+
+    let mut buf = Vec::new();
+    let sources: Vec<&[u8]> = vec![b"\x01a 11\n"];
+    assert!(matches!(
+        merger.merge_readers_into(sources.clone(), &mut buf),
+        Err(ErrorKind::BufferExceeded(4))
+    ));
+
+    #[cfg(feature = "tempfile")]
+    {
+        let mut buf = Vec::new();
+        merger.spill_to_disk(true);
+        merger.merge_readers_into(sources, &mut buf).unwrap();
+        assert_eq!(buf, b" 11\n");
+    }
+}
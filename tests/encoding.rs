@@ -0,0 +1,162 @@
+#![cfg(feature = "encoding_rs")]
+
+use std::io::Cursor;
+
+use admerge::*;
+use encoding_rs::{SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+mod common;
+
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<Vec<u8>> {
+    common::merge(
+        merger,
+        sources,
+        &[ErrorKind::InvalidSkip, ErrorKind::BufferExceeded(0)],
+    )
+}
+
+// Encodes the given text into UTF-16LE.
+fn utf16le(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+#[test]
+fn decode_sources_into_utf8() {
+    let encodings = [UTF_16LE, WINDOWS_1252];
+    let mut merger = RsMerger::new();
+    merger.decode_sources(Decode::Each(&encodings));
+
+    let utf16: &'static [u8] = Box::leak(utf16le("\u{feff}naïve\n").into_boxed_slice());
+    let sources: &[&[u8]] = &[utf16, b"caf\xe9\n", "日本\n".as_bytes()];
+    assert_eq!(
+        merge(&merger, sources).unwrap(),
+        "naïve\ncafé\n日本\n".as_bytes()
+    );
+
+    merger.decode_sources(Decode::All(WINDOWS_1252));
+    assert_eq!(
+        merge(&merger, &[b"\xe9", b"\xff"]).unwrap(),
+        "éÿ".as_bytes()
+    );
+
+    // Malformed input is replaced.
+    merger.decode_sources(Decode::All(UTF_8));
+    assert_eq!(
+        merge(&merger, &[b"a\xffb"]).unwrap(),
+        "a\u{fffd}b".as_bytes()
+    );
+}
+
+#[test]
+fn decode_sources_by_bom() {
+    let mut merger = RsMerger::new();
+    merger.decode_sources(Decode::Auto(WINDOWS_1252));
+
+    let utf16: &'static [u8] = Box::leak(utf16le("\u{feff}ï\n").into_boxed_slice());
+    let sources: &[&[u8]] = &[utf16, b"\xfe\xff\x00\xe9", b"\xef\xbb\xbf\xc3\xa9", b"\xe9"];
+    assert_eq!(merge(&merger, sources).unwrap(), "ï\nééé".as_bytes());
+}
+
+#[test]
+fn skips_apply_to_decoded_text() {
+    let mut merger = RsMerger::new();
+    merger.decode_sources(Decode::All(UTF_16LE));
+    merger.skip_head(Skip::Lines(1));
+    merger.skip_tail(Skip::Until("—".as_bytes()));
+
+    let s1: &'static [u8] = Box::leak(utf16le("name\r\nÅsa\r\n— 1").into_boxed_slice());
+    let s2: &'static [u8] = Box::leak(utf16le("name\r\nÖrjan\r\n— 2").into_boxed_slice());
+    assert_eq!(
+        merge(&merger, &[s1, s2]).unwrap(),
+        "Åsa\r\nÖrjan\r\n".as_bytes()
+    );
+}
+
+#[test]
+fn encode_output() {
+    let mut merger = RsMerger::new();
+    merger.encode_output(UTF_16BE).emit_bom(Bom::Utf16Be);
+    merger.pad_with(Pad::Between("¦".as_bytes()));
+    merger.force_ending_newline(Newline::Lf);
+
+    let sources: &[&[u8]] = &[b"a", "é\n".as_bytes()];
+    assert_eq!(
+        merge(&merger, sources).unwrap(),
+        b"\xfe\xff\x00a\x00\n\x00\xa6\x00\xe9\x00\n"
+    );
+
+    // Unmappable characters become numeric character references.
+    let mut merger = RsMerger::new();
+    merger.encode_output(WINDOWS_1252);
+    assert_eq!(
+        merge(&merger, &["é日".as_bytes()]).unwrap(),
+        b"\xe9&#26085;"
+    );
+
+    merger.decode_sources(Decode::All(SHIFT_JIS));
+    merger.encode_output(UTF_8);
+    assert_eq!(merge(&merger, &[b"\x93\xfa"]).unwrap(), "日".as_bytes());
+}
+
+#[test]
+fn encode_output_splits_characters_across_writes() {
+    let mut merger = RsMerger::new();
+    merger.encode_output(UTF_16LE);
+
+    let text = "é".repeat(10_000);
+    let source: &'static [u8] = Box::leak(text.clone().into_bytes().into_boxed_slice());
+    assert_eq!(
+        merge(&merger, &[b"a", source]).unwrap(),
+        utf16le(&format!("a{}", text))
+    );
+}
+
+#[test]
+fn reports_offsets_in_output() {
+    let mut merger = RsMerger::new();
+    merger.decode_sources(Decode::All(WINDOWS_1252));
+    merger.encode_output(UTF_16LE);
+    merger.pad_with(Pad::Custom(Some(b"#"), Some(b"|"), None));
+
+    let mut buf = Vec::new();
+    let sources = vec![Cursor::new(&b"\xe9\xe9"[..]), Cursor::new(&b"x"[..])];
+    let report = merger
+        .merge_sources_into_with_report(sources, &mut buf)
+        .unwrap();
+    assert_eq!(buf, utf16le("#éé|x"));
+    assert_eq!(report.parts()[0].output, 2..6);
+    assert_eq!(report.parts()[1].output, 8..10);
+    assert_eq!(report.padding_len(), 4);
+}
+
+#[test]
+fn plans_are_unsupported() {
+    let mut merger = RsMerger::new();
+    merger.encode_output(UTF_8);
+
+    let mut sources = vec![Cursor::new(&b"a"[..])];
+    assert!(matches!(
+        merger.plan(&mut sources),
+        Err(ErrorKind::Unsupported("plan"))
+    ));
+    assert!(matches!(
+        merger.merged_reader(sources),
+        Err(ErrorKind::Unsupported("merged_reader"))
+    ));
+}
+
+#[test]
+fn columns_apply_to_decoded_text() {
+    let mut merger = RsMerger::new();
+    merger.decode_sources(Decode::Auto(WINDOWS_1252));
+    merger.csv_columns(Columns::Only(&["b", "a"]));
+    let buf = merge(&merger, &[b"a,b\n\xe9,1\n"]).unwrap();
+    assert_eq!(buf, "b,a\n1,é\n".as_bytes());
+
+    // Records are bounded by the buffer limit.
+    merger.buffer_limit(4);
+    assert!(matches!(
+        merge(&merger, &[b"a,b\n12345\n"]),
+        Err(ErrorKind::BufferExceeded(4))
+    ));
+}
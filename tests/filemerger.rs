@@ -1,3 +1,6 @@
+// `TempDir::into_path` is deprecated by recent `tempfile` releases in favour of `keep`.
+#![allow(deprecated)]
+
 use std::io::prelude::*;
 use std::path::Path;
 
@@ -92,6 +95,48 @@ fn with_paths_lossy_accepts_invalid_paths() {
     assert_eq!(&buf, b" 11\n 12\n 13 21\n 22\n 23 31\n 32\n 33");
 }
 
+#[test]
+fn with_paths_checks_every_path_before_writing() {
+    let merger = FileMerger::new();
+    let tempfiles = tempfiles!();
+    let tempdir = tempdir().unwrap();
+
+    let mut buf = Vec::new();
+    let mut paths: Vec<&Path> = tempfiles.iter().map(|f| f.path()).collect();
+    paths.push(tempdir.path());
+    assert!(merger.with_paths(paths, &mut buf).is_err());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn with_paths_iter_basics() {
+    let tempfiles = tempfiles!();
+    let mut merger = FileMerger::new();
+
+    for limit in 0..5 {
+        merger.max_open_files(limit);
+        let mut buf = Vec::new();
+        let paths = tempfiles.iter().map(|f| f.path());
+        assert!(merger.with_paths_iter(paths, &mut buf).is_ok());
+        assert_eq!(&buf, b" 11\n 12\n 13 21\n 22\n 23 31\n 32\n 33");
+    }
+
+    let mut buf = Vec::new();
+    match merger.with_paths_iter(Vec::<&Path>::new(), &mut buf) {
+        Err(e) => assert!(matches!(e, ErrorKind::NothingPassed)),
+        Ok(_) => panic!(),
+    }
+
+    // Invalid paths are only noticed when they are reached.
+    let tempdir = tempdir().unwrap();
+    let paths = tempfiles.iter().map(|f| f.path()).chain(Some(tempdir.path()));
+    let mut buf = Vec::new();
+    match merger.with_paths_iter(paths, &mut buf) {
+        Err(e) => assert!(matches!(e, ErrorKind::InvalidPath(3))),
+        Ok(_) => panic!(),
+    }
+}
+
 #[test]
 fn skip_head_and_skip_tail() {
     let tempfiles = tempfiles!();
@@ -167,3 +212,44 @@ fn options_cooperate_with_each_other() {
         b" leading \n 12\n 13\n inner 22\n 23\n inner 32\n 33\n ending \n"
     );
 }
+
+#[test]
+fn auto_newline_from_first_file() {
+    let mut file1 = NamedTempFile::new().unwrap();
+    let mut file2 = NamedTempFile::new().unwrap();
+    write!(&mut file1, " 11\r\n 12").unwrap();
+    write!(&mut file2, " 21\n 22").unwrap();
+    let paths = vec![file1.path(), file2.path()];
+
+    let mut merger = FileMerger::new();
+    merger.force_ending_newline(NewlineStyle::Auto);
+    let mut buf = Vec::new();
+    let report = merger
+        .with_paths_with_report(paths.clone(), &mut buf)
+        .unwrap();
+    assert_eq!(&buf, b" 11\r\n 12\r\n 21\n 22\r\n");
+    assert_eq!(report.newline(), Some(Newline::Crlf));
+
+    let plan = merger.plan(&paths).unwrap();
+    assert_eq!(plan.len(), buf.len() as u64);
+}
+
+#[test]
+fn buffer_limit_bounds_filtered_lines() {
+    let mut merger = FileMerger::new();
+    merger.filter_lines(LineFilter::DropContaining(b"2"));
+
+    let mut buf = Vec::new();
+    let files = tempfiles!().into_iter().map(|f| f.into_file()).collect();
+    merger.buffer_limit(2);
+    assert!(matches!(
+        merger.with_files(files, &mut buf),
+        Err(ErrorKind::BufferExceeded(2))
+    ));
+
+    let mut buf = Vec::new();
+    let files = tempfiles!().into_iter().map(|f| f.into_file()).collect();
+    merger.buffer_limit(4);
+    merger.with_files(files, &mut buf).unwrap();
+    assert_eq!(&buf, b" 11\n 13 31\n 33");
+}
@@ -0,0 +1,224 @@
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use admerge::*;
+use tempfile::NamedTempFile;
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => 0xedb8_8320 ^ (crc >> 1),
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+// Checks that the index of a merge matches the merged output, for seekable and streaming merges.
+fn check(merger: &RsMerger, sources: &[&[u8]]) -> (Index, Vec<u8>) {
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let report = merger
+        .merge_sources_into_with_report(cursors, &mut buf)
+        .unwrap();
+    let index = report.index().unwrap();
+    assert_eq!(index.entries().len(), sources.len());
+    for entry in index.entries() {
+        let range = entry.offset as usize..(entry.offset + entry.len) as usize;
+        assert_eq!(entry.crc32, crc32(&buf[range]), "{:?}", entry);
+    }
+
+    let mut stream = Vec::new();
+    let report = merger
+        .merge_readers_into_with_report(sources.to_vec(), &mut stream)
+        .unwrap();
+    assert_eq!(buf, stream);
+    assert_eq!(report.index().unwrap(), index);
+
+    (index, buf)
+}
+
+#[test]
+fn index_matches_output() {
+    let sources: &[&[u8]] = &[
+        b"h\n 11\n 12\nf\n",
+        b"h\n 21\n 22\nf",
+        b"",
+        b"h\n 31\r\nf\n",
+    ];
+    let mut merger = RsMerger::new();
+    merger.emit_index(IndexPlacement::Sidecar);
+    let (index, _) = check(&merger, sources);
+    assert_eq!(index.entries()[1].name, "1");
+    assert_eq!(index.entries()[1].offset, 12);
+
+    merger.skip_head(Skip::LinesOnce(1));
+    merger.skip_tail(Skip::LinesOnce(1));
+    check(&merger, sources);
+
+    merger.pad_with(Pad::Custom(Some(b"<"), Some(b"--\n"), Some(b">")));
+    merger.force_ending_newline(Newline::Crlf);
+    let (index, buf) = check(&merger, sources);
+    assert_eq!(&buf[..8], b"<h\n 11\n ");
+    assert_eq!(index.entries()[0].offset, 1);
+
+    merger.normalize_newlines(Newline::Lf);
+    merger.align_parts(16).image_size(96);
+    let (_, buf) = check(&merger, sources);
+    assert_eq!(buf.len(), 96);
+
+    merger.emit_bom(Bom::Utf8);
+    merger.filter_lines(LineFilter::DropContaining(b" 12"));
+    check(&merger, sources);
+}
+
+#[test]
+fn index_without_emit_index() {
+    let merger = RsMerger::new();
+    let cursors = vec![Cursor::new("a"), Cursor::new("b")];
+    let report = merger
+        .merge_sources_into_with_report(cursors, &mut Vec::new())
+        .unwrap();
+    assert!(report.index().is_none());
+    assert!(report.parts().iter().all(|part| part.checksum.is_none()));
+}
+
+#[test]
+fn index_round_trips() {
+    let mut merger = RsMerger::new();
+    merger.emit_index(IndexPlacement::Sidecar);
+    let (index, _) = check(&merger, &[b"abc", b"", b"de\nf"]);
+
+    let mut json = Vec::new();
+    index.write_json(&mut json).unwrap();
+    assert_eq!(Index::from_json(&json).unwrap(), index);
+
+    let mut csv = Vec::new();
+    index.write_csv(&mut csv).unwrap();
+    assert_eq!(Index::from_csv(&csv).unwrap(), index);
+    assert_eq!(
+        std::str::from_utf8(&csv).unwrap(),
+        "name,offset,len,crc32\n0,0,3,891568578\n1,3,0,0\n2,3,4,2233637476\n"
+    );
+
+    // Names that need escaping or quoting.
+    let dir = tempfile::tempdir().unwrap();
+    let names = ["a,b.txt", "quote\"d.txt", "ünï çödé.txt", "new\nline.txt"];
+    let mut paths = Vec::new();
+    for (i, name) in names.iter().enumerate() {
+        let path = dir.path().join(name);
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(file, "file {}", i).unwrap();
+        paths.push(path);
+    }
+    let mut merger = FileMerger::new();
+    merger.emit_index(IndexPlacement::Sidecar);
+    let report = merger
+        .with_paths_with_report(paths.clone(), &mut Vec::new())
+        .unwrap();
+    let index = report.index().unwrap();
+    assert_eq!(Path::new(&index.entries()[2].name), paths[2]);
+
+    let mut json = Vec::new();
+    index.write_json(&mut json).unwrap();
+    assert_eq!(Index::from_json(&json).unwrap(), index);
+    let mut csv = Vec::new();
+    index.write_csv(&mut csv).unwrap();
+    assert_eq!(Index::from_csv(&csv).unwrap(), index);
+}
+
+#[test]
+fn index_footer() {
+    let mut merger = RsMerger::new();
+    merger.emit_index(IndexPlacement::Footer);
+    merger.force_ending_newline(Newline::Lf);
+
+    let sources: &[&[u8]] = &[b"first", b"second\n"];
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let report = merger
+        .merge_sources_into_with_report(cursors, &mut buf)
+        .unwrap();
+    assert_eq!(report.len(), buf.len() as u64);
+    assert!(buf.starts_with(b"first\nsecond\n{"));
+    assert!(buf.ends_with(b"ADMINDEX"));
+
+    let index = Index::read_footer(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(Some(index), report.index());
+
+    let mut stream = Vec::new();
+    merger
+        .merge_readers_into(sources.to_vec(), &mut stream)
+        .unwrap();
+    assert_eq!(buf, stream);
+
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(b"first").unwrap();
+    let mut merger = FileMerger::new();
+    merger.emit_index(IndexPlacement::Footer);
+    let mut out = Vec::new();
+    merger.with_paths(vec![file.path()], &mut out).unwrap();
+    let index = Index::read_footer(&mut Cursor::new(&out)).unwrap();
+    assert_eq!(Path::new(&index.entries()[0].name), file.path());
+
+    // Plans do not know about the footer.
+    let mut merger = RsMerger::new();
+    merger.emit_index(IndexPlacement::Footer);
+    let mut cursors: Vec<_> = sources.iter().map(Cursor::new).collect();
+    assert!(matches!(
+        merger.plan(&mut cursors),
+        Err(ErrorKind::Unsupported("plan"))
+    ));
+}
+
+#[test]
+fn malformed_indices() {
+    let invalid = |result: Result<Index>| match result {
+        Err(ErrorKind::InvalidIndex(at)) => at,
+        other => panic!("{:?}", other),
+    };
+
+    assert_eq!(invalid(Index::from_json(b"{\"entries\": [}")), 13);
+    assert_eq!(invalid(Index::from_json(b"[]")), 0);
+    assert_eq!(
+        invalid(Index::from_json(
+            br#"{"entries":[{"name":"a","offset":1}]}"#
+        )),
+        0
+    );
+    assert_eq!(
+        invalid(Index::from_json(
+            br#"{"entries":[{"name":"a","offset":1,"len":1,"crc32":4294967296}]}"#
+        )),
+        0
+    );
+    assert!(Index::from_json(br#" {"version": 1, "entries": [] } "#).is_ok());
+
+    assert_eq!(invalid(Index::from_csv(b"name,offset\n")), 0);
+    assert_eq!(
+        invalid(Index::from_csv(
+            b"name,offset,len,crc32\na,1,2,3\nb,1,x,3\n"
+        )),
+        30
+    );
+    assert_eq!(
+        invalid(Index::from_csv(b"name,offset,len,crc32\n\"a,1,2,3\n")),
+        22
+    );
+
+    assert_eq!(invalid(Index::read_footer(&mut Cursor::new(b"short"))), 0);
+    assert_eq!(
+        invalid(Index::read_footer(&mut Cursor::new(
+            b"0123456789abcdefADMINDEY"
+        ))),
+        16
+    );
+    let mut footer = b"{}".to_vec();
+    footer.extend_from_slice(&100u64.to_le_bytes());
+    footer.extend_from_slice(b"ADMINDEX");
+    assert_eq!(invalid(Index::read_footer(&mut Cursor::new(footer))), 2);
+}
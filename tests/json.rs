@@ -0,0 +1,130 @@
+use std::fs;
+
+use admerge::*;
+
+fn merge(merger: &JsonMerger, sources: &[&str]) -> Result<String> {
+    let mut buf = Vec::new();
+    let readers = sources.iter().map(|s| s.as_bytes()).collect();
+    let report = merger.merge_readers_into_with_report(readers, &mut buf)?;
+    assert_eq!(report.len(), buf.len() as u64);
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+fn invalid(result: Result<String>) -> (usize, u64) {
+    match result {
+        Err(ErrorKind::InvalidJson(index, offset)) => (index, offset),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn json_arrays() {
+    let merger = JsonMerger::new();
+    let buf = merge(&merger, &["[1,2]", "[3]"]).unwrap();
+    assert_eq!(buf, "[1,2,3]");
+
+    // Whitespace between elements is dropped, but kept inside them.
+    let buf = merge(
+        &merger,
+        &["\r\n [ {\"a\" : [1, \"],\"]} ,\n\"x\\\"]\" ]\n", "[ null ]"],
+    )
+    .unwrap();
+    assert_eq!(buf, "[{\"a\" : [1, \"],\"]},\"x\\\"]\",null]");
+
+    // Empty arrays.
+    let buf = merge(&merger, &["[]", "[ ]", "[1]", "[\n]"]).unwrap();
+    assert_eq!(buf, "[1]");
+    let buf = merge(&merger, &["[]", " []"]).unwrap();
+    assert_eq!(buf, "[]");
+
+    let mut buf = Vec::new();
+    let result = merger.merge_readers_into(Vec::<&[u8]>::new(), &mut buf);
+    assert!(matches!(result, Err(ErrorKind::NothingPassed)));
+}
+
+#[test]
+fn json_errors() {
+    let merger = JsonMerger::new();
+    assert_eq!(invalid(merge(&merger, &["[1]", "{}"])), (1, 0));
+    assert_eq!(invalid(merge(&merger, &["[1]", ""])), (1, 0));
+    assert_eq!(invalid(merge(&merger, &["[1,]"])), (0, 3));
+    assert_eq!(invalid(merge(&merger, &["[1 2]"])), (0, 3));
+    assert_eq!(invalid(merge(&merger, &["[1]", "[1, {\"a\" 1}]"])), (1, 9));
+    assert_eq!(invalid(merge(&merger, &["[1] [2]"])), (0, 4));
+    assert_eq!(invalid(merge(&merger, &["[1, 2"])), (0, 5));
+
+    // A string that is never closed runs to the end of the source.
+    assert_eq!(invalid(merge(&merger, &["[\"abc]"])), (0, 6));
+
+    // Elements are bounded by the buffer limit.
+    let mut merger = JsonMerger::new();
+    merger.buffer_limit(4);
+    merge(&merger, &["[1234, \"ab\"]"]).unwrap();
+    let result = merge(&merger, &["[12345]"]);
+    assert!(matches!(result, Err(ErrorKind::BufferExceeded(4))));
+}
+
+#[test]
+fn json_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut paths = Vec::new();
+    for (i, contents) in ["[1]\n", "[]\n", "[{}]\n"].iter().enumerate() {
+        let path = dir.path().join(format!("{}.json", i));
+        fs::write(&path, contents).unwrap();
+        paths.push(path);
+    }
+
+    let mut buf = Vec::new();
+    let report = JsonMerger::new()
+        .with_paths_with_report(paths.clone(), &mut buf)
+        .unwrap();
+    assert_eq!(buf, b"[1,{}]");
+    assert_eq!(report.values(), 2);
+
+    // Every path is checked before anything is written.
+    paths.insert(1, dir.path().to_path_buf());
+    let mut buf = Vec::new();
+    let result = JsonMerger::new().with_paths(paths, &mut buf);
+    assert!(matches!(result, Err(ErrorKind::InvalidPath(1))));
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn json_lines() {
+    let mut merger = JsonMerger::new();
+    merger.format(JsonFormat::Lines);
+    let buf = merge(
+        &merger,
+        &["{\"a\":1}\n\n  \r\n[2, 3] \r\n", "\"x\"", "", "null\n\n"],
+    )
+    .unwrap();
+    assert_eq!(buf, "{\"a\":1}\n[2, 3]\n\"x\"\nnull\n");
+
+    // Invalid records fail the merge, with their line numbers.
+    let result = merge(&merger, &["1\n", "2\n\n3 4\n5\n"]);
+    assert!(matches!(result, Err(ErrorKind::InvalidRecord(1, 3))));
+    let result = merge(&merger, &["{\"a\":\n1}\n"]);
+    assert!(matches!(result, Err(ErrorKind::InvalidRecord(0, 1))));
+
+    // Or are collected once lenient.
+    merger.lenient(true);
+    let mut buf = Vec::new();
+    let sources: Vec<&[u8]> = vec![b"1\n]\n2\n", b"3 4", b"5,\n"];
+    let report = merger
+        .merge_readers_into_with_report(sources, &mut buf)
+        .unwrap();
+    assert_eq!(buf, b"1\n2\n");
+    assert_eq!(report.values(), 2);
+    let rejected: Vec<_> = report
+        .rejected()
+        .iter()
+        .map(|r| (r.index, r.line))
+        .collect();
+    assert_eq!(rejected, [(0, 2), (1, 1), (2, 1)]);
+
+    // Lines are bounded by the buffer limit, terminators included.
+    merger.buffer_limit(4);
+    merge(&merger, &["123\n"]).unwrap();
+    let result = merge(&merger, &["1234\n"]);
+    assert!(matches!(result, Err(ErrorKind::BufferExceeded(4))));
+}
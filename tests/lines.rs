@@ -0,0 +1,160 @@
+use std::io::{Cursor, Read};
+
+use admerge::*;
+
+mod common;
+
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<Vec<u8>> {
+    common::merge(merger, sources, &[ErrorKind::InvalidSkip])
+}
+
+#[test]
+fn filter_lines_by_literal() {
+    let mut merger = RsMerger::new();
+    merger.filter_lines(LineFilter::DropContaining(b"DEBUG"));
+
+    let sources: &[&[u8]] = &[b"a\nDEBUG b\r\nc\r\n", b"DEBUG d\ne\nDEBUG f"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\nc\r\ne\n");
+
+    merger.filter_lines(LineFilter::KeepContaining(b"DEBUG"));
+    assert_eq!(
+        merge(&merger, sources).unwrap(),
+        b"DEBUG b\r\nDEBUG d\nDEBUG f"
+    );
+}
+
+#[test]
+fn filter_lines_by_predicate() {
+    let blank = |line: &[u8]| line.iter().all(u8::is_ascii_whitespace);
+    let mut merger = RsMerger::new();
+    merger.filter_lines(LineFilter::DropIf(&blank));
+
+    let sources: &[&[u8]] = &[b"\n a\n\r\n  \n b\n", b"\n", b"", b" c\n\n"];
+    assert_eq!(merge(&merger, sources).unwrap(), b" a\n b\n c\n");
+
+    let comment = |line: &[u8]| !line.starts_with(b"#");
+    merger.filter_lines(LineFilter::KeepIf(&comment));
+    assert_eq!(merge(&merger, &[b"# x\n1\n#y\n2"]).unwrap(), b"1\n2");
+}
+
+#[test]
+fn filter_lines_by_terminator() {
+    let empty = |line: &[u8]| line.is_empty();
+    let mut merger = RsMerger::new();
+    merger.filter_lines(LineFilter::DropIf(&empty));
+
+    // Lines end with the configured terminators, which are left out of the tested lines.
+    merger.line_terminator(Terminator::Cr);
+    let sources: &[&[u8]] = &[b"a\r\rb\nc\r\r", b"\rd"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\rb\nc\rd");
+
+    merger.line_terminator(Terminator::Any);
+    let sources: &[&[u8]] = &[b"a\r\n\r\nb\r\rc\n\n", b"\r"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\r\nb\rc\n");
+
+    merger.line_terminator(Terminator::Delimiter(b"\0"));
+    let sources: &[&[u8]] = &[b"a\0\0b\n\0", b"\0c"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\0b\n\0c");
+}
+
+#[test]
+fn filter_lines_after_skips() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::LinesOnce(1));
+    merger.skip_tail(Skip::Until(b"-- end"));
+    merger.filter_lines(LineFilter::DropContaining(b"#"));
+
+    let sources: &[&[u8]] = &[b"# header\n1\n# 2\n-- end\n", b"# header\n3\n#4\n5\n-- end"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"1\n3\n5\n");
+
+    // Lines are split from where the head skip stopped.
+    merger.skip_head(Skip::Bytes(3));
+    assert_eq!(merge(&merger, &[b"#  #\n1\n-- end"]).unwrap(), b"1\n");
+}
+
+#[test]
+fn filter_lines_before_ending_newlines() {
+    let mut merger = RsMerger::new();
+    merger.filter_lines(LineFilter::DropContaining(b"x"));
+    merger.force_ending_newline(Newline::Crlf);
+
+    // The last kept line decides whether a newline is forced.
+    let sources: &[&[u8]] = &[b"a\nx", b"b\nc", b"x\n", b""];
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\nb\nc\r\n\r\n\r\n");
+
+    let mut merger = RsMerger::new();
+    merger.force_ending_newline(Newline::Lf);
+    merger.skip_tail(Skip::Bytes(1));
+    merger.filter_lines(LineFilter::KeepIf(&|_| true));
+    assert_eq!(merge(&merger, &[b"a\nb\n"]).unwrap(), b"a\nb\n");
+}
+
+#[test]
+fn filter_lines_reports_dropped_lines() {
+    let mut merger = RsMerger::new();
+    merger.filter_lines(LineFilter::DropContaining(b"x"));
+    merger.pad_with(Pad::Between(b"|"));
+
+    let mut buf = Vec::new();
+    let sources = vec![Cursor::new(&b"x\na\nx\n"[..]), Cursor::new(&b"b\nx"[..])];
+    let report = merger
+        .merge_sources_into_with_report(sources, &mut buf)
+        .unwrap();
+    assert_eq!(buf, b"a\n|b\n");
+    assert_eq!(report.parts()[0].lines_dropped, 2);
+    assert_eq!(report.parts()[0].output, 0..2);
+    assert_eq!(report.parts()[1].lines_dropped, 1);
+    assert_eq!(report.parts()[1].output, 3..5);
+}
+
+#[test]
+fn filter_lines_in_plans_and_readers() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Bytes(1));
+    merger.filter_lines(LineFilter::DropContaining(b"x"));
+    merger.force_ending_newline(Newline::Lf);
+
+    let sources = || {
+        vec![
+            Cursor::new(&b"_a\nb\nx\nc\nx"[..]),
+            Cursor::new(&b"_x\n"[..]),
+        ]
+    };
+    let plan = merger.plan(&mut sources()).unwrap();
+    assert_eq!(plan.parts()[0].runs, vec![1..5, 7..9]);
+    assert_eq!(plan.parts()[0].output, 0..6);
+    assert_eq!(plan.parts()[0].newline, None);
+    assert!(plan.parts()[1].runs.is_empty());
+    assert_eq!(plan.parts()[1].newline, Some(&b"\n"[..]));
+
+    let mut expected = Vec::new();
+    merger.merge_sources_into(sources(), &mut expected).unwrap();
+    assert_eq!(expected, b"a\nb\nc\n\n");
+    assert_eq!(plan.len(), expected.len() as u64);
+
+    let mut buf = Vec::new();
+    let mut reader = merger.merged_reader(sources()).unwrap();
+    reader.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, expected);
+}
+
+#[test]
+fn filter_lines_respect_buffer_limit() {
+    let mut merger = RsMerger::new();
+    merger.filter_lines(LineFilter::DropContaining(b"x"));
+    merger.buffer_limit(4);
+
+    let mut buf = Vec::new();
+    let sources = vec![Cursor::new(&b"abc\nabcdef\n"[..])];
+    assert!(matches!(
+        merger.merge_sources_into(sources, &mut buf),
+        Err(ErrorKind::BufferExceeded(4))
+    ));
+
+    let mut buf = Vec::new();
+    let sources: Vec<&[u8]> = vec![b"abcdef\n"];
+    assert!(matches!(
+        merger.merge_readers_into(sources, &mut buf),
+        Err(ErrorKind::BufferExceeded(4))
+    ));
+}
@@ -0,0 +1,182 @@
+use std::io::{Cursor, Read};
+
+use admerge::*;
+
+mod common;
+
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<Vec<u8>> {
+    common::merge(merger, sources, &[ErrorKind::InvalidSkip])
+}
+
+// A reader that hands out one byte per read.
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match (self.0.split_first(), buf.is_empty()) {
+            (Some((&b, rest)), false) => {
+                buf[0] = b;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn normalize_source_newlines() {
+    let mut merger = RsMerger::new();
+    merger.normalize_newlines(Newline::Lf);
+
+    let sources: &[&[u8]] = &[b"a\r\nb\rc\n", b"\r\r\n\n", b"d\r", b"", b"e"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\nb\nc\n\n\n\nd\ne");
+
+    merger.normalize_newlines(Newline::Crlf);
+    assert_eq!(
+        merge(&merger, sources).unwrap(),
+        b"a\r\nb\r\nc\r\n\r\n\r\n\r\nd\r\ne"
+    );
+}
+
+#[test]
+fn normalize_paddings_and_ending_newlines() {
+    let mut merger = RsMerger::new();
+    merger.normalize_newlines(Newline::Crlf);
+    merger.pad_with(Pad::Custom(Some(b"h\n"), Some(b"\r--\n"), Some(b"\r")));
+    merger.force_ending_newline(Newline::Lf);
+
+    let sources: &[&[u8]] = &[b"1", b"2\r"];
+    assert_eq!(
+        merge(&merger, sources).unwrap(),
+        b"h\r\n1\r\n\r\n--\r\n2\r\n\r\n"
+    );
+}
+
+#[test]
+fn skips_and_filters_see_normalized_contents() {
+    let mut merger = RsMerger::new();
+    merger.normalize_newlines(Newline::Lf);
+    merger.skip_head(Skip::Lines(1));
+    merger.skip_tail(Skip::Until(b"x\n"));
+    merger.filter_lines(LineFilter::DropIf(&|line: &[u8]| line.is_empty()));
+
+    let sources: &[&[u8]] = &[b"h\r1\r\r\n2\rx\r\n", b"h\r\n3\r\nx\r"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"1\n2\n3\n");
+}
+
+#[test]
+fn normalize_across_reads() {
+    let mut merger = RsMerger::new();
+    merger.normalize_newlines(Newline::Lf);
+    merger.force_ending_newline(Newline::Crlf);
+
+    let mut buf = Vec::new();
+    let sources = vec![Trickle(b"a\r\nb\r\r\n"), Trickle(b"c\r")];
+    merger.merge_readers_into(sources, &mut buf).unwrap();
+    assert_eq!(buf, b"a\nb\n\nc\n");
+
+    // A `\r\n` may also straddle the internal buffers.
+    let mut source = vec![b'a'; 8 * 1024 - 1];
+    source.extend_from_slice(b"\r\nb");
+    let mut buf = Vec::new();
+    merger
+        .merge_readers_into(vec![&source[..]], &mut buf)
+        .unwrap();
+    assert_eq!(buf.len(), source.len());
+    assert!(buf.ends_with(b"a\nb\n"));
+}
+
+#[test]
+fn report_normalized_offsets() {
+    let mut merger = RsMerger::new();
+    merger.normalize_newlines(Newline::Crlf);
+    merger.pad_with(Pad::Between(b"\n"));
+
+    let mut buf = Vec::new();
+    let sources = vec![Cursor::new(&b"a\nb"[..]), Cursor::new(&b"c\n"[..])];
+    let report = merger
+        .merge_sources_into_with_report(sources, &mut buf)
+        .unwrap();
+    assert_eq!(buf, b"a\r\nb\r\nc\r\n");
+    assert_eq!(report.parts()[0].output, 0..4);
+    assert_eq!(report.parts()[1].output, 6..9);
+    assert_eq!(report.padding_len(), 2);
+
+    let mut sources = vec![Cursor::new(&b"a"[..])];
+    assert!(matches!(
+        merger.plan(&mut sources),
+        Err(ErrorKind::Unsupported("plan"))
+    ));
+}
+
+#[test]
+fn auto_newline_follows_first_source() {
+    let mut merger = RsMerger::new();
+    merger.force_ending_newline(NewlineStyle::Auto);
+
+    let sources: &[&[u8]] = &[b"a\r\nb\r\nc\n", b"d", b"e\n"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\r\nb\r\nc\nd\r\ne\n");
+
+    let sources: &[&[u8]] = &[b"a\r\nb\nc\n", b"d\r\n", b"e"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\r\nb\nc\nd\r\ne\n");
+
+    // Falls back to `LF` if the first source has no line endings.
+    let sources: &[&[u8]] = &[b"a", b"b\r\n"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\nb\r\n");
+
+    // Fixed styles are given as they are.
+    merger.force_ending_newline(NewlineStyle::Fixed(Newline::Crlf));
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\r\nb\r\n");
+}
+
+#[test]
+fn auto_newline_normalizes() {
+    let mut merger = RsMerger::new();
+    merger.normalize_newlines(NewlineStyle::Auto);
+    merger.pad_with(Pad::Between(b"--\n"));
+
+    let sources: &[&[u8]] = &[b"a\r\nb\r\n", b"c\nd\r"];
+    assert_eq!(
+        merge(&merger, sources).unwrap(),
+        b"a\r\nb\r\n--\r\nc\r\nd\r\n"
+    );
+
+    // UTF-16 line endings are detected as well.
+    let mut merger = RsMerger::new();
+    merger.force_ending_newline(NewlineStyle::Auto);
+    let utf16: &'static [u8] = b"\xff\xfea\0\r\0\n\0b\0";
+    assert_eq!(
+        merge(&merger, &[utf16]).unwrap(),
+        b"\xff\xfea\0\r\0\n\0b\0\r\n"
+    );
+}
+
+#[test]
+fn report_detected_newline() {
+    let mut buf = Vec::new();
+    let sources = || vec![Cursor::new(&b"a\r\n"[..]), Cursor::new(&b"b"[..])];
+    let report = RsMerger::new()
+        .merge_sources_into_with_report(sources(), &mut buf)
+        .unwrap();
+    assert_eq!(report.newline(), None);
+
+    let mut merger = RsMerger::new();
+    merger.force_ending_newline(NewlineStyle::Auto);
+    let mut buf = Vec::new();
+    let report = merger
+        .merge_sources_into_with_report(sources(), &mut buf)
+        .unwrap();
+    assert_eq!(buf, b"a\r\nb\r\n");
+    assert_eq!(report.newline(), Some(Newline::Crlf));
+
+    // Plans resolve the style alike.
+    let plan = merger.plan(&mut sources()).unwrap();
+    assert_eq!(plan.parts()[1].newline, Some(&b"\r\n"[..]));
+    assert_eq!(plan.len(), buf.len() as u64);
+
+    let mut buf = Vec::new();
+    let mut reader = merger.merged_reader(sources()).unwrap();
+    reader.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, b"a\r\nb\r\n");
+}
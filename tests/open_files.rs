@@ -0,0 +1,52 @@
+//! Kept in its own test binary, as it counts the file descriptors of the whole process.
+#![cfg(target_os = "linux")]
+
+use std::cell::Cell;
+use std::fs;
+use std::io::{self, Write};
+
+use admerge::*;
+use tempfile::tempdir;
+
+// A writer that records the largest number of open file descriptors seen while writing.
+struct FdProbe<'a>(&'a Cell<usize>);
+
+impl<'a> Write for FdProbe<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.set(self.0.get().max(open_fds()));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn open_fds() -> usize {
+    fs::read_dir("/proc/self/fd").unwrap().count()
+}
+
+#[test]
+fn with_paths_keeps_at_most_max_open_files_open() {
+    let dir = tempdir().unwrap();
+    let paths: Vec<_> = (0..64)
+        .map(|i| {
+            let path = dir.path().join(format!("{}.txt", i));
+            fs::write(&path, format!("line {}\n", i)).unwrap();
+            path
+        })
+        .collect();
+
+    let mut merger = FileMerger::new();
+    for limit in [1, 4] {
+        merger.max_open_files(limit);
+        let baseline = open_fds();
+        let peak = Cell::new(0);
+        assert!(merger.with_paths(paths.clone(), &mut FdProbe(&peak)).is_ok());
+        assert!(peak.get() <= baseline + limit, "{} > {} + {}", peak.get(), baseline, limit);
+
+        let peak = Cell::new(0);
+        assert!(merger.with_paths_iter(&paths, &mut FdProbe(&peak)).is_ok());
+        assert!(peak.get() <= baseline + limit, "{} > {} + {}", peak.get(), baseline, limit);
+    }
+}
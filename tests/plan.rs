@@ -0,0 +1,95 @@
+use std::io::prelude::*;
+use std::io::Cursor;
+
+use admerge::*;
+use tempfile::NamedTempFile;
+
+fn sources() -> Vec<Cursor<&'static [u8]>> {
+    vec![
+        Cursor::new(&b"header\n 11\n 12\nfooter"[..]),
+        Cursor::new(&b"header\n 21\nfooter\n"[..]),
+        Cursor::new(&b"header\nfooter"[..]),
+    ]
+}
+
+#[test]
+fn plan_matches_merge_sources() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::LinesOnce(1));
+    merger.skip_tail(Skip::Until(b"footer"));
+    merger.pad_with(Pad::Custom(Some(b"<"), Some(b"|"), Some(b">")));
+    merger.force_ending_newline(Newline::Crlf);
+
+    let mut sources = sources();
+    let plan = merger.plan(&mut sources).unwrap();
+
+    // Planning leaves the sources usable.
+    let mut buf = Vec::new();
+    merger.merge_sources_into(sources, &mut buf).unwrap();
+    assert_eq!(plan.len(), buf.len() as u64);
+
+    let parts = plan.parts();
+    assert_eq!(parts.len(), 3);
+    for (i, part) in parts.iter().enumerate() {
+        assert_eq!(part.index, i);
+        assert_eq!(part.head_skipped.end, part.kept.start);
+        assert_eq!(part.kept.end, part.tail_skipped.start);
+        assert_eq!(part.tail_skipped.end, part.source_len);
+        assert_eq!(
+            part.output.end - part.output.start,
+            part.kept.end - part.kept.start
+        );
+    }
+
+    assert_eq!(parts[0].head_skipped, 0..0);
+    assert_eq!(parts[0].padding_before, Some(&b"<"[..]));
+    assert_eq!(
+        &buf[parts[0].output.start as usize..parts[0].output.end as usize],
+        b"header\n 11\n 12\n"
+    );
+    assert_eq!(parts[1].kept, 7..11);
+    assert_eq!(parts[1].newline, None);
+    assert_eq!(parts[1].padding_after, Some(&b"|"[..]));
+    assert_eq!(
+        &buf[parts[1].output.start as usize..parts[1].output.end as usize],
+        b" 21\n"
+    );
+    assert_eq!(parts[2].kept, 7..7);
+    assert_eq!(parts[2].newline, Some(&b"\r\n"[..]));
+    assert_eq!(parts[2].padding_after, Some(&b">"[..]));
+}
+
+#[test]
+fn plan_throws() {
+    let mut merger = RsMerger::new();
+    match merger.plan(&mut Vec::<Cursor<&[u8]>>::new()) {
+        Err(e) => assert!(matches!(e, ErrorKind::NothingPassed)),
+        Ok(_) => panic!(),
+    }
+
+    merger.skip_head(Skip::Lines(5));
+    match merger.plan(&mut sources()) {
+        Err(e) => assert!(matches!(e, ErrorKind::InvalidSkip)),
+        Ok(_) => panic!(),
+    }
+}
+
+#[test]
+fn file_plan_basics() {
+    let mut file1 = NamedTempFile::new().unwrap();
+    let mut file2 = NamedTempFile::new().unwrap();
+    write!(&mut file1, " 11\n 12").unwrap();
+    write!(&mut file2, " 21\n 22\n").unwrap();
+
+    let mut merger = FileMerger::new();
+    merger.force_ending_newline(Newline::Lf);
+    let plan = merger.plan(&[file1.path(), file2.path()]).unwrap();
+    assert_eq!(plan.len(), 16);
+    assert_eq!(plan.parts()[0].newline, Some(&b"\n"[..]));
+    assert_eq!(plan.parts()[1].output, 8..16);
+
+    match merger.plan(&[file1.path(), std::path::Path::new("")]) {
+        Err(e) => assert!(matches!(e, ErrorKind::InvalidPath(1))),
+        Ok(_) => panic!(),
+    }
+}
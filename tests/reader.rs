@@ -0,0 +1,83 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use admerge::*;
+
+fn sources() -> Vec<Cursor<Vec<u8>>> {
+    // Large enough that kept ranges span multiple reads.
+    let big = (0..20_000).map(|i| format!(" {}\n", i)).collect::<String>();
+    vec![
+        Cursor::new(format!("header\n{}footer", big).into_bytes()),
+        Cursor::new(b"header\n 1\n 2\nfooter\n".to_vec()),
+        Cursor::new(b"header\nfooter".to_vec()),
+    ]
+}
+
+fn merger() -> RsMerger<'static> {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::LinesOnce(1));
+    merger.skip_tail(Skip::Until(b"footer"));
+    merger.pad_with(Pad::Custom(Some(b"<<\n"), Some(b"--\n"), Some(b">>\n")));
+    merger.force_ending_newline(Newline::Crlf);
+    merger
+}
+
+fn expected() -> Vec<u8> {
+    let mut buf = Vec::new();
+    merger().merge_sources_into(sources(), &mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn merged_reader_matches_merge_sources() {
+    let expected = expected();
+    let mut reader = merger().merged_reader(sources()).unwrap();
+    assert_eq!(reader.len(), expected.len() as u64);
+
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, expected);
+
+    // Reading past the end yields nothing.
+    assert_eq!(reader.read(&mut [0; 8]).unwrap(), 0);
+}
+
+#[test]
+fn merged_reader_seeks() {
+    let expected = expected();
+    let len = expected.len() as u64;
+    let mut reader = merger().merged_reader(sources()).unwrap();
+
+    for &at in &[0, 1, 2, 3, 4, 100, 50_000, len - 30, len - 3, len - 1, len] {
+        assert_eq!(reader.seek(SeekFrom::Start(at)).unwrap(), at);
+        let mut buf = vec![0; 17];
+        let n = reader.read(&mut buf).unwrap();
+        let at = at as usize;
+        assert!(n > 0 || at == expected.len());
+        assert_eq!(&buf[..n], &expected[at..at + n]);
+    }
+
+    assert_eq!(reader.seek(SeekFrom::End(-5)).unwrap(), len - 5);
+    assert_eq!(reader.seek(SeekFrom::Current(-10)).unwrap(), len - 15);
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, &expected[expected.len() - 15..]);
+
+    assert!(reader.seek(SeekFrom::Current(-(len as i64) - 1)).is_err());
+    assert_eq!(reader.seek(SeekFrom::Start(len + 10)).unwrap(), len + 10);
+    assert_eq!(reader.read(&mut [0; 8]).unwrap(), 0);
+}
+
+#[test]
+fn merged_reader_throws() {
+    match RsMerger::new().merged_reader(Vec::<Cursor<Vec<u8>>>::new()) {
+        Err(e) => assert!(matches!(e, ErrorKind::NothingPassed)),
+        Ok(_) => panic!(),
+    }
+
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Lines(3));
+    match merger.merged_reader(vec![Cursor::new(b"1\n2\n")]) {
+        Err(e) => assert!(matches!(e, ErrorKind::InvalidSkip)),
+        Ok(_) => panic!(),
+    }
+}
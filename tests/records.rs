@@ -0,0 +1,75 @@
+use std::io::Cursor;
+
+use admerge::*;
+
+mod common;
+
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<Vec<u8>> {
+    common::merge(
+        merger,
+        sources,
+        &[ErrorKind::InvalidSkip, ErrorKind::PartialRecord(0)],
+    )
+}
+
+#[test]
+fn skip_records() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Records(1, 4));
+    merger.skip_tail(Skip::Records(2, 2));
+
+    let sources: &[&[u8]] = &[b"head1234tatb", b"head5678tatb"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"12345678");
+
+    merger.skip_head(Skip::RecordsOnce(1, 4));
+    merger.skip_tail(Skip::RecordsOnce(1, 4));
+    assert_eq!(merge(&merger, sources).unwrap(), b"head12345678tatb");
+
+    merger.skip_head(Skip::Chain(&[Skip::Bom, Skip::Records(1, 2)]));
+    merger.skip_tail(Skip::Records(0, 4));
+    let sources: &[&[u8]] = &[b"\xef\xbb\xbfhd12", b"hd34"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"1234");
+
+    // Skips that run past the end of a source are not valid.
+    merger.skip_head(Skip::Records(3, 2));
+    assert!(matches!(
+        merge(&merger, sources),
+        Err(ErrorKind::InvalidSkip)
+    ));
+    merger.skip_head(Skip::Records(0, 2));
+    merger.skip_tail(Skip::Records(usize::MAX, 2));
+    assert!(matches!(
+        merge(&merger, sources),
+        Err(ErrorKind::InvalidSkip)
+    ));
+}
+
+#[test]
+fn check_whole_records() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Records(1, 3));
+    merger.check_records(2);
+
+    let sources: &[&[u8]] = &[b"hdr1122", b"hdr", b"hdr3344"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"11223344");
+
+    // Paddings and ending newlines are not part of the records.
+    merger.pad_with(Pad::Between(b"-"));
+    merger.force_ending_newline(Newline::Lf);
+    assert_eq!(merge(&merger, sources).unwrap(), b"1122\n-\n-3344\n");
+
+    let sources: &[&[u8]] = &[b"hdr1122", b"hdr334", b"hdr"];
+    assert!(matches!(
+        merge(&merger, sources),
+        Err(ErrorKind::PartialRecord(1))
+    ));
+    let mut cursors: Vec<_> = sources.iter().map(Cursor::new).collect();
+    assert!(matches!(
+        merger.plan(&mut cursors),
+        Err(ErrorKind::PartialRecord(1))
+    ));
+
+    // A size of zero disables the check.
+    merger.check_records(0);
+    assert!(merge(&merger, sources).is_ok());
+}
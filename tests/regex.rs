@@ -0,0 +1,95 @@
+#![cfg(feature = "regex")]
+
+use admerge::*;
+
+mod common;
+
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<Vec<u8>> {
+    common::merge(
+        merger,
+        sources,
+        &[ErrorKind::InvalidSkip, ErrorKind::BufferExceeded(0)],
+    )
+}
+
+#[test]
+fn skip_head_until_match() {
+    let re = Regex::new(r"^-- Generated at \d{4}-\d{2}-\d{2}$").unwrap();
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::UntilMatch(&re));
+
+    let sources: &[&[u8]] = &[
+        b"-- Generated at 2024-01-01\n 11\n 12\n",
+        b"preamble\n-- Generated at 2024-02-01\n 21\n",
+        b"\xff\xfe no match\n",
+    ];
+    assert_eq!(merge(&merger, sources).unwrap(), b"\n 11\n 12\n\n 21\n");
+
+    merger.skip_head(Skip::BeforeMatch(&re));
+    assert_eq!(
+        merge(&merger, sources).unwrap(),
+        b"-- Generated at 2024-01-01\n 11\n 12\n-- Generated at 2024-02-01\n 21\n"
+    );
+
+    // Searched lines are bounded by the buffer limit.
+    merger.buffer_limit(8);
+    assert_eq!(merge(&merger, &[b"1234567\nx"]).unwrap(), b"");
+    assert!(matches!(
+        merge(&merger, &[b"123456789\nx"]),
+        Err(ErrorKind::BufferExceeded(8))
+    ));
+}
+
+#[test]
+fn skip_tail_until_match() {
+    let re = Regex::new(r"total: \d+").unwrap();
+    let mut merger = RsMerger::new();
+    merger.skip_tail(Skip::UntilMatch(&re));
+
+    let sources: &[&[u8]] = &[
+        b" 11\ntotal: 1\n 12\ntotal: 2\n",
+        b" 21\ntotal: 3",
+        b" 31\n",
+    ];
+    assert_eq!(
+        merge(&merger, sources).unwrap(),
+        b" 11\ntotal: 1\n 12\n 21\n"
+    );
+
+    merger.skip_tail(Skip::BeforeMatch(&re));
+    assert_eq!(
+        merge(&merger, sources).unwrap(),
+        b" 11\ntotal: 1\n 12\ntotal: 2 21\ntotal: 3"
+    );
+}
+
+#[test]
+fn regex_matches_never_span_lines() {
+    let re = Regex::new(r"a\s+b").unwrap();
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::UntilMatch(&re));
+    merger.skip_tail(Skip::BeforeMatch(&re));
+
+    assert_eq!(merge(&merger, &[b"a\nb a  b|a b\n"]).unwrap(), b"|a b");
+
+    // The tail skip starts before the head skip ends.
+    merger.skip_tail(Skip::UntilMatch(&re));
+    assert!(matches!(
+        merge(&merger, &[b"xa b"]),
+        Err(ErrorKind::InvalidSkip)
+    ));
+}
+
+#[test]
+fn filter_lines_by_regex() {
+    let re = Regex::new(r"^\s*(#|$)").unwrap();
+    let mut merger = RsMerger::new();
+    merger.filter_lines(LineFilter::DropMatching(&re));
+
+    let sources: &[&[u8]] = &[b"# a\n1\r\n\r\n  # b\n2", b"\n3\n"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"1\r\n23\n");
+
+    let re = Regex::new(r"^\d$").unwrap();
+    merger.filter_lines(LineFilter::KeepMatching(&re));
+    assert_eq!(merge(&merger, sources).unwrap(), b"1\r\n23\n");
+}
@@ -0,0 +1,87 @@
+use std::io::prelude::*;
+use std::io::Cursor;
+use std::path::Path;
+
+use admerge::*;
+use tempfile::NamedTempFile;
+
+fn merger() -> RsMerger<'static> {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::LinesOnce(1));
+    merger.skip_tail(Skip::Until(b"footer"));
+    merger.pad_with(Pad::Custom(Some(b"<"), Some(b"|"), Some(b">")));
+    merger.force_ending_newline(Newline::Crlf);
+    merger
+}
+
+fn sources() -> Vec<&'static [u8]> {
+    vec![
+        b"header\n 11\n 12\nfooter",
+        b"header\n 21\nfooter\n",
+        b"header\nfooter",
+    ]
+}
+
+#[test]
+fn merge_report_basics() {
+    let merger = merger();
+    let mut buf = Vec::new();
+    let cursors = sources().into_iter().map(Cursor::new).collect();
+    let report = merger
+        .merge_sources_into_with_report(cursors, &mut buf)
+        .unwrap();
+    assert_eq!(report.len(), buf.len() as u64);
+    assert_eq!(report.padding_len(), 4);
+
+    let parts = report.parts();
+    assert_eq!(parts.len(), 3);
+    assert_eq!(parts[0].path, None);
+    assert_eq!((parts[0].head_skipped, parts[0].tail_skipped), (0, 6));
+    assert_eq!((parts[1].head_skipped, parts[1].tail_skipped), (7, 7));
+    assert_eq!((parts[2].head_skipped, parts[2].tail_skipped), (7, 6));
+    assert_eq!(parts[1].source_len, 18);
+    assert!(!parts[1].newline);
+    assert!(parts[2].newline);
+
+    let slice = |i: usize| &buf[parts[i].output.start as usize..parts[i].output.end as usize];
+    assert_eq!(slice(0), b"header\n 11\n 12\n");
+    assert_eq!(slice(1), b" 21\n");
+    assert_eq!(slice(2), b"");
+
+    // Streaming merges report the same.
+    let mut buf = Vec::new();
+    let streamed = merger
+        .merge_readers_into_with_report(sources(), &mut buf)
+        .unwrap();
+    assert_eq!(streamed.parts(), parts);
+    assert_eq!(streamed.len(), report.len());
+}
+
+#[test]
+fn merge_report_paths() {
+    let mut file1 = NamedTempFile::new().unwrap();
+    let mut file2 = NamedTempFile::new().unwrap();
+    write!(&mut file1, " 11\n 12").unwrap();
+    write!(&mut file2, " 21\n 22\n").unwrap();
+    let paths: Vec<&Path> = vec![file1.path(), file2.path()];
+
+    let mut merger = FileMerger::new();
+    merger.force_ending_newline(Newline::Lf);
+    let mut buf = Vec::new();
+    let report = merger
+        .with_paths_with_report(paths.clone(), &mut buf)
+        .unwrap();
+    assert_eq!(report.len(), 16);
+    assert_eq!(report.parts()[0].path.as_deref(), Some(paths[0]));
+    assert_eq!(report.parts()[1].path.as_deref(), Some(paths[1]));
+    assert_eq!(report.parts()[1].output, 8..16);
+
+    let mut buf = Vec::new();
+    let files = paths
+        .iter()
+        .map(|p| std::fs::File::open(p).unwrap())
+        .collect();
+    let report = merger.with_files_with_report(files, &mut buf).unwrap();
+    assert_eq!(report.parts()[0].path, None);
+    assert_eq!(report.parts()[1].output, 8..16);
+}
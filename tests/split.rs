@@ -0,0 +1,145 @@
+use std::fs;
+use std::io::Cursor;
+
+use admerge::*;
+
+fn bundle(merger: &RsMerger, sources: &[&[u8]]) -> (Vec<u8>, Index) {
+    let mut buf = Vec::new();
+    let cursors = sources.iter().map(Cursor::new).collect();
+    let report = merger
+        .merge_sources_into_with_report(cursors, &mut buf)
+        .unwrap();
+    (buf, report.index().unwrap())
+}
+
+#[test]
+fn split_round_trips() {
+    let sources: &[&[u8]] = &[b"h\n 11\n 12\nf\n", b"h\n 21\nf", b"", b"h\n 31\r\nf\n"];
+    let mut merger = RsMerger::new();
+    merger.emit_index(IndexPlacement::Footer);
+    merger.pad_with(Pad::Custom(Some(b"<"), Some(b"--\n"), Some(b">")));
+    merger.force_ending_newline(Newline::Lf);
+    merger.align_parts(8).fill_byte(b'.');
+
+    let (buf, index) = bundle(&merger, sources);
+    let splitter = Splitter::from_footer(&mut Cursor::new(&buf)).unwrap();
+    assert_eq!(splitter.index(), &index);
+    let parts = splitter
+        .split_with(&mut Cursor::new(&buf), |_| Ok(Vec::new()))
+        .unwrap();
+    assert_eq!(parts, sources);
+    splitter.verify(&mut Cursor::new(&buf)).unwrap();
+
+    // Skipped contents are not part of the index, and so are not recovered.
+    merger.emit_index(IndexPlacement::Sidecar);
+    merger.skip_head(Skip::LinesOnce(1));
+    merger.skip_tail(Skip::LinesOnce(1));
+    let (buf, index) = bundle(&merger, sources);
+    let splitter = Splitter::new(index);
+    let parts = splitter
+        .split_with(&mut Cursor::new(&buf), |_| Ok(Vec::new()))
+        .unwrap();
+    assert_eq!(parts, [&b"h\n 11\n 12\n"[..], b" 21\n", b"", b" 31\r\nf\n"]);
+}
+
+#[test]
+fn split_to_dir() {
+    let src = tempfile::tempdir().unwrap();
+    let mut paths = Vec::new();
+    for (name, contents) in [("a.txt", "first\n"), ("b.txt", "second")] {
+        let path = src.path().join(name);
+        fs::write(&path, contents).unwrap();
+        paths.push(path);
+    }
+    let mut merger = FileMerger::new();
+    merger.emit_index(IndexPlacement::Footer);
+    merger.force_ending_newline(Newline::Lf);
+    let mut buf = Vec::new();
+    merger.with_paths(paths, &mut buf).unwrap();
+
+    let mut merged = Cursor::new(buf);
+    let splitter = Splitter::from_footer(&mut merged).unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let written = splitter.split_to_dir(&mut merged, out.path()).unwrap();
+    assert_eq!(
+        written,
+        [out.path().join("a.txt"), out.path().join("b.txt")]
+    );
+    assert_eq!(fs::read(&written[0]).unwrap(), b"first\n");
+    assert_eq!(fs::read(&written[1]).unwrap(), b"second");
+
+    // Existing files are not overwritten.
+    let result = splitter.split_to_dir(&mut merged, out.path());
+    assert!(matches!(result, Err(ErrorKind::Io(_))), "{:?}", result);
+    assert_eq!(fs::read(&written[0]).unwrap(), b"first\n");
+
+    // Entries named after their index.
+    let mut merger = RsMerger::new();
+    merger.emit_index(IndexPlacement::Sidecar);
+    let (buf, index) = bundle(&merger, &[b"x", b"y"]);
+    let out = tempfile::tempdir().unwrap();
+    let written = Splitter::new(index)
+        .split_to_dir(&mut Cursor::new(buf), out.path())
+        .unwrap();
+    assert_eq!(written, [out.path().join("0"), out.path().join("1")]);
+
+    // Entries whose names end alike are told apart by their index.
+    let mut paths = Vec::new();
+    for (dir, contents) in [("a", "1\n"), ("b", "2\n")] {
+        fs::create_dir(src.path().join(dir)).unwrap();
+        let path = src.path().join(dir).join("data.txt");
+        fs::write(&path, contents).unwrap();
+        paths.push(path);
+    }
+    paths.push(src.path().join("a.txt"));
+    let mut merger = FileMerger::new();
+    merger.emit_index(IndexPlacement::Footer);
+    let mut buf = Vec::new();
+    merger.with_paths(paths, &mut buf).unwrap();
+
+    let mut merged = Cursor::new(buf);
+    let splitter = Splitter::from_footer(&mut merged).unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let written = splitter.split_to_dir(&mut merged, out.path()).unwrap();
+    assert_eq!(
+        written,
+        [
+            out.path().join("data.0.txt"),
+            out.path().join("data.1.txt"),
+            out.path().join("a.txt")
+        ]
+    );
+    assert_eq!(fs::read(&written[1]).unwrap(), b"2\n");
+}
+
+#[test]
+fn split_detects_damage() {
+    let mut merger = RsMerger::new();
+    merger.emit_index(IndexPlacement::Sidecar);
+    let (mut buf, index) = bundle(&merger, &[b"first\n", b"second\n"]);
+    let splitter = Splitter::new(index);
+
+    buf[8] ^= 1;
+    let result = splitter.verify(&mut Cursor::new(&buf));
+    assert!(matches!(result, Err(ErrorKind::ChecksumMismatch(1))));
+    let mut part = Vec::new();
+    let copied = splitter
+        .split_part(0, &mut Cursor::new(&buf), &mut part)
+        .unwrap();
+    assert_eq!((copied, &part[..]), (6, &b"first\n"[..]));
+
+    buf[8] ^= 1;
+    buf.truncate(10);
+    let result = splitter.verify(&mut Cursor::new(&buf));
+    assert!(matches!(result, Err(ErrorKind::Truncated(1))));
+
+    // Parts are written as they are read, even if found not to match.
+    let mut part = Vec::new();
+    let result = splitter.split_part(1, &mut Cursor::new(&buf), &mut part);
+    assert!(matches!(result, Err(ErrorKind::Truncated(1))));
+    assert_eq!(part, b"seco");
+
+    // A footer that is no longer there.
+    let result = Splitter::from_footer(&mut Cursor::new(&buf));
+    assert!(matches!(result, Err(ErrorKind::InvalidIndex(_))));
+}
@@ -0,0 +1,189 @@
+use std::fs;
+use std::io::Cursor;
+
+use admerge::*;
+
+fn split(splitter: &RsSplitter, source: &[u8]) -> Result<Vec<Vec<u8>>> {
+    splitter.split_source_with(&mut Cursor::new(source), |_| Ok(Vec::new()))
+}
+
+fn strs(parts: &[Vec<u8>]) -> Vec<&str> {
+    parts
+        .iter()
+        .map(|part| std::str::from_utf8(part).unwrap())
+        .collect()
+}
+
+#[test]
+fn split_by_lines() {
+    let source = b"id,name\n1,foo\n2,bar\n3,baz\n4,qux";
+    let mut splitter = RsSplitter::new();
+    splitter.split_by(SplitBy::Lines(2));
+    let parts = split(&splitter, source).unwrap();
+    assert_eq!(
+        strs(&parts),
+        ["id,name\n1,foo\n", "2,bar\n3,baz\n", "4,qux"]
+    );
+
+    // Parts merge back into the source, with the repeated header kept only once.
+    splitter.repeat_head(Skip::LinesOnce(1));
+    let parts = split(&splitter, source).unwrap();
+    assert_eq!(
+        strs(&parts),
+        ["id,name\n1,foo\n2,bar\n", "id,name\n3,baz\n4,qux"]
+    );
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::LinesOnce(1));
+    let mut merged = Vec::new();
+    let sources = parts.iter().map(Cursor::new).collect();
+    merger.merge_sources_into(sources, &mut merged).unwrap();
+    assert_eq!(merged, source);
+
+    // Zero lines never cut.
+    splitter.split_by(SplitBy::Lines(0));
+    let parts = split(&splitter, source).unwrap();
+    assert_eq!(parts.len(), 1);
+
+    // Lines ended by other terminators, including a `\r\n` split across reads.
+    let mut source = vec![b'x'; 8 * 1024 - 1];
+    source.extend_from_slice(b"\r\ny\rz\n");
+    let mut splitter = RsSplitter::new();
+    splitter.split_by(SplitBy::Lines(1));
+    splitter.line_terminator(Terminator::Any);
+    let parts = split(&splitter, &source).unwrap();
+    let lens: Vec<_> = parts.iter().map(Vec::len).collect();
+    assert_eq!(lens, [8 * 1024 + 1, 2, 2]);
+}
+
+#[test]
+fn split_by_bytes_and_delimiters() {
+    let mut splitter = RsSplitter::new();
+    splitter.split_by(SplitBy::Bytes(3));
+    let parts = split(&splitter, b"abcdefg").unwrap();
+    assert_eq!(strs(&parts), ["abc", "def", "g"]);
+    let parts = split(&splitter, b"abcdef").unwrap();
+    assert_eq!(strs(&parts), ["abc", "def"]);
+
+    splitter.split_by(SplitBy::Delimiter(b"\0\0"));
+    let parts = split(&splitter, b"a\0\0\0b\0\0").unwrap();
+    assert_eq!(parts, [&b"a\0\0"[..], b"\0b\0\0"]);
+
+    // Nothing to split.
+    assert!(split(&splitter, b"").unwrap().is_empty());
+    splitter.repeat_head(Skip::Bytes(2));
+    assert!(split(&splitter, b"ab").unwrap().is_empty());
+}
+
+#[test]
+#[cfg(feature = "regex")]
+fn split_before_matches() {
+    let regex = Regex::new("^## ").unwrap();
+    let mut splitter = RsSplitter::new();
+    splitter.split_by(SplitBy::BeforeMatch(&regex));
+
+    let parts = split(&splitter, b"## a\n1\n## b\r\n2\n## c").unwrap();
+    assert_eq!(strs(&parts), ["## a\n1\n", "## b\r\n2\n", "## c"]);
+    let parts = split(&splitter, b"intro\n## a\n").unwrap();
+    assert_eq!(strs(&parts), ["intro\n", "## a\n"]);
+
+    // Lines are tested without their terminators.
+    let regex = Regex::new("^end$").unwrap();
+    splitter.split_by(SplitBy::BeforeMatch(&regex));
+    splitter.line_terminator(Terminator::Crlf);
+    let parts = split(&splitter, b"a\r\nend\r\nb").unwrap();
+    assert_eq!(strs(&parts), ["a\r\n", "end\r\nb"]);
+
+    // Lines are bounded by the buffer limit.
+    splitter.buffer_limit(5);
+    assert_eq!(split(&splitter, b"a\r\nend\r\n").unwrap().len(), 2);
+    assert!(matches!(
+        split(&splitter, b"abcd\r\n"),
+        Err(ErrorKind::BufferExceeded(5))
+    ));
+}
+
+#[test]
+fn split_respects_buffer_limit() {
+    let source = b"head\n1\n2\ntail\n";
+    let mut splitter = RsSplitter::new();
+    splitter.split_by(SplitBy::Lines(1));
+    splitter.repeat_head(Skip::Lines(1));
+    splitter.repeat_tail(Skip::Lines(1));
+    splitter.buffer_limit(5);
+    assert_eq!(
+        strs(&split(&splitter, source).unwrap()),
+        ["head\n1\ntail\n", "head\n2\ntail\n"]
+    );
+
+    // The repeated head and tail are held in memory.
+    splitter.buffer_limit(4);
+    assert!(matches!(
+        split(&splitter, source),
+        Err(ErrorKind::BufferExceeded(4))
+    ));
+}
+
+#[test]
+fn split_with_tails_and_paddings() {
+    let source = b"<doc>\na\nb\nc\n</doc>\n";
+    let mut splitter = RsSplitter::new();
+    splitter.split_by(SplitBy::Lines(2));
+    splitter.repeat_head(Skip::Until(b"<doc>\n"));
+    splitter.repeat_tail(Skip::Lines(1));
+    splitter.force_ending_newline(Newline::Lf);
+    splitter.pad_with(Pad::Custom(Some(b"#\n"), Some(b"...\n"), Some(b"$\n")));
+    let parts = split(&splitter, source).unwrap();
+    assert_eq!(
+        strs(&parts),
+        [
+            "#\n<doc>\na\nb\n</doc>\n...\n$\n",
+            "#\n<doc>\nc\n</doc>\n$\n"
+        ]
+    );
+
+    // The head and tail may not overlap.
+    splitter.repeat_head(Skip::Bytes(10));
+    splitter.repeat_tail(Skip::Bytes(10));
+    assert!(matches!(
+        split(&splitter, source),
+        Err(ErrorKind::InvalidSkip)
+    ));
+
+    // Ending newlines follow the source or the delimiter.
+    let mut splitter = RsSplitter::new();
+    splitter.split_by(SplitBy::Bytes(3));
+    splitter.force_ending_newline(NewlineStyle::Auto);
+    let parts = split(&splitter, b"a\r\nb").unwrap();
+    assert_eq!(strs(&parts), ["a\r\n", "b\r\n"]);
+    splitter.split_by(SplitBy::Bytes(2));
+    splitter.line_terminator(Terminator::Delimiter(b";"));
+    let parts = split(&splitter, b"a;bc").unwrap();
+    assert_eq!(strs(&parts), ["a;", "bc;"]);
+}
+
+#[test]
+fn split_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.csv");
+    fs::write(&path, "id\n1\n2\n3\n").unwrap();
+
+    let mut splitter = FileSplitter::new();
+    splitter.split_by(SplitBy::Lines(2));
+    splitter.repeat_head(Skip::LinesOnce(1));
+    let out = tempfile::tempdir().unwrap();
+    let paths = splitter.with_path(&path, out.path()).unwrap();
+    assert_eq!(
+        paths,
+        [out.path().join("data.0.csv"), out.path().join("data.1.csv")]
+    );
+    assert_eq!(fs::read(&paths[0]).unwrap(), b"id\n1\n2\n");
+    assert_eq!(fs::read(&paths[1]).unwrap(), b"id\n3\n");
+
+    // Existing files are not overwritten.
+    let result = splitter.with_path(&path, out.path());
+    assert!(matches!(result, Err(ErrorKind::Io(_))), "{:?}", result);
+    assert_eq!(fs::read(&paths[0]).unwrap(), b"id\n1\n2\n");
+
+    let result = splitter.with_path(dir.path(), out.path());
+    assert!(matches!(result, Err(ErrorKind::InvalidPath(0))));
+}
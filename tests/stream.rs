@@ -0,0 +1,167 @@
+use std::io::{Cursor, Read};
+
+use admerge::*;
+
+// A reader that implements `Read` only and hands out at most `step` bytes per call.
+struct Trickle<'a> {
+    data: &'a [u8],
+    step: usize,
+}
+
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.step.min(buf.len()).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+// Generates pseudo-random sources made of a few distinct bytes, some starting with a whole or
+// partial byte order mark.
+fn sources(seed: u64) -> Vec<Vec<u8>> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+    let alphabet = b"ab\nxx";
+    (0..1 + next() % 4)
+        .map(|_| {
+            let bom = &b"\xef\xbb\xbf"[..(next() % 6).saturating_sub(2)];
+            let body = (0..next() % 24).map(|_| alphabet[next() % alphabet.len()]);
+            bom.iter().copied().chain(body).collect()
+        })
+        .collect()
+}
+
+fn skips() -> Vec<Skip<'static>> {
+    vec![
+        Skip::Bytes(0),
+        Skip::Bytes(3),
+        Skip::BytesOnce(2),
+        Skip::Lines(0),
+        Skip::Lines(1),
+        Skip::Lines(2),
+        Skip::LinesOnce(1),
+        Skip::LinesOnce(3),
+        Skip::Repeats(b""),
+        Skip::Repeats(b"a"),
+        Skip::Repeats(b"ab"),
+        Skip::Repeats(b"xx"),
+        Skip::Until(b""),
+        Skip::Until(b"a"),
+        Skip::Until(b"ab"),
+        Skip::Until(b"xxa"),
+        Skip::Before(b"b"),
+        Skip::Before(b"ab"),
+        Skip::Before(b"\nx"),
+        Skip::Chain(&[]),
+        Skip::Chain(&[Skip::Bytes(1), Skip::Lines(1)]),
+        Skip::Chain(&[Skip::Repeats(b"a"), Skip::Until(b"b"), Skip::Repeats(b"\n")]),
+        Skip::Chain(&[Skip::LinesOnce(1), Skip::Before(b"\nx"), Skip::Bytes(2)]),
+        Skip::Chain(&[Skip::Chain(&[Skip::Until(b"x")]), Skip::Lines(2)]),
+        Skip::Bom,
+        Skip::BomOnce,
+        Skip::Chain(&[Skip::Bom, Skip::Lines(1)]),
+    ]
+}
+
+#[test]
+fn merge_readers_matches_merge_sources() {
+    let mut merger = RsMerger::new();
+    for seed in 0..40 {
+        let sources = sources(seed);
+        for head in skips() {
+            for tail in skips() {
+                merger.skip_head(head.clone());
+                merger.skip_tail(tail.clone());
+                merger.force_ending_newline(Newline::Crlf);
+                merger.pad_with(Pad::Custom(Some(b"<"), Some(b"|"), Some(b">")));
+
+                let mut expected = Vec::new();
+                let cursors = sources.iter().map(Cursor::new).collect();
+                let expected = merger
+                    .merge_sources_into_with_report(cursors, &mut expected)
+                    .map(|report| (report.parts().to_vec(), expected));
+
+                let mut buf = Vec::new();
+                let readers = sources.iter().map(|s| Trickle { data: s, step: 1 + seed as usize % 5 }).collect();
+                let actual = merger
+                    .merge_readers_into_with_report(readers, &mut buf)
+                    .map(|report| (report.parts().to_vec(), buf));
+
+                match (expected, actual) {
+                    (Ok(expected), Ok(actual)) => assert_eq!(
+                        expected, actual,
+                        "sources {:?}, head {:?}, tail {:?}",
+                        sources, head, tail
+                    ),
+                    (Err(ErrorKind::InvalidSkip), Err(ErrorKind::InvalidSkip)) => (),
+                    (expected, actual) => panic!(
+                        "sources {:?}, head {:?}, tail {:?}: {:?} != {:?}",
+                        sources, head, tail, expected, actual
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn merge_readers_basics() {
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::LinesOnce(1));
+    merger.skip_tail(Skip::Lines(1));
+    merger.force_ending_newline(Newline::Lf);
+
+    let r1: &[u8] = b"header\n 11\n 12\nfooter\n";
+    let r2: &[u8] = b"header\n 21\n 22\nfooter\n";
+    let mut buf = Vec::new();
+    assert!(merger.merge_readers_into(vec![r1, r2], &mut buf).is_ok());
+    assert_eq!(&buf, b"header\n 11\n 12\n 21\n 22\n");
+
+    let mut buf = Vec::new();
+    match merger.merge_readers_into(Vec::<&[u8]>::new(), &mut buf) {
+        Err(e) => assert!(matches!(e, ErrorKind::NothingPassed)),
+        Ok(_) => panic!(),
+    }
+}
+
+#[test]
+fn merge_readers_throws_if_buffer_exceeded() {
+    let data = vec![b'x'; 64 * 1024];
+    let mut merger = RsMerger::new();
+    merger.skip_tail(Skip::Until(b"footer"));
+    merger.buffer_limit(1024);
+
+    let mut buf = Vec::new();
+    match merger.merge_readers_into(vec![&data[..]], &mut buf) {
+        Err(e) => assert!(matches!(e, ErrorKind::BufferExceeded(1024))),
+        Ok(_) => panic!(),
+    }
+
+    // Bounded windows fit in the buffer regardless of the source length.
+    merger.skip_tail(Skip::Bytes(16));
+    let mut buf = Vec::new();
+    assert!(merger.merge_readers_into(vec![&data[..]], &mut buf).is_ok());
+    assert_eq!(buf.len(), data.len() - 16);
+}
+
+#[test]
+#[cfg(feature = "tempfile")]
+fn merge_readers_spills_to_disk() {
+    let mut data = vec![b'x'; 64 * 1024];
+    data.extend_from_slice(b"footer");
+    data.extend_from_slice(&vec![b'y'; 64 * 1024]);
+    let mut merger = RsMerger::new();
+    merger.skip_head(Skip::Bytes(1));
+    merger.skip_tail(Skip::Until(b"footer"));
+    merger.buffer_limit(1024).spill_to_disk(true);
+
+    let mut buf = Vec::new();
+    let readers = vec![Trickle { data: &data, step: 1000 }, Trickle { data: &data, step: 777 }];
+    assert!(merger.merge_readers_into(readers, &mut buf).is_ok());
+    assert_eq!(buf.len(), 2 * (64 * 1024 - 1));
+    assert!(buf.iter().all(|&b| b == b'x'));
+}
@@ -0,0 +1,235 @@
+use std::io::{Cursor, Read};
+
+use admerge::*;
+
+mod common;
+
+fn merge(merger: &RsMerger, sources: &[&[u8]]) -> Result<Vec<u8>> {
+    common::merge(merger, sources, &[ErrorKind::InvalidSkip])
+}
+
+// A reader that implements `Read` only and hands out at most `step` bytes per call.
+struct Trickle<'a> {
+    data: &'a [u8],
+    step: usize,
+}
+
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.step.min(buf.len()).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+// Generates pseudo-random sources made of line terminators and a few other bytes.
+fn sources(seed: u64) -> Vec<Vec<u8>> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+    let alphabet = b"a\r\n\r\nb\0\0";
+    (0..1 + next() % 4)
+        .map(|_| {
+            (0..next() % 16)
+                .map(|_| alphabet[next() % alphabet.len()])
+                .collect()
+        })
+        .collect()
+}
+
+fn skips() -> Vec<Skip<'static>> {
+    vec![
+        Skip::Lines(0),
+        Skip::Lines(1),
+        Skip::Lines(2),
+        Skip::LinesOnce(1),
+        Skip::LinesOnce(3),
+        Skip::Bytes(1),
+        Skip::Chain(&[Skip::Bytes(1), Skip::Lines(1)]),
+        Skip::Chain(&[Skip::Lines(1), Skip::Bytes(1), Skip::LinesOnce(1)]),
+    ]
+}
+
+#[test]
+fn merge_readers_matches_merge_sources() {
+    let terminators = [
+        Terminator::Lf,
+        Terminator::Crlf,
+        Terminator::Cr,
+        Terminator::Any,
+        Terminator::Delimiter(b"\0"),
+        Terminator::Delimiter(b"\0\0"),
+        Terminator::Delimiter(b"\r\n\r"),
+        Terminator::Delimiter(b""),
+    ];
+    let mut merger = RsMerger::new();
+    merger.force_ending_newline(Newline::Lf);
+    for seed in 0..40 {
+        let sources = sources(seed);
+        for &terminator in terminators.iter() {
+            for head in skips() {
+                for tail in skips() {
+                    merger.line_terminator(terminator);
+                    merger.skip_head(head.clone());
+                    merger.skip_tail(tail.clone());
+
+                    let mut expected = Vec::new();
+                    let cursors = sources.iter().map(Cursor::new).collect();
+                    let expected = merger
+                        .merge_sources_into_with_report(cursors, &mut expected)
+                        .map(|report| (report.parts().to_vec(), expected));
+
+                    let mut buf = Vec::new();
+                    let readers = sources
+                        .iter()
+                        .map(|s| Trickle {
+                            data: s,
+                            step: 1 + seed as usize % 3,
+                        })
+                        .collect();
+                    let actual = merger
+                        .merge_readers_into_with_report(readers, &mut buf)
+                        .map(|report| (report.parts().to_vec(), buf));
+
+                    match (expected, actual) {
+                        (Ok(expected), Ok(actual)) => assert_eq!(
+                            expected, actual,
+                            "sources {:?}, {:?}, head {:?}, tail {:?}",
+                            sources, terminator, head, tail
+                        ),
+                        (Err(ErrorKind::InvalidSkip), Err(ErrorKind::InvalidSkip)) => (),
+                        (expected, actual) => panic!(
+                            "sources {:?}, {:?}, head {:?}, tail {:?}: {:?} != {:?}",
+                            sources, terminator, head, tail, expected, actual
+                        ),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn skip_lines_ending_with_cr() {
+    let mut merger = RsMerger::new();
+    merger.line_terminator(Terminator::Cr);
+    merger.skip_head(Skip::Lines(1));
+
+    let sources: &[&[u8]] = &[b"header\r 1\r 2\r", b"header\r 3"];
+    assert_eq!(merge(&merger, sources).unwrap(), b" 1\r 2\r 3");
+
+    merger.skip_head(Skip::LinesOnce(1));
+    merger.skip_tail(Skip::LinesOnce(1));
+    let sources: &[&[u8]] = &[b"h\r 1\rf\r", b"h\r 2\rf"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"h\r 1\r 2\rf");
+}
+
+#[test]
+fn skip_lines_ending_with_crlf() {
+    let mut merger = RsMerger::new();
+    merger.line_terminator(Terminator::Crlf);
+    merger.skip_head(Skip::Lines(1));
+    merger.skip_tail(Skip::Lines(1));
+
+    // A lone `\n` or `\r` does not end a line.
+    let sources: &[&[u8]] = &[b"a\nb\r\n 1\r\nc\rd\r\n", b"h\r\n 2\r\nf"];
+    assert_eq!(merge(&merger, sources).unwrap(), b" 1\r\n 2\r\n");
+
+    let sources: &[&[u8]] = &[b"a\nb\n"];
+    merger.skip_tail(Skip::Lines(0));
+    assert_eq!(merge(&merger, sources).unwrap(), b"");
+
+    merger.skip_head(Skip::Lines(2));
+    assert!(matches!(
+        merge(&merger, sources),
+        Err(ErrorKind::InvalidSkip)
+    ));
+}
+
+#[test]
+fn skip_lines_ending_with_any_terminator() {
+    let mut merger = RsMerger::new();
+    merger.line_terminator(Terminator::Any);
+    merger.skip_head(Skip::Lines(2));
+    merger.skip_tail(Skip::Lines(1));
+
+    let sources: &[&[u8]] = &[b"a\r\nb\r 1\nc\r", b"a\rb\n 2\r\nc", b"a\n\r 3\r\r"];
+    assert_eq!(merge(&merger, sources).unwrap(), b" 1\n 2\r\n 3\r");
+}
+
+#[test]
+fn force_ending_newline_after_any_terminator() {
+    let mut merger = RsMerger::new();
+    merger.force_ending_newline(Newline::Lf);
+
+    let sources: &[&[u8]] = &[b"1\r", b"2\r\n", b"3"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"1\r\n2\r\n3\n");
+
+    merger.line_terminator(Terminator::Cr);
+    assert_eq!(merge(&merger, sources).unwrap(), b"1\r2\r\n\n3\n");
+
+    merger.line_terminator(Terminator::Crlf);
+    assert_eq!(merge(&merger, sources).unwrap(), b"1\r\n2\r\n3\n");
+
+    merger.line_terminator(Terminator::Any);
+    assert_eq!(merge(&merger, sources).unwrap(), b"1\r2\r\n3\n");
+}
+
+#[test]
+fn skip_records_ending_with_delimiter() {
+    let mut merger = RsMerger::new();
+    merger.line_terminator(Terminator::Delimiter(b"\0"));
+    merger.skip_head(Skip::Lines(1));
+    merger.skip_tail(Skip::Lines(1));
+
+    // Newlines do not end a record.
+    let sources: &[&[u8]] = &[b"h\n1\0 1\n\0f", b"h\0 2\0f\0"];
+    assert_eq!(merge(&merger, sources).unwrap(), b" 1\n\0 2\0");
+
+    // The ASCII record separator, with records that span several lines.
+    merger.line_terminator(Terminator::Delimiter(b"\x1e"));
+    merger.skip_head(Skip::LinesOnce(1));
+    merger.skip_tail(Skip::LinesOnce(1));
+    let sources: &[&[u8]] = &[b"h\x1ea\nb\x1ef\x1e", b"h\x1ec\x1ef"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"h\x1ea\nb\x1ec\x1ef");
+}
+
+#[test]
+fn skip_records_ending_with_overlapping_delimiter() {
+    let mut merger = RsMerger::new();
+    merger.line_terminator(Terminator::Delimiter(b"\0\0"));
+    merger.skip_head(Skip::Lines(1));
+
+    // Delimiters are matched from left to right.
+    let sources: &[&[u8]] = &[b"a\0\0\0b\0\0c"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"\0b\0\0c");
+
+    merger.skip_head(Skip::Lines(0));
+    merger.skip_tail(Skip::Lines(1));
+    assert_eq!(merge(&merger, sources).unwrap(), b"a\0\0\0b\0\0");
+}
+
+#[test]
+fn force_ending_delimiter() {
+    let mut merger = RsMerger::new();
+    merger.line_terminator(Terminator::Delimiter(b"\0"));
+    merger.force_ending_newline(Newline::Lf);
+
+    let sources: &[&[u8]] = &[b"1\n", b"2\0", b"3"];
+    assert_eq!(merge(&merger, sources).unwrap(), b"1\n\x002\x003\0");
+
+    let mut cursors: Vec<_> = sources.iter().map(Cursor::new).collect();
+    let plan = merger.plan(&mut cursors).unwrap();
+    assert_eq!(plan.parts()[0].newline, Some(&b"\0"[..]));
+    assert_eq!(plan.parts()[1].newline, None);
+    assert_eq!(plan.len(), 7);
+
+    // An empty delimiter ends no record, so the given newline is appended instead.
+    merger.line_terminator(Terminator::Delimiter(b""));
+    assert_eq!(merge(&merger, sources).unwrap(), b"1\n\n2\0\n3\n");
+}